@@ -28,7 +28,7 @@ fn main() {
     }
     // Check if libsystemd is available via pkg-config
     let output = Command::new("pkg-config")
-        .args(&["--exists", "libsystemd"])
+        .args(["--exists", "libsystemd"])
         .output();
     
     match output {
@@ -38,7 +38,7 @@ fn main() {
             
             // Get the library paths
             if let Ok(libs_output) = Command::new("pkg-config")
-                .args(&["--libs", "libsystemd"])
+                .args(["--libs", "libsystemd"])
                 .output()
             {
                 if let Ok(libs_str) = String::from_utf8(libs_output.stdout) {
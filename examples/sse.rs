@@ -2,7 +2,7 @@ use std::fs;
 use std::sync::Arc;
 use std::collections::HashMap;
 
-use journald_query::{TailConfig, JournalTail};
+use journald_query::{Entry, TailConfig, JournalTail};
 use poem::{
     get, handler,
     listener::TcpListener,
@@ -12,7 +12,7 @@ use poem::{
     },
     Route, Server,
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use tokio::time::Duration;
 use tokio::sync::{broadcast, RwLock};
 
@@ -22,26 +22,6 @@ struct LogsQuery {
     service: String,
 }
 
-/// Serializable wrapper for journal entries (for SSE/JSON output)
-#[derive(Serialize, Clone)]
-struct SerializableEntry {
-    hostname: Option<String>,
-    unit: Option<String>,
-    timestamp_utc: u64,
-    message: String,
-}
-
-impl From<journald_query::Entry> for SerializableEntry {
-    fn from(entry: journald_query::Entry) -> Self {
-        Self {
-            hostname: entry.hostname,
-            unit: entry.unit,
-            timestamp_utc: entry.timestamp_utc,
-            message: entry.message,
-        }
-    }
-}
-
 /// Key for identifying unique journal streams
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 struct StreamKey {
@@ -51,7 +31,7 @@ struct StreamKey {
 
 /// Shared journal reader that multiplexes to multiple connections
 struct JournalMultiplexer {
-    streams: Arc<RwLock<HashMap<StreamKey, broadcast::Sender<SerializableEntry>>>>,
+    streams: Arc<RwLock<HashMap<StreamKey, broadcast::Sender<Entry>>>>,
     machine_id: String,
 }
 
@@ -68,7 +48,7 @@ impl JournalMultiplexer {
     }
     
     /// Get or create a broadcast channel for a specific hostname/service combination
-    async fn get_or_create_stream(&self, key: StreamKey) -> broadcast::Receiver<SerializableEntry> {
+    async fn get_or_create_stream(&self, key: StreamKey) -> broadcast::Receiver<Entry> {
         let mut streams = self.streams.write().await;
         
         if let Some(sender) = streams.get(&key) {
@@ -100,10 +80,8 @@ impl JournalMultiplexer {
             for entry_result in tail.iter() {
                 match entry_result {
                     Ok(entry) => {
-                        let serializable = SerializableEntry::from(entry);
-                        
                         // Send to all subscribers (non-blocking)
-                        if tx.send(serializable).is_err() {
+                        if tx.send(entry).is_err() {
                             // No more subscribers, clean up
                             break;
                         }
@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+use std::ops::ControlFlow;
+use std::time::Duration;
+use crate::control::ScanControl;
+use crate::error::{JournalError, Result};
+use crate::query::{field_value, matches_text_filters, scan_journal, Direction, Query};
+use crate::source::JournalSource;
+
+/// Describes how matching entries are counted
+///
+/// Fields:
+/// - `group_by`: Fields whose values split the counts (e.g. `_SYSTEMD_UNIT`, `PRIORITY`,
+///   `_HOSTNAME`, `_COMM`). Empty means a single group.
+/// - `bucket`: Optional histogram bucket width. Without it, counts cover the whole time range.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Aggregation {
+    pub group_by: Vec<String>,
+    pub bucket: Option<Duration>,
+}
+
+/// One row of an aggregation result
+///
+/// Fields:
+/// - `group`: Values of the `group_by` fields, in the same order. `None` when the
+///   entry does not carry the field.
+/// - `bucket_start_utc`: Start of the time bucket in microseconds since Unix epoch (UTC),
+///   or `None` when the aggregation has no bucket width.
+/// - `count`: Number of matching entries in this group and bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AggregateCount {
+    pub group: Vec<Option<String>>,
+    pub bucket_start_utc: Option<u64>,
+    pub count: u64,
+}
+
+impl Aggregation {
+    /// Create an aggregation that counts all matching entries
+    pub fn new() -> Self {
+        Aggregation::default()
+    }
+
+    /// Group counts by the values of a field
+    ///
+    /// Calling this more than once groups by every field given, in order.
+    pub fn group_by<S: Into<String>>(mut self, field: S) -> Self {
+        self.group_by.push(field.into());
+        self
+    }
+
+    /// Split counts into time buckets of the given width
+    pub fn bucket(mut self, width: Duration) -> Self {
+        self.bucket = Some(width);
+        self
+    }
+}
+
+/// Count journal entries matching a query, grouped by fields and time buckets
+///
+/// The query's filters and time range apply as in [`crate::query_journal`], but
+/// no `Entry` values are built: only the grouped fields are read from each
+/// entry. The query's `projection`, `limit` and `reverse` are ignored.
+///
+/// # Returns
+/// Counts ordered by bucket and then by group, or `JournalError::InvalidArgument`
+/// if the bucket width is shorter than a microsecond
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{aggregate, Aggregation, Query};
+/// use std::path::Path;
+/// use std::time::Duration;
+///
+/// // Errors per minute per unit over the last hour
+/// let query = Query::all().since("-1h")?.priority(3);
+/// let aggregation = Aggregation::new()
+///     .group_by("_SYSTEMD_UNIT")
+///     .bucket(Duration::from_secs(60));
+///
+/// for row in aggregate(Path::new("/var/log/journal"), query, &aggregation)? {
+///     println!("{:?} {:?}: {}", row.bucket_start_utc, row.group, row.count);
+/// }
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+pub fn aggregate<S: JournalSource + ?Sized>(
+    source: &S,
+    mut query: Query,
+    aggregation: &Aggregation,
+) -> Result<Vec<AggregateCount>> {
+    let width = match aggregation.bucket {
+        Some(width) if width.as_micros() == 0 => return Err(JournalError::InvalidArgument),
+        Some(width) => Some(u64::try_from(width.as_micros()).unwrap_or(u64::MAX)),
+        None => None,
+    };
+
+    // An indexed source answers from the index, or only reads the files
+    // that can hold matches
+    if let Some(index) = source.index() {
+        if let Some(counts) = index.counts(source, &query, aggregation)? {
+            return Ok(counts);
+        }
+        if let Some(files) = index.files_for(source, &mut query)? {
+            return aggregate(&files, query, aggregation);
+        }
+    }
+
+    let mut counts: BTreeMap<(Option<u64>, Vec<Option<String>>), u64> = BTreeMap::new();
+
+    scan_journal(source, &query, Direction::Forward, &mut ScanControl::new(), |journal, grep, position| {
+        if !matches_text_filters(journal, &query, grep)? {
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        let group = aggregation
+            .group_by
+            .iter()
+            .map(|field| field_value(journal, field))
+            .collect::<Result<Vec<_>>>()?;
+        let bucket = width.map(|width| bucket_start(position.timestamp, width));
+
+        *counts.entry((bucket, group)).or_insert(0) += 1;
+        Ok(ControlFlow::Continue(()))
+    })?;
+
+    Ok(counts
+        .into_iter()
+        .map(|((bucket_start_utc, group), count)| AggregateCount {
+            group,
+            bucket_start_utc,
+            count,
+        })
+        .collect())
+}
+
+/// Round a timestamp down to the start of its bucket
+pub(crate) fn bucket_start(timestamp: u64, width: u64) -> u64 {
+    timestamp - timestamp % width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_bucket_start() {
+        let minute = 60_000_000;
+        assert_eq!(bucket_start(1640995200000000, minute), 1640995200000000);
+        assert_eq!(bucket_start(1640995259999999, minute), 1640995200000000);
+        assert_eq!(bucket_start(1640995260000000, minute), 1640995260000000);
+    }
+
+    #[test]
+    fn test_aggregation_builder() {
+        let aggregation = Aggregation::new()
+            .group_by("_SYSTEMD_UNIT")
+            .group_by("PRIORITY")
+            .bucket(Duration::from_secs(60));
+        assert_eq!(aggregation.group_by, vec!["_SYSTEMD_UNIT", "PRIORITY"]);
+        assert_eq!(aggregation.bucket, Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_zero_bucket_rejected() {
+        let aggregation = Aggregation::new().bucket(Duration::ZERO);
+        let result = aggregate(Path::new("/nonexistent"), Query::all(), &aggregation);
+        assert_eq!(result, Err(JournalError::InvalidArgument));
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use crate::error::Result;
+use crate::journal::Journal;
+
+/// Hostnames that refer to the same host
+///
+/// Maps aliases, such as a fully qualified name and a short name, to one
+/// canonical hostname. With `strip_domains`, a name without an explicit
+/// alias is shortened to its first label, so `web1.example.com` and `web1`
+/// are the same host. IP addresses are never shortened.
+///
+/// Used by discovery to name and merge hosts, and by [`crate::Query`] so a
+/// hostname filter selects entries logged under any of the host's names.
+///
+/// # Examples
+/// ```
+/// use journald_query::HostAliases;
+///
+/// let aliases = HostAliases::new()
+///     .alias("db-old", "db1")
+///     .strip_domains(true);
+///
+/// assert_eq!(aliases.canonical("db-old"), "db1");
+/// assert_eq!(aliases.canonical("web1.example.com"), "web1");
+/// assert!(aliases.same_host("web1", "web1.prod.example.com"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostAliases {
+    aliases: BTreeMap<String, String>,
+    strip_domains: bool,
+}
+
+impl HostAliases {
+    /// Create an alias map where every hostname stands for itself
+    pub fn new() -> Self {
+        HostAliases::default()
+    }
+
+    /// Treat `alias` as another name for `canonical`
+    pub fn alias<A: Into<String>, C: Into<String>>(mut self, alias: A, canonical: C) -> Self {
+        self.aliases.insert(alias.into(), canonical.into());
+        self
+    }
+
+    /// Shorten hostnames without an explicit alias to their first label
+    pub fn strip_domains(mut self, enabled: bool) -> Self {
+        self.strip_domains = enabled;
+        self
+    }
+
+    /// Get the canonical name of a hostname
+    ///
+    /// An explicit alias wins. Otherwise, with `strip_domains`, the short name
+    /// is looked up in turn, so aliases can be given for short names only.
+    pub fn canonical<'a>(&'a self, hostname: &'a str) -> &'a str {
+        if let Some(canonical) = self.aliases.get(hostname) {
+            return canonical;
+        }
+        if !self.strip_domains || hostname.parse::<IpAddr>().is_ok() {
+            return hostname;
+        }
+
+        let short = hostname.split('.').next().unwrap_or(hostname);
+        self.aliases.get(short).map_or(short, String::as_str)
+    }
+
+    /// Check whether two hostnames name the same host
+    pub fn same_host(&self, a: &str, b: &str) -> bool {
+        self.canonical(a) == self.canonical(b)
+    }
+}
+
+/// The `_HOSTNAME` values in the journal that name the same host as `hostname`
+///
+/// Includes `hostname` itself, so a filter built from the result selects
+/// nothing rather than everything when the journal has no such host.
+pub(crate) fn hostnames_of(journal: &Journal, aliases: &HostAliases, hostname: &str) -> Result<Vec<String>> {
+    let mut names: Vec<String> = journal
+        .get_unique_values("_HOSTNAME")?
+        .into_iter()
+        .filter_map(|value| value.strip_prefix("_HOSTNAME=").map(|name| name.to_string()))
+        .filter(|name| aliases.same_host(name, hostname))
+        .collect();
+    if !names.iter().any(|name| name == hostname) {
+        names.push(hostname.to_string());
+    }
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_aliases() {
+        let aliases = HostAliases::new().alias("web1.example.com", "web1");
+        assert_eq!(aliases.canonical("web1.example.com"), "web1");
+        assert_eq!(aliases.canonical("web1.other.com"), "web1.other.com");
+        assert_eq!(aliases.canonical("web1"), "web1");
+    }
+
+    #[test]
+    fn test_strip_domains() {
+        let aliases = HostAliases::new().alias("legacy", "db1").strip_domains(true);
+        assert_eq!(aliases.canonical("web1.example.com"), "web1");
+        assert_eq!(aliases.canonical("legacy.example.com"), "db1");
+        assert_eq!(aliases.canonical("10.0.0.12"), "10.0.0.12");
+        assert_eq!(aliases.canonical("localhost"), "localhost");
+        assert!(!aliases.same_host("web1", "web2.example.com"));
+    }
+}
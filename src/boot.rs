@@ -0,0 +1,179 @@
+use crate::journal::Journal;
+use crate::error::{JournalError, Result};
+
+/// A single boot recorded in the journal
+///
+/// Fields:
+/// - `offset`: Position relative to the most recent boot (`0` is the latest, `-1` the one before).
+/// - `boot_id`: The `_BOOT_ID` of the boot.
+/// - `first_realtime_usec`: Timestamp of the first entry of the boot, in microseconds since Unix epoch (UTC).
+/// - `last_realtime_usec`: Timestamp of the last entry of the boot, in microseconds since Unix epoch (UTC).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Boot {
+    pub offset: i64,
+    pub boot_id: String,
+    pub first_realtime_usec: u64,
+    pub last_realtime_usec: u64,
+}
+
+/// Selects a boot the way `journalctl -b` does
+///
+/// - `Offset(n)` with `n <= 0`: relative to the most recent boot (`0` is the latest, `-1` the previous).
+/// - `Offset(n)` with `n > 0`: counted from the oldest boot (`1` is the first one in the journal).
+/// - `Id(..)`: a specific `_BOOT_ID`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum BootSelector {
+    Offset(i64),
+    Id(String),
+}
+
+impl BootSelector {
+    /// Find the selected boot in a list returned by [`list_boots`]
+    pub fn resolve<'a>(&self, boots: &'a [Boot]) -> Option<&'a Boot> {
+        match self {
+            BootSelector::Id(id) => boots.iter().find(|boot| &boot.boot_id == id),
+            BootSelector::Offset(offset) if *offset > 0 => {
+                boots.get(usize::try_from(*offset - 1).ok()?)
+            }
+            BootSelector::Offset(offset) => boots.iter().find(|boot| boot.offset == *offset),
+        }
+    }
+
+    /// Resolve this selector to a boot ID against the journal
+    ///
+    /// `Id` selectors are returned as-is without touching the journal.
+    pub(crate) fn boot_id(&self, journal: &Journal) -> Result<String> {
+        match self {
+            BootSelector::Id(id) => Ok(id.clone()),
+            BootSelector::Offset(_) => {
+                let boots = list_boots(journal)?;
+                self.resolve(&boots)
+                    .map(|boot| boot.boot_id.clone())
+                    .ok_or(JournalError::NotFound)
+            }
+        }
+    }
+}
+
+/// List the boots recorded in the journal, oldest first
+///
+/// This is the equivalent of `journalctl --list-boots`. Any matches set on
+/// the journal are flushed.
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{list_boots, Journal};
+///
+/// let journal = Journal::open_directory("/var/log/journal")?;
+/// for boot in list_boots(&journal)? {
+///     println!("{:>3} {} {}..{}", boot.offset, boot.boot_id,
+///         boot.first_realtime_usec, boot.last_realtime_usec);
+/// }
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+pub fn list_boots(journal: &Journal) -> Result<Vec<Boot>> {
+    let boot_ids: Vec<String> = journal
+        .get_unique_values("_BOOT_ID")?
+        .into_iter()
+        .filter_map(|value| value.strip_prefix("_BOOT_ID=").map(|s| s.to_string()))
+        .collect();
+
+    let mut boots = Vec::new();
+    for boot_id in boot_ids {
+        journal.flush_matches();
+        journal.add_match("_BOOT_ID", &boot_id)?;
+
+        journal.seek_head()?;
+        if !journal.next()? {
+            continue;
+        }
+        let first_realtime_usec = journal.get_realtime_usec()?;
+
+        journal.seek_tail()?;
+        if !journal.previous()? {
+            continue;
+        }
+        let last_realtime_usec = journal.get_realtime_usec()?;
+
+        boots.push(Boot {
+            offset: 0,
+            boot_id,
+            first_realtime_usec,
+            last_realtime_usec,
+        });
+    }
+    journal.flush_matches();
+
+    boots.sort_by(|a, b| {
+        a.first_realtime_usec
+            .cmp(&b.first_realtime_usec)
+            .then_with(|| a.boot_id.cmp(&b.boot_id))
+    });
+    assign_offsets(&mut boots);
+
+    Ok(boots)
+}
+
+/// Number boots relative to the most recent one, which gets offset 0
+fn assign_offsets(boots: &mut [Boot]) {
+    let latest = boots.len() as i64 - 1;
+    for (index, boot) in boots.iter_mut().enumerate() {
+        boot.offset = index as i64 - latest;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boots() -> Vec<Boot> {
+        let mut boots: Vec<Boot> = ["aaaa", "bbbb", "cccc"]
+            .iter()
+            .enumerate()
+            .map(|(i, id)| Boot {
+                offset: 0,
+                boot_id: id.to_string(),
+                first_realtime_usec: 1_000 * i as u64,
+                last_realtime_usec: 1_000 * i as u64 + 500,
+            })
+            .collect();
+        assign_offsets(&mut boots);
+        boots
+    }
+
+    #[test]
+    fn test_offsets_relative_to_latest() {
+        let offsets: Vec<i64> = boots().iter().map(|boot| boot.offset).collect();
+        assert_eq!(offsets, vec![-2, -1, 0]);
+    }
+
+    #[test]
+    fn test_resolve_relative_offsets() {
+        let boots = boots();
+        assert_eq!(BootSelector::Offset(0).resolve(&boots).unwrap().boot_id, "cccc");
+        assert_eq!(BootSelector::Offset(-1).resolve(&boots).unwrap().boot_id, "bbbb");
+        assert!(BootSelector::Offset(-3).resolve(&boots).is_none());
+    }
+
+    #[test]
+    fn test_resolve_absolute_offsets() {
+        let boots = boots();
+        assert_eq!(BootSelector::Offset(1).resolve(&boots).unwrap().boot_id, "aaaa");
+        assert_eq!(BootSelector::Offset(3).resolve(&boots).unwrap().boot_id, "cccc");
+        assert!(BootSelector::Offset(4).resolve(&boots).is_none());
+    }
+
+    #[test]
+    fn test_resolve_by_id() {
+        let boots = boots();
+        let selector = BootSelector::Id("bbbb".to_string());
+        assert_eq!(selector.resolve(&boots).unwrap().offset, -1);
+        assert!(BootSelector::Id("zzzz".to_string()).resolve(&boots).is_none());
+    }
+
+    #[test]
+    fn test_resolve_empty() {
+        assert!(BootSelector::Offset(0).resolve(&[]).is_none());
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+use crate::aggregate::{aggregate, AggregateCount, Aggregation};
+use crate::boot::BootSelector;
+use crate::error::Result;
+use crate::query::{query_journal, receive_range, Entry, Query, TimestampSource};
+use crate::source::JournalSource;
+
+/// A cache of query and aggregation results
+///
+/// Results are keyed by the `Query` and the state of the source's journal
+/// files: their paths, sizes and modification times. Any change to the files
+/// makes the next identical query read the journal again, except for queries
+/// whose time range ends before the newest entry in the journal and that
+/// ask for no context. Entries written later fall outside such a range, so
+/// these results are reused as long as the same files are present, even
+/// while the active file grows.
+///
+/// This assumes entries are written in receive-time order, which does not
+/// hold for journals that reach `systemd-journal-remote` late.
+///
+/// The cache can be shared between threads. When it holds `capacity` results,
+/// the least recently used one is dropped to make room.
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{Query, QueryCache};
+/// use std::path::Path;
+///
+/// let cache = QueryCache::new(100);
+/// let query = Query::new(1640995200000000, 1640998800000000).unit("nginx.service");
+///
+/// let entries = cache.query_journal(Path::new("/var/log/journal"), query.clone())?;
+/// // Served from the cache
+/// let again = cache.query_journal(Path::new("/var/log/journal"), query)?;
+/// assert_eq!(cache.stats().hits, 1);
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+#[derive(Debug)]
+pub struct QueryCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+/// Counters describing how a [`QueryCache`] has been used
+///
+/// Fields:
+/// - `hits`: Queries answered from the cache.
+/// - `misses`: Queries that read the journal.
+/// - `len`: Results currently held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub len: usize,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    results: HashMap<CacheKey, Cached>,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Debug)]
+struct Cached {
+    result: CachedResult,
+    last_used: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum CachedResult {
+    Entries(Vec<Entry>),
+    Counts(Vec<AggregateCount>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    files: Vec<FileState>,
+    query: Query,
+    aggregation: Option<Aggregation>,
+}
+
+/// A journal file as seen when a result was cached
+///
+/// `size` and `modified` are left out for queries whose range has closed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FileState {
+    path: PathBuf,
+    size: Option<u64>,
+    modified: Option<SystemTime>,
+}
+
+impl QueryCache {
+    /// Create a cache holding up to `capacity` results
+    ///
+    /// A capacity of zero disables caching.
+    pub fn new(capacity: usize) -> Self {
+        QueryCache {
+            capacity,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Query journal entries, reusing an earlier result if it is still valid
+    ///
+    /// Returns the same entries as [`crate::query_journal`].
+    pub fn query_journal<S: JournalSource + ?Sized>(&self, source: &S, query: Query) -> Result<Vec<Entry>> {
+        let result = self.cached(source, query, None, |query| {
+            query_journal(source, query).map(CachedResult::Entries)
+        })?;
+        match result {
+            CachedResult::Entries(entries) => Ok(entries),
+            CachedResult::Counts(_) => unreachable!("entries are cached without an aggregation"),
+        }
+    }
+
+    /// Count journal entries, reusing an earlier result if it is still valid
+    ///
+    /// Returns the same counts as [`crate::aggregate`].
+    pub fn aggregate<S: JournalSource + ?Sized>(
+        &self,
+        source: &S,
+        query: Query,
+        aggregation: &Aggregation,
+    ) -> Result<Vec<AggregateCount>> {
+        let result = self.cached(source, query, Some(aggregation), |query| {
+            aggregate(source, query, aggregation).map(CachedResult::Counts)
+        })?;
+        match result {
+            CachedResult::Counts(counts) => Ok(counts),
+            CachedResult::Entries(_) => unreachable!("counts are cached with an aggregation"),
+        }
+    }
+
+    /// How the cache has been used so far
+    pub fn stats(&self) -> CacheStats {
+        let state = self.lock();
+        CacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            len: state.results.len(),
+        }
+    }
+
+    /// Drop all cached results
+    pub fn clear(&self) {
+        self.lock().results.clear();
+    }
+
+    fn cached<S, F>(&self, source: &S, query: Query, aggregation: Option<&Aggregation>, run: F) -> Result<CachedResult>
+    where
+        S: JournalSource + ?Sized,
+        F: FnOnce(Query) -> Result<CachedResult>,
+    {
+        if self.capacity == 0 {
+            return run(query);
+        }
+
+        let key = CacheKey {
+            files: file_states(source, &query)?,
+            query: query.clone(),
+            aggregation: aggregation.cloned(),
+        };
+        if let Some(result) = self.lock().get(&key) {
+            return Ok(result);
+        }
+
+        // The lock is not held while the journal is read, so identical
+        // queries running at the same time may each read it
+        let result = run(query)?;
+        self.lock().insert(key, result.clone(), self.capacity);
+        Ok(result)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CacheState> {
+        // The state stays consistent even if a thread panicked holding the lock
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl CacheState {
+    fn get(&mut self, key: &CacheKey) -> Option<CachedResult> {
+        self.clock += 1;
+        match self.results.get_mut(key) {
+            Some(cached) => {
+                cached.last_used = self.clock;
+                self.hits += 1;
+                Some(cached.result.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: CacheKey, result: CachedResult, capacity: usize) {
+        if !self.results.contains_key(&key) && self.results.len() >= capacity {
+            let oldest = self.results
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.results.remove(&oldest);
+            }
+        }
+
+        self.clock += 1;
+        self.results.insert(key, Cached {
+            result,
+            last_used: self.clock,
+        });
+    }
+}
+
+/// The state of the source's journal files that decides whether a result for
+/// `query` is still valid
+fn file_states<S: JournalSource + ?Sized>(source: &S, query: &Query) -> Result<Vec<FileState>> {
+    let closed = is_closed(source, query)?;
+    let mut files: Vec<_> = source
+        .journal_files()?
+        .into_iter()
+        .map(|path| {
+            let metadata = fs::metadata(&path).ok().filter(|_| !closed);
+            FileState {
+                size: metadata.as_ref().map(|metadata| metadata.len()),
+                modified: metadata.and_then(|metadata| metadata.modified().ok()),
+                path,
+            }
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Whether entries written from now on cannot change the query's result:
+/// its range ends before the newest entry, it selects boots by ID, and it
+/// asks for no context, which is read past the end of the range
+fn is_closed<S: JournalSource + ?Sized>(source: &S, query: &Query) -> Result<bool> {
+    let fixed_boot = matches!(query.boot, None | Some(BootSelector::Id(_)));
+    if query.context.is_some()
+        || !fixed_boot
+        || query.timestamp_source != TimestampSource::Receive
+        || query.end_time_utc == u64::MAX
+    {
+        return Ok(false);
+    }
+    // Boot windows are scanned by monotonic time, not receive time
+    let Some((_, end)) = receive_range(query) else {
+        return Ok(false);
+    };
+
+    let journal = source.open_journal()?;
+    journal.seek_tail()?;
+    if !journal.previous()? {
+        return Ok(false);
+    }
+    Ok(end < journal.get_realtime_usec()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(start: u64) -> CacheKey {
+        CacheKey {
+            files: vec![FileState {
+                path: PathBuf::from("/var/log/journal/system.journal"),
+                size: Some(8388608),
+                modified: None,
+            }],
+            query: Query::new(start, start + 1),
+            aggregation: None,
+        }
+    }
+
+    #[test]
+    fn test_hits_and_misses() {
+        let mut state = CacheState::default();
+        assert_eq!(state.get(&key(1)), None);
+        state.insert(key(1), CachedResult::Counts(Vec::new()), 2);
+        assert_eq!(state.get(&key(1)), Some(CachedResult::Counts(Vec::new())));
+        assert_eq!((state.hits, state.misses), (1, 1));
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let mut state = CacheState::default();
+        state.insert(key(1), CachedResult::Counts(Vec::new()), 2);
+        state.insert(key(2), CachedResult::Counts(Vec::new()), 2);
+        state.get(&key(1));
+        state.insert(key(3), CachedResult::Counts(Vec::new()), 2);
+
+        assert_eq!(state.results.len(), 2);
+        assert!(state.results.contains_key(&key(1)));
+        assert!(!state.results.contains_key(&key(2)));
+        assert!(state.results.contains_key(&key(3)));
+    }
+
+    #[test]
+    fn test_file_changes_change_the_key() {
+        let mut grown = key(1);
+        grown.files[0].size = Some(16777216);
+        assert_ne!(grown, key(1));
+    }
+
+    #[test]
+    fn test_open_ranges_are_not_closed() {
+        let source = std::path::Path::new("/nonexistent/journal");
+        assert_eq!(is_closed(source, &Query::all()), Ok(false));
+        assert_eq!(is_closed(source, &Query::new(0, 1).boot(BootSelector::Offset(-1))), Ok(false));
+        assert_eq!(is_closed(source, &Query::new(0, 1).context(0, 3)), Ok(false));
+        assert_eq!(is_closed(source, &Query::new(0, 1).surrounding(std::time::Duration::from_secs(5))), Ok(false));
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use crate::alias::HostAliases;
+use crate::discover::{scan_after, unique_values, DiscoveryOptions, Host, HostnameSeen, Hosts, UnitStats};
+use crate::error::{JournalError, Result};
+use crate::index::{read_bytes, read_optional, read_u64, read_u8, write_bytes, write_optional, write_u64};
+use crate::journal::Journal;
+use crate::source::JournalSource;
+
+/// Marks a checkpoint file, with the version of its format
+const MAGIC: &[u8; 8] = b"JQCHKPT1";
+
+/// Discovered hosts saved with the journal position they were discovered up to
+///
+/// [`DiscoveryCheckpoint::update`] resumes discovery from the saved cursor,
+/// reading only entries written since, so a daemon does not have to discover
+/// the whole journal at every start. The first update reads every entry, as
+/// `DiscoveryStrategy::Scan` does.
+///
+/// Entries after the cursor are those received later. This assumes entries
+/// are written in receive-time order, which does not hold for journals that
+/// reach `systemd-journal-remote` late: their older entries are not read.
+///
+/// The checkpoint also records the source's journal files. When some of them
+/// are gone, as after `journalctl --vacuum-time`, hosts that no longer have
+/// entries in the journal are dropped. The units and statistics of the
+/// remaining hosts are kept as they were.
+///
+/// Pass the same options to every update: hosts already found keep the names
+/// the alias map gave them then. Changing `unit_stats` starts discovery over,
+/// and a `filter` is rejected with `JournalError::InvalidArgument`, since it
+/// could not be applied to the saved hosts.
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{DiscoveryCheckpoint, DiscoveryOptions};
+///
+/// let mut checkpoint = DiscoveryCheckpoint::open("/var/lib/myapp/discovery.checkpoint")?;
+/// let read = checkpoint.update("/var/log/journal", &DiscoveryOptions::new())?;
+/// checkpoint.save()?;
+///
+/// println!("{} new entries, {} hosts", read, checkpoint.hosts().len());
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveryCheckpoint {
+    path: PathBuf,
+    unit_stats: bool,
+    cursor: Option<String>,
+    files: Vec<PathBuf>,
+    hosts: Hosts,
+}
+
+impl DiscoveryCheckpoint {
+    /// Load the checkpoint stored at `path`, or start an empty one if there is none
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        match File::open(&path) {
+            Ok(file) => read_checkpoint(path, &mut BufReader::new(file)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(DiscoveryCheckpoint {
+                path,
+                unit_stats: false,
+                cursor: None,
+                files: Vec::new(),
+                hosts: Hosts::new(),
+            }),
+            Err(_) => Err(JournalError::IoError),
+        }
+    }
+
+    /// Discover the hosts and units in the entries written since the checkpoint
+    ///
+    /// Drops hosts whose entries were all in journal files that are gone.
+    ///
+    /// # Returns
+    /// The number of entries read
+    pub fn update<S: JournalSource + ?Sized>(&mut self, source: &S, options: &DiscoveryOptions) -> Result<u64> {
+        if options.filter.is_some() {
+            return Err(JournalError::InvalidArgument);
+        }
+        if options.unit_stats != self.unit_stats {
+            self.unit_stats = options.unit_stats;
+            self.cursor = None;
+            self.hosts = Hosts::new();
+        }
+        let aliases = options.host_aliases.clone().unwrap_or_default();
+
+        let files = source.journal_files()?;
+        let journal = source.open_journal()?;
+        let present: HashSet<&PathBuf> = files.iter().collect();
+        if self.files.iter().any(|path| !present.contains(path)) {
+            drop_vacuumed_hosts(&journal, &aliases, &mut self.hosts)?;
+        }
+
+        let hosts = std::mem::take(&mut self.hosts);
+        let (hosts, read, cursor) = scan_after(&journal, source.has_duplicates(), hosts, self.cursor.as_deref(), &aliases, self.unit_stats)?;
+        self.hosts = hosts;
+        self.cursor = cursor;
+        self.files = files;
+        Ok(read)
+    }
+
+    /// Write the checkpoint to its path
+    ///
+    /// The checkpoint is written to a temporary file first, so readers never
+    /// see a partly written checkpoint.
+    pub fn save(&self) -> Result<()> {
+        let mut partial = self.path.clone().into_os_string();
+        partial.push(".tmp");
+        let partial = PathBuf::from(partial);
+
+        let file = File::create(&partial).map_err(|_| JournalError::IoError)?;
+        let mut writer = BufWriter::new(file);
+        write_checkpoint(self, &mut writer)
+            .and_then(|_| writer.flush())
+            .map_err(|_| JournalError::IoError)?;
+        fs::rename(&partial, &self.path).map_err(|_| JournalError::IoError)
+    }
+
+    /// The hosts discovered up to the checkpoint, sorted by hostname
+    pub fn hosts(&self) -> &Hosts {
+        &self.hosts
+    }
+
+    /// The cursor of the last entry read, if any
+    pub fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
+    /// The journal files of the source at the last update
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(PathBuf::as_path)
+    }
+}
+
+/// Remove the hosts that no longer have entries in the journal
+///
+/// Hosts with a machine ID are looked up by it, others by hostname.
+fn drop_vacuumed_hosts(journal: &Journal, aliases: &HostAliases, hosts: &mut Hosts) -> Result<()> {
+    let machine_ids = unique_values(journal, "_MACHINE_ID")?;
+    let hostnames: HashSet<String> = unique_values(journal, "_HOSTNAME")?
+        .iter()
+        .map(|hostname| aliases.canonical(hostname).to_string())
+        .collect();
+
+    hosts.hosts.retain(|host| match &host.machine_id {
+        Some(machine_id) => machine_ids.contains(machine_id),
+        None => hostnames.contains(&host.hostname),
+    });
+    Ok(())
+}
+
+fn write_checkpoint<W: Write>(checkpoint: &DiscoveryCheckpoint, out: &mut W) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&[u8::from(checkpoint.unit_stats)])?;
+    write_optional(out, &checkpoint.cursor)?;
+    write_u64(out, checkpoint.files.len() as u64)?;
+    for path in &checkpoint.files {
+        write_bytes(out, path.as_os_str().as_bytes())?;
+    }
+
+    write_u64(out, checkpoint.hosts.len() as u64)?;
+    for host in &checkpoint.hosts.hosts {
+        write_bytes(out, host.hostname.as_bytes())?;
+        write_optional(out, &host.machine_id)?;
+        write_u64(out, host.hostnames.len() as u64)?;
+        for seen in &host.hostnames {
+            write_bytes(out, seen.hostname.as_bytes())?;
+            write_u64(out, seen.first_seen_utc)?;
+            write_u64(out, seen.last_seen_utc)?;
+        }
+        write_u64(out, host.units.len() as u64)?;
+        for unit in &host.units {
+            write_bytes(out, unit.as_bytes())?;
+        }
+
+        match &host.unit_stats {
+            Some(unit_stats) => {
+                out.write_all(&[1])?;
+                write_u64(out, unit_stats.len() as u64)?;
+                for stats in unit_stats {
+                    write_bytes(out, stats.unit.as_bytes())?;
+                    write_u64(out, stats.entries)?;
+                    write_u64(out, stats.first_seen_utc)?;
+                    write_u64(out, stats.last_seen_utc)?;
+                    for count in stats.priority_counts {
+                        write_u64(out, count)?;
+                    }
+                    write_optional(out, &stats.last_message)?;
+                }
+            }
+            None => out.write_all(&[0])?,
+        }
+    }
+    Ok(())
+}
+
+/// Read a checkpoint, reporting unreadable contents as `JournalError::InvalidCheckpoint`
+fn read_checkpoint<R: Read>(path: PathBuf, input: &mut R) -> Result<DiscoveryCheckpoint> {
+    // The field readers are shared with the index and report its error
+    read_contents(path, input).map_err(|error| match error {
+        JournalError::InvalidIndex(reason) => JournalError::InvalidCheckpoint(reason),
+        error => error,
+    })
+}
+
+fn read_contents<R: Read>(path: PathBuf, input: &mut R) -> Result<DiscoveryCheckpoint> {
+    let mut magic = [0; 8];
+    input.read_exact(&mut magic).map_err(|error| match error.kind() {
+        io::ErrorKind::UnexpectedEof => JournalError::InvalidIndex("truncated".to_string()),
+        _ => JournalError::IoError,
+    })?;
+    if &magic != MAGIC {
+        return Err(JournalError::InvalidIndex("not a checkpoint file, or an unsupported version".to_string()));
+    }
+
+    let unit_stats = read_u8(input)? != 0;
+    let cursor = read_optional(input)?;
+    let files = (0..read_u64(input)?)
+        .map(|_| Ok(PathBuf::from(std::ffi::OsStr::from_bytes(&read_bytes(input)?))))
+        .collect::<Result<_>>()?;
+
+    let mut hosts = Vec::new();
+    for _ in 0..read_u64(input)? {
+        let hostname = read_string(input)?;
+        let machine_id = read_optional(input)?;
+        let mut hostnames = Vec::new();
+        for _ in 0..read_u64(input)? {
+            hostnames.push(HostnameSeen {
+                hostname: read_string(input)?,
+                first_seen_utc: read_u64(input)?,
+                last_seen_utc: read_u64(input)?,
+            });
+        }
+        let units = (0..read_u64(input)?).map(|_| read_string(input)).collect::<Result<_>>()?;
+
+        let unit_stats = match read_u8(input)? {
+            0 => None,
+            _ => {
+                let mut unit_stats = Vec::new();
+                for _ in 0..read_u64(input)? {
+                    let unit = read_string(input)?;
+                    let entries = read_u64(input)?;
+                    let first_seen_utc = read_u64(input)?;
+                    let last_seen_utc = read_u64(input)?;
+                    let mut priority_counts = [0; 8];
+                    for count in &mut priority_counts {
+                        *count = read_u64(input)?;
+                    }
+                    unit_stats.push(UnitStats {
+                        unit,
+                        entries,
+                        first_seen_utc,
+                        last_seen_utc,
+                        priority_counts,
+                        last_message: read_optional(input)?,
+                    });
+                }
+                Some(unit_stats)
+            }
+        };
+
+        hosts.push(Host {
+            hostname,
+            machine_id,
+            hostnames,
+            units,
+            unit_stats,
+        });
+    }
+
+    Ok(DiscoveryCheckpoint {
+        path,
+        unit_stats,
+        cursor,
+        files,
+        hosts: Hosts { hosts },
+    })
+}
+
+fn read_string<R: Read>(input: &mut R) -> Result<String> {
+    String::from_utf8(read_bytes(input)?).map_err(|_| JournalError::InvalidIndex("value is not UTF-8".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_checkpoint() -> DiscoveryCheckpoint {
+        let mut stats = UnitStats {
+            unit: "nginx.service".to_string(),
+            entries: 12,
+            first_seen_utc: 1640995200000000,
+            last_seen_utc: 1640995290000000,
+            priority_counts: [0; 8],
+            last_message: Some("GET / 200".to_string()),
+        };
+        stats.priority_counts[6] = 12;
+
+        DiscoveryCheckpoint {
+            path: PathBuf::from("/var/lib/discovery.checkpoint"),
+            unit_stats: true,
+            cursor: Some("s=739ad463348b4ceca5a9e69c95a3c93f;i=4ece7;b=6c7c6013a8354d1b8d5d0b3a8b1c2c3d".to_string()),
+            files: vec![PathBuf::from("/var/log/journal/system.journal")],
+            hosts: Hosts {
+                hosts: vec![
+                    Host {
+                        hostname: "web-server".to_string(),
+                        machine_id: Some("aaaa".to_string()),
+                        hostnames: vec![HostnameSeen {
+                            hostname: "web-server".to_string(),
+                            first_seen_utc: 1640995200000000,
+                            last_seen_utc: 1640995290000000,
+                        }],
+                        units: vec!["nginx.service".to_string()],
+                        unit_stats: Some(vec![stats]),
+                    },
+                    Host {
+                        hostname: "db-server".to_string(),
+                        machine_id: None,
+                        hostnames: Vec::new(),
+                        units: Vec::new(),
+                        unit_stats: None,
+                    },
+                ],
+            },
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let checkpoint = sample_checkpoint();
+        let mut bytes = Vec::new();
+        write_checkpoint(&checkpoint, &mut bytes).unwrap();
+        let read = read_checkpoint(checkpoint.path.clone(), &mut bytes.as_slice()).unwrap();
+        assert_eq!(read, checkpoint);
+
+        assert!(matches!(
+            read_checkpoint(checkpoint.path.clone(), &mut &bytes[..bytes.len() - 1]),
+            Err(JournalError::InvalidCheckpoint(_))
+        ));
+        assert!(matches!(
+            read_checkpoint(checkpoint.path.clone(), &mut &b"JQINDEX1"[..]),
+            Err(JournalError::InvalidCheckpoint(_))
+        ));
+    }
+
+    #[test]
+    fn test_filters_are_rejected() {
+        let mut checkpoint = sample_checkpoint();
+        let options = DiscoveryOptions::new().filter(crate::Query::all());
+        assert_eq!(checkpoint.update("/nonexistent", &options), Err(JournalError::InvalidArgument));
+        assert_eq!(checkpoint, sample_checkpoint());
+    }
+}
@@ -0,0 +1,281 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::error::{JournalError, Result};
+
+/// Cancels a running query from another thread
+///
+/// Clones share the same state, so one clone can be handed to the query and
+/// another kept to cancel it, e.g. when an HTTP client disconnects.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a token that is not cancelled
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Cancel every query holding a clone of this token
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Check whether the token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Progress of a running query
+///
+/// Fields:
+/// - `entries_scanned`: Entries read so far, including those the filters dropped.
+///   For service discovery, the host and unit pairs checked so far.
+/// - `position_utc`: Receive time of the last entry read, in microseconds since
+///   Unix epoch (UTC). `None` for service discovery.
+/// - `fraction_done`: Estimated share of the work done, from 0.0 to 1.0.
+///   `None` when there is nothing to estimate from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub entries_scanned: u64,
+    pub position_utc: Option<u64>,
+    pub fraction_done: Option<f64>,
+}
+
+/// A callback receiving progress reports
+type ProgressCallback<'a> = Box<dyn FnMut(&Progress) + 'a>;
+
+/// Cancellation, deadline and progress reporting for a running query
+///
+/// A cancelled query fails with `JournalError::Cancelled`, and one that runs
+/// past its deadline with `JournalError::DeadlineExceeded`. Both are checked
+/// before each entry is read.
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{query_journal_with_control, CancellationToken, Query, ScanControl};
+/// use std::path::Path;
+/// use std::time::Duration;
+///
+/// let token = CancellationToken::new();
+/// let mut control = ScanControl::new()
+///     .cancellation(token.clone())
+///     .timeout(Duration::from_secs(30))
+///     .on_progress(|progress| {
+///         println!("{} entries, {:.0}%", progress.entries_scanned,
+///             progress.fraction_done.unwrap_or(0.0) * 100.0);
+///     });
+///
+/// // Call `token.cancel()` from another thread to stop the query
+/// let entries = query_journal_with_control(Path::new("/var/log/journal"), Query::all(), &mut control)?;
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+pub struct ScanControl<'a> {
+    cancellation: Option<CancellationToken>,
+    deadline: Option<Instant>,
+    progress: Option<ProgressCallback<'a>>,
+    progress_interval: u64,
+    current: Progress,
+}
+
+impl<'a> ScanControl<'a> {
+    /// Create a control that never stops the query and reports no progress
+    pub fn new() -> Self {
+        ScanControl {
+            cancellation: None,
+            deadline: None,
+            progress: None,
+            progress_interval: 1000,
+            current: Progress {
+                entries_scanned: 0,
+                position_utc: None,
+                fraction_done: None,
+            },
+        }
+    }
+
+    /// Stop the query when the token is cancelled
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Stop the query once the deadline has passed
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Stop the query once it has run for `timeout` from now
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.deadline(Instant::now() + timeout)
+    }
+
+    /// Report progress to `callback`
+    ///
+    /// The callback is called every `progress_interval` entries (1000 unless
+    /// set) and once more when the query completes.
+    pub fn on_progress<F: FnMut(&Progress) + 'a>(mut self, callback: F) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Set how many entries are read between progress reports
+    pub fn progress_interval(mut self, entries: u64) -> Self {
+        self.progress_interval = entries.max(1);
+        self
+    }
+
+    /// Fail if the query has been cancelled or is past its deadline
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled) {
+            return Err(JournalError::Cancelled);
+        }
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(JournalError::DeadlineExceeded);
+        }
+        Ok(())
+    }
+
+    /// Whether anyone is listening for progress, so estimates are worth making
+    pub(crate) fn wants_progress(&self) -> bool {
+        self.progress.is_some()
+    }
+
+    /// Count one unit of work, then check whether to stop
+    pub(crate) fn advance(&mut self, position_utc: Option<u64>, fraction_done: Option<f64>) -> Result<()> {
+        self.current.entries_scanned += 1;
+        self.current.position_utc = position_utc.or(self.current.position_utc);
+        self.current.fraction_done = fraction_done;
+
+        if self.current.entries_scanned.is_multiple_of(self.progress_interval) {
+            self.report();
+        }
+        self.check()
+    }
+
+    /// Deadline for controls handed to worker threads
+    pub(crate) fn deadline_at(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Record entries counted elsewhere, e.g. by worker threads, then check
+    /// whether to stop
+    pub(crate) fn update(&mut self, entries_scanned: u64) -> Result<()> {
+        let reported = self.current.entries_scanned / self.progress_interval;
+        self.current.entries_scanned = entries_scanned;
+        if entries_scanned / self.progress_interval > reported {
+            self.report();
+        }
+        self.check()
+    }
+
+    /// Report the final progress of a completed query
+    pub(crate) fn finish(&mut self) {
+        self.current.fraction_done = Some(1.0);
+        self.report();
+    }
+
+    fn report(&mut self) {
+        if let Some(progress) = &mut self.progress {
+            progress(&self.current);
+        }
+    }
+}
+
+impl Default for ScanControl<'_> {
+    fn default() -> Self {
+        ScanControl::new()
+    }
+}
+
+impl fmt::Debug for ScanControl<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScanControl")
+            .field("cancellation", &self.cancellation)
+            .field("deadline", &self.deadline)
+            .field("progress", &self.progress.is_some())
+            .field("progress_interval", &self.progress_interval)
+            .field("current", &self.current)
+            .finish()
+    }
+}
+
+/// Estimate how far `position` is through `start..=end`
+pub(crate) fn fraction_between(start: u64, end: u64, position: u64) -> Option<f64> {
+    if end <= start {
+        return None;
+    }
+    let done = position.clamp(start, end) - start;
+    Some(done as f64 / (end - start) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_cancellation_token_is_shared() {
+        let token = CancellationToken::new();
+        let control = ScanControl::new().cancellation(token.clone());
+        assert_eq!(control.check(), Ok(()));
+        token.cancel();
+        assert!(token.is_cancelled());
+        assert_eq!(control.check(), Err(JournalError::Cancelled));
+    }
+
+    #[test]
+    fn test_deadline() {
+        let control = ScanControl::new().deadline(Instant::now());
+        assert_eq!(control.check(), Err(JournalError::DeadlineExceeded));
+        let control = ScanControl::new().timeout(Duration::from_secs(3600));
+        assert_eq!(control.check(), Ok(()));
+    }
+
+    #[test]
+    fn test_progress_reports() {
+        let reports = RefCell::new(Vec::new());
+        let mut control = ScanControl::new()
+            .progress_interval(2)
+            .on_progress(|progress| reports.borrow_mut().push(*progress));
+
+        for position in 1..=5 {
+            control.advance(Some(position), Some(position as f64 / 10.0)).unwrap();
+        }
+        control.finish();
+        drop(control);
+
+        let reports = reports.into_inner();
+        assert_eq!(reports.iter().map(|p| p.entries_scanned).collect::<Vec<_>>(), vec![2, 4, 5]);
+        assert_eq!(reports[1].position_utc, Some(4));
+        assert_eq!(reports[1].fraction_done, Some(0.4));
+        assert_eq!(reports[2].fraction_done, Some(1.0));
+    }
+
+    #[test]
+    fn test_update_reports_counted_entries() {
+        let reports = RefCell::new(Vec::new());
+        let mut control = ScanControl::new()
+            .progress_interval(10)
+            .on_progress(|progress| reports.borrow_mut().push(progress.entries_scanned));
+
+        for scanned in [3, 9, 12, 15, 31] {
+            control.update(scanned).unwrap();
+        }
+        drop(control);
+        assert_eq!(reports.into_inner(), vec![12, 31]);
+    }
+
+    #[test]
+    fn test_fraction_between() {
+        assert_eq!(fraction_between(100, 200, 150), Some(0.5));
+        assert_eq!(fraction_between(100, 200, 50), Some(0.0));
+        assert_eq!(fraction_between(100, 200, 250), Some(1.0));
+        assert_eq!(fraction_between(100, 100, 100), None);
+    }
+}
@@ -1,19 +1,108 @@
+use crate::alias::HostAliases;
 use crate::journal::Journal;
-use crate::error::Result;
-use std::path::Path;
-use std::collections::HashSet;
+use crate::control::{fraction_between, ScanControl};
+use crate::error::{JournalError, Result};
+use crate::query::{field_value, matches_text_filters, scan_journal, Direction, Query};
+use crate::source::{JournalSource, SeenEntries};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::ControlFlow;
 
 /// Represents a single host and its associated systemd units
 /// 
 /// This struct contains information about a host that has logged entries
 /// to the systemd journal, along with all the systemd units that have
 /// logged entries from that host.
+///
+/// Hosts are told apart by machine ID, so a renamed host appears once with
+/// each of its hostnames, and two hosts that both call themselves
+/// "localhost" appear separately. Entries without a machine ID are told
+/// apart by hostname.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Host {
-    /// The hostname of the system
+    /// The hostname of the system: the one it logged under most recently,
+    /// normalized with [`DiscoveryOptions::host_aliases`]
     pub hostname: String,
+    /// The machine ID (`_MACHINE_ID`) of the system, if its entries carry one
+    pub machine_id: Option<String>,
+    /// Every hostname the system logged under, oldest first
+    ///
+    /// `DiscoveryStrategy::Probe` and `UniqueValues` take the times from the first
+    /// and last entries in journal order, which only differ from the earliest and
+    /// latest when entries were written out of time order.
+    pub hostnames: Vec<HostnameSeen>,
     /// List of systemd unit names that have logged entries from this host
     pub units: Vec<String>,
+    /// Statistics for each unit, in the same order as `units`, if they were
+    /// requested with [`DiscoveryOptions::unit_stats`]
+    pub unit_stats: Option<Vec<UnitStats>>,
+}
+
+/// What a unit has logged from a host
+///
+/// Fields:
+/// - `unit`: The systemd unit name.
+/// - `entries`: Number of entries the unit logged.
+/// - `first_seen_utc`: Receive time of its oldest entry, in microseconds since Unix epoch (UTC).
+/// - `last_seen_utc`: Receive time of its newest entry, in microseconds since Unix epoch (UTC).
+/// - `priority_counts`: Entries per priority, indexed by level (0 = emerg ... 7 = debug).
+///   Entries without a valid `PRIORITY` are not counted here.
+/// - `last_message`: `MESSAGE` of the newest entry, if it has one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnitStats {
+    pub unit: String,
+    pub entries: u64,
+    pub first_seen_utc: u64,
+    pub last_seen_utc: u64,
+    pub priority_counts: [u64; 8],
+    pub last_message: Option<String>,
+}
+
+impl UnitStats {
+    fn new(unit: String) -> Self {
+        UnitStats {
+            unit,
+            entries: 0,
+            first_seen_utc: u64::MAX,
+            last_seen_utc: 0,
+            priority_counts: [0; 8],
+            last_message: None,
+        }
+    }
+
+    /// Count the entries at `level` or more severe, e.g. `3` for errors and worse
+    pub fn at_or_above(&self, level: u8) -> u64 {
+        self.priority_counts.iter().take(usize::from(level) + 1).sum()
+    }
+}
+
+/// A hostname a host logged under
+///
+/// Fields:
+/// - `hostname`: The hostname, normalized with [`DiscoveryOptions::host_aliases`].
+/// - `first_seen_utc`: Receive time of the oldest entry under this hostname, in
+///   microseconds since Unix epoch (UTC).
+/// - `last_seen_utc`: Receive time of the newest entry under this hostname, in
+///   microseconds since Unix epoch (UTC).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostnameSeen {
+    pub hostname: String,
+    pub first_seen_utc: u64,
+    pub last_seen_utc: u64,
+}
+
+impl Host {
+    /// What tells this host apart from others
+    pub(crate) fn key(&self) -> HostKey {
+        HostKey::new(self.machine_id.clone(), &self.hostname)
+    }
+
+    /// Find the statistics for one of the host's units
+    pub fn stats(&self, unit: &str) -> Option<&UnitStats> {
+        self.unit_stats.as_ref()?.iter().find(|stats| stats.unit == unit)
+    }
 }
 
 /// Collection of hosts discovered from journal logs
@@ -21,7 +110,10 @@ pub struct Host {
 /// This struct represents the result of scanning journal logs to discover
 /// all hosts and their associated systemd units. It provides methods to
 /// access and iterate over the discovered services.
+///
+/// Serialized with a `schema_version` field, see [`crate::schema`].
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct Hosts {
     /// Vector of all discovered hosts and their units
     pub hosts: Vec<Host>,
@@ -45,7 +137,7 @@ impl Hosts {
         self.hosts.is_empty()
     }
 
-    /// Get all unique hostnames
+    /// Get the hostname of each host
     pub fn hostnames(&self) -> Vec<&String> {
         self.hosts.iter().map(|host| &host.hostname).collect()
     }
@@ -59,8 +151,89 @@ impl Hosts {
     }
 
     /// Find a host by hostname
+    ///
+    /// A host currently using the hostname is preferred over one that used it before.
     pub fn find_host(&self, hostname: &str) -> Option<&Host> {
-        self.hosts.iter().find(|host| host.hostname == hostname)
+        self.hosts.iter().find(|host| host.hostname == hostname).or_else(|| {
+            self.hosts
+                .iter()
+                .find(|host| host.hostnames.iter().any(|seen| seen.hostname == hostname))
+        })
+    }
+
+    /// Find a host by machine ID
+    pub fn find_machine(&self, machine_id: &str) -> Option<&Host> {
+        self.hosts.iter().find(|host| host.machine_id.as_deref() == Some(machine_id))
+    }
+}
+
+impl Default for Hosts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One value of a field found by [`discover_groups`], with the values of the
+/// next field found alongside it
+///
+/// Fields:
+/// - `field`: The field name, e.g. `_HOSTNAME`.
+/// - `value`: The field's value.
+/// - `entries`: Number of entries carrying this value under the parent groups.
+/// - `children`: Groups for the next field, sorted by value. Entries without the next
+///   field count towards `entries` but not towards any child.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FieldGroup {
+    pub field: String,
+    pub value: String,
+    pub entries: u64,
+    pub children: Vec<FieldGroup>,
+}
+
+impl FieldGroup {
+    /// Find the child group with the given value
+    pub fn child(&self, value: &str) -> Option<&FieldGroup> {
+        self.children.iter().find(|child| child.value == value)
+    }
+
+    /// Get the values of the child groups
+    pub fn child_values(&self) -> Vec<&String> {
+        self.children.iter().map(|child| &child.value).collect()
+    }
+}
+
+/// Entry counts for a level of the grouping tree while it is built
+#[derive(Debug, Default)]
+struct GroupNode {
+    entries: u64,
+    children: BTreeMap<String, GroupNode>,
+}
+
+impl GroupNode {
+    /// Count an entry along the path of its field values
+    fn record(&mut self, values: impl IntoIterator<Item = String>) {
+        let mut node = self;
+        for value in values {
+            node = node.children.entry(value).or_default();
+            node.entries += 1;
+        }
+    }
+
+    /// Turn the children into groups of `fields[0]`, their children into
+    /// groups of `fields[1]` and so on
+    fn into_groups(self, fields: &[&str]) -> Vec<FieldGroup> {
+        let Some((field, rest)) = fields.split_first() else {
+            return Vec::new();
+        };
+        self.children
+            .into_iter()
+            .map(|(value, node)| FieldGroup {
+                field: field.to_string(),
+                value,
+                entries: node.entries,
+                children: node.into_groups(rest),
+            })
+            .collect()
     }
 }
 
@@ -71,7 +244,7 @@ impl Hosts {
 /// by their host.
 /// 
 /// # Arguments
-/// * `journal_dir` - Directory containing journal files
+/// * `source` - Journal directory, or a [`crate::MultiJournal`] over several
 /// 
 /// # Returns
 /// A `Hosts` struct containing all discovered hosts and their units
@@ -88,74 +261,798 @@ impl Hosts {
 /// }
 /// # Ok::<(), journald_query::JournalError>(())
 /// ```
-pub fn discover_services<P: AsRef<Path>>(journal_dir: P) -> Result<Hosts> {
-    let journal = Journal::open_directory(journal_dir)?;
-    discover_services_from_journal(&journal)
+pub fn discover_services<S: JournalSource + ?Sized>(source: &S) -> Result<Hosts> {
+    discover_services_with_control(source, &mut ScanControl::new())
 }
 
-/// Ideally we could use sd_journal_enumerate_entries with a couple of filters
-/// to get the results, but according to the API docs:
-/// 
-/// "Note that these functions currently are not influenced by matches set with sd_journal_add_match() but 
-/// this might change in a later version of this software."
-/// 
-/// As such, we have to instead:
-/// - Query to get all the unique hostnames
-/// - Query to get all the unique units
-/// - Check for each hostname+unit combination if it exists in the journal
-/// 
-/// This is... not great, but the best one can reasonably do with the API.
-fn discover_services_from_journal(journal: &Journal) -> Result<Hosts> {
-    let hostname_values = journal.get_unique_values("_HOSTNAME")?;
-    let hostnames: HashSet<String> = hostname_values
-        .into_iter()
-        .filter_map(|value| value.strip_prefix("_HOSTNAME=").map(|s| s.to_string()))
-        .collect();
+/// Discover the hosts and units that logged entries matching `filter`
+///
+/// Only entries the query selects are considered: its time range, boot,
+/// priority, matches and text filters all apply, while `limit`, `reverse`,
+/// `context` and `projection` are ignored. Hosts and units with no entry
+/// in range are left out, so hosts decommissioned long ago do not appear
+/// in a discovery over the last day.
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{discover_services_matching, Query};
+///
+/// // Which units on which hosts logged errors in the last hour
+/// let filter = Query::all().since("-1h")?.priority(3);
+/// let services = discover_services_matching("/var/log/journal", filter)?;
+///
+/// // Entries that came in through syslog during the current boot
+/// let filter = Query::all()
+///     .boot(journald_query::BootSelector::Offset(0))
+///     .add_match("_TRANSPORT", "syslog");
+/// let services = discover_services_matching("/var/log/journal", filter)?;
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+pub fn discover_services_matching<S: JournalSource + ?Sized>(source: &S, filter: Query) -> Result<Hosts> {
+    let options = DiscoveryOptions::new().filter(filter);
+    discover_services_with_options(source, &options, &mut ScanControl::new())
+}
+
+/// Discover the values of nested fields, such as the syslog identifiers
+/// each host logged from
+///
+/// Each entry is counted under its value of `fields[0]`, then under its value
+/// of `fields[1]` within that group, and so on. An entry without one of the
+/// fields is not counted any deeper. This finds processes that are not systemd
+/// units, such as cron jobs, containers and user sessions, which `Hosts` does
+/// not show.
+///
+/// # Returns
+/// The groups of the first field, sorted by value, or `JournalError::InvalidArgument`
+/// if `fields` is empty
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::discover_groups;
+///
+/// // host → container
+/// for host in discover_groups("/var/log/journal", &["_HOSTNAME", "CONTAINER_NAME"])? {
+///     println!("{}: {:?}", host.value, host.child_values());
+/// }
+///
+/// // host → user → user unit
+/// let groups = discover_groups("/var/log/journal", &["_HOSTNAME", "_UID", "_SYSTEMD_USER_UNIT"])?;
+///
+/// // unit → invocation
+/// let groups = discover_groups("/var/log/journal", &["_SYSTEMD_UNIT", "_SYSTEMD_INVOCATION_ID"])?;
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+pub fn discover_groups<S: JournalSource + ?Sized>(source: &S, fields: &[&str]) -> Result<Vec<FieldGroup>> {
+    discover_groups_with_options(source, fields, &DiscoveryOptions::new(), &mut ScanControl::new())
+}
+
+/// Discover the values of nested fields with the given options
+///
+/// Like [`discover_groups`], but only the entries `options.filter` selects are
+/// counted, and `control` can stop the scan and receives its progress. Every
+/// selected entry is read, so `options.strategy` and `options.unit_stats` do not apply.
+pub fn discover_groups_with_options<S: JournalSource + ?Sized>(
+    source: &S,
+    fields: &[&str],
+    options: &DiscoveryOptions,
+    control: &mut ScanControl,
+) -> Result<Vec<FieldGroup>> {
+    if fields.is_empty() {
+        return Err(JournalError::InvalidArgument);
+    }
     
-    let unit_values = journal.get_unique_values("_SYSTEMD_UNIT")?;
-    let units: HashSet<String> = unit_values
-        .into_iter()
-        .filter_map(|value| value.strip_prefix("_SYSTEMD_UNIT=").map(|s| s.to_string()))
-        .collect();
+    let filter = options.filter.clone().unwrap_or_else(Query::all);
+    let mut root = GroupNode::default();
+    scan_journal(source, &filter, Direction::Forward, control, |journal, grep, _| {
+        if matches_text_filters(journal, &filter, grep)? {
+            let mut values = Vec::with_capacity(fields.len());
+            for field in fields {
+                match field_value(journal, field)? {
+                    Some(value) => values.push(value),
+                    None => break,
+                }
+            }
+            root.record(values);
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
     
-    let mut host_units: std::collections::HashMap<String, HashSet<String>> = std::collections::HashMap::new();
+    Ok(root.into_groups(fields))
+}
+
+/// How [`discover_services`] finds the units each host has logged from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DiscoveryStrategy {
+    /// Use `UniqueValues` where it works, otherwise `Scan` for journals
+    /// small enough to read faster than `Probe` would seek
+    #[default]
+    Auto,
+    /// Seek for each host and unit pair: hosts × units seeks, plus three
+    /// seeks for each hostname a host logged under. A hostname a machine used
+    /// only while it also logged under an earlier one is not found.
+    /// Falls back to `Scan` where a hostname is logged both with and without a machine ID.
+    Probe,
+    /// List the units and hostnames under a match for each host: one listing
+    /// per host, plus two seeks per hostname.
+    /// Falls back to `Scan` where libsystemd ignores matches when listing, or
+    /// where a hostname is logged both with and without a machine ID.
+    UniqueValues,
+    /// Read every entry once
+    Scan,
+}
+
+/// Options for [`discover_services_with_options`]
+///
+/// Fields:
+/// - `strategy`: How units are matched to hosts. Every strategy finds the same hosts and units.
+/// - `unit_stats`: Whether to collect [`UnitStats`] for each unit. This reads every entry,
+///   so it implies `DiscoveryStrategy::Scan`.
+/// - `filter`: Optional query selecting the entries considered, see
+///   [`discover_services_matching`]. The selected entries are read one by one, so
+///   `strategy` does not apply.
+/// - `host_aliases`: Optional alias map normalizing hostnames. Hosts without a machine ID
+///   whose hostnames are aliases of each other are merged.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct DiscoveryOptions {
+    pub strategy: DiscoveryStrategy,
+    pub unit_stats: bool,
+    pub filter: Option<Query>,
+    pub host_aliases: Option<HostAliases>,
+}
+
+impl DiscoveryOptions {
+    /// Create options for the default, automatically chosen strategy
+    pub fn new() -> Self {
+        DiscoveryOptions::default()
+    }
+
+    /// Set the discovery strategy
+    pub fn strategy(mut self, strategy: DiscoveryStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Collect entry counts, first and last seen times, priorities and the
+    /// most recent message for each unit
+    pub fn unit_stats(mut self, enabled: bool) -> Self {
+        self.unit_stats = enabled;
+        self
+    }
+
+    /// Only consider the entries `filter` selects
+    pub fn filter(mut self, filter: Query) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Normalize hostnames with an alias map
+    pub fn host_aliases(mut self, aliases: HostAliases) -> Self {
+        self.host_aliases = Some(aliases);
+        self
+    }
+}
+
+/// Journal bytes a scan can read in the time a probe takes, used by
+/// `DiscoveryStrategy::Auto` to pick between them
+const SCAN_BYTES_PER_PROBE: u64 = 64 * 1024;
+
+/// Discover services, with cancellation, a deadline and progress reporting
+///
+/// Like [`discover_services`], but stops with `JournalError::Cancelled` or
+/// `JournalError::DeadlineExceeded` as `control` directs. See [`ScanControl`].
+///
+/// [`ScanControl`]: crate::ScanControl
+pub fn discover_services_with_control<S: JournalSource + ?Sized>(source: &S, control: &mut ScanControl) -> Result<Hosts> {
+    discover_services_with_options(source, &DiscoveryOptions::new(), control)
+}
+
+/// Discover services with the given options
+///
+/// Progress counts the host and unit pairs checked with `Probe`, the hosts
+/// listed with `UniqueValues` and the entries read with `Scan`.
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{discover_services_with_options, DiscoveryOptions, DiscoveryStrategy, ScanControl};
+///
+/// let options = DiscoveryOptions::new().strategy(DiscoveryStrategy::Scan);
+/// let services = discover_services_with_options("/var/log/journal", &options, &mut ScanControl::new())?;
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+pub fn discover_services_with_options<S: JournalSource + ?Sized>(
+    source: &S,
+    options: &DiscoveryOptions,
+    control: &mut ScanControl,
+) -> Result<Hosts> {
+    control.check()?;
+    let aliases = options.host_aliases.clone().unwrap_or_default();
     
-    for hostname in &hostnames {
-        let mut units_for_host = HashSet::new();
-        // For each unit, check if the hostname+unit combination exists
-        for unit in &units {
-            journal.flush_matches();
-            journal.add_match("_HOSTNAME", hostname)?;
-            journal.add_match("_SYSTEMD_UNIT", unit)?;
-            journal.seek_head()?;
-            if journal.next()? {
-                units_for_host.insert(unit.clone());
+    // Filtered discovery reads the selected entries, which also reports
+    // the final progress
+    if let Some(filter) = &options.filter {
+        let found = hosts_by_filtered_scan(source, filter, &aliases, control, options.unit_stats)?;
+        return Ok(hosts_from(found, options.unit_stats));
+    }
+    
+    let journal = source.open_journal()?;
+    
+    let hostnames = unique_values(&journal, "_HOSTNAME")?;
+    let strategy = match options.strategy {
+        // Statistics need every entry read
+        _ if options.unit_stats => DiscoveryStrategy::Scan,
+        DiscoveryStrategy::Auto if unique_values_honor_matches(&journal, &hostnames)? => {
+            DiscoveryStrategy::UniqueValues
+        }
+        DiscoveryStrategy::Auto => {
+            let units = unique_values(&journal, "_SYSTEMD_UNIT")?;
+            let probes = (hostnames.len() * units.len()) as u64;
+            if journal.get_usage()? < probes.saturating_mul(SCAN_BYTES_PER_PROBE) {
+                DiscoveryStrategy::Scan
+            } else {
+                DiscoveryStrategy::Probe
             }
         }
-        
-        host_units.insert(hostname.clone(), units_for_host);
+        DiscoveryStrategy::UniqueValues if !unique_values_honor_matches(&journal, &hostnames)? => {
+            DiscoveryStrategy::Scan
+        }
+        strategy => strategy,
+    };
+    
+    // Listing and probing select each host's entries with matches, which
+    // cannot tell apart the hosts sharing a hostname with and without a machine ID
+    let selectors = match strategy {
+        DiscoveryStrategy::UniqueValues | DiscoveryStrategy::Probe => host_selectors(&journal, &hostnames, &aliases)?,
+        _ => None,
+    };
+    
+    let found = match (strategy, selectors) {
+        (DiscoveryStrategy::UniqueValues, Some(selectors)) => hosts_by_listing(&journal, selectors, &aliases, control)?,
+        (DiscoveryStrategy::Probe, Some(selectors)) => hosts_by_probing(&journal, selectors, &aliases, control)?,
+        _ => hosts_by_scanning(&journal, source.has_duplicates(), &aliases, control, options.unit_stats)?,
+    };
+    
+    control.finish();
+    Ok(hosts_from(found, options.unit_stats))
+}
+
+/// What tells hosts apart: the machine ID, or the canonical hostname for
+/// entries without one
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum HostKey {
+    Machine(String),
+    Hostname(String),
+}
+
+impl HostKey {
+    pub(crate) fn new(machine_id: Option<String>, hostname: &str) -> Self {
+        match machine_id {
+            Some(machine_id) => HostKey::Machine(machine_id),
+            None => HostKey::Hostname(hostname.to_string()),
+        }
     }
     
+    /// The field whose values select the host's entries
+    fn field(&self) -> &'static str {
+        match self {
+            HostKey::Machine(_) => "_MACHINE_ID",
+            HostKey::Hostname(_) => "_HOSTNAME",
+        }
+    }
+}
+
+/// What discovery found out about one host
+#[derive(Debug, Clone, Default)]
+struct Found {
+    /// Receive times of the first and last entries under each canonical hostname
+    hostnames: HashMap<String, (u64, u64)>,
+    units: HashSet<String>,
+    stats: HashMap<String, UnitStats>,
+}
+
+impl Found {
+    fn saw_hostname(&mut self, hostname: &str, first: u64, last: u64) {
+        let seen = self.hostnames.entry(hostname.to_string()).or_insert((first, last));
+        *seen = (seen.0.min(first), seen.1.max(last));
+    }
+}
+
+/// Build the sorted hosts from what was found, keeping the unit statistics
+/// if they were asked for
+///
+/// Machines that never logged a hostname are left out, as entries without
+/// `_HOSTNAME` are when scanning.
+fn hosts_from(found: HashMap<HostKey, Found>, keep_stats: bool) -> Hosts {
     let mut hosts = Vec::new();
-    for (hostname, units_set) in host_units {
-        let mut units: Vec<String> = units_set.into_iter().collect();
+    for (key, mut found) in found {
+        let mut hostnames: Vec<HostnameSeen> = found.hostnames
+            .into_iter()
+            .map(|(hostname, (first_seen_utc, last_seen_utc))| HostnameSeen {
+                hostname,
+                first_seen_utc,
+                last_seen_utc,
+            })
+            .collect();
+        hostnames.sort_by(|a, b| (a.first_seen_utc, &a.hostname).cmp(&(b.first_seen_utc, &b.hostname)));
+        
+        // A host goes by the name it logged under last
+        let current = hostnames
+            .iter()
+            .max_by(|a, b| (a.last_seen_utc, &a.hostname).cmp(&(b.last_seen_utc, &b.hostname)));
+        let Some(hostname) = current.map(|seen| seen.hostname.clone()) else {
+            continue;
+        };
+        
+        let mut units: Vec<String> = found.units.into_iter().chain(found.stats.keys().cloned()).collect();
         units.sort(); // Sort for consistent output
+        units.dedup();
+        
+        let unit_stats = keep_stats.then(|| units.iter().filter_map(|unit| found.stats.remove(unit)).collect());
+        let machine_id = match key {
+            HostKey::Machine(machine_id) => Some(machine_id),
+            HostKey::Hostname(_) => None,
+        };
         
         hosts.push(Host {
             hostname,
+            machine_id,
+            hostnames,
             units,
+            unit_stats,
         });
     }
     
     // Sort hosts by hostname for consistent output
-    hosts.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+    hosts.sort_by(|a, b| (&a.hostname, &a.machine_id).cmp(&(&b.hostname, &b.machine_id)));
+    Hosts { hosts }
+}
+
+/// The values of a field across the journal, ignoring matches
+pub(crate) fn unique_values(journal: &Journal, field: &str) -> Result<HashSet<String>> {
+    let prefix = format!("{}=", field);
+    Ok(journal
+        .get_unique_values(field)?
+        .into_iter()
+        .filter_map(|value| value.strip_prefix(&prefix).map(|s| s.to_string()))
+        .collect())
+}
+
+/// Whether this libsystemd applies matches when listing unique values
+///
+/// According to the API docs, listing unique values is "currently not
+/// influenced by matches set with sd_journal_add_match() but this might
+/// change in a later version of this software." The check lists the values
+/// of a field under a match on one of them, so it needs a field with several
+/// values. If the journal has a single machine ID, hostname and unit, every
+/// host lists the same values either way, so it is taken as yes.
+fn unique_values_honor_matches(journal: &Journal, hostnames: &HashSet<String>) -> Result<bool> {
+    let machines = unique_values(journal, "_MACHINE_ID")?;
+    let units = unique_values(journal, "_SYSTEMD_UNIT")?;
+    let varying = [("_MACHINE_ID", &machines), ("_HOSTNAME", hostnames), ("_SYSTEMD_UNIT", &units)]
+        .into_iter()
+        .find(|(_, values)| values.len() > 1);
+    let Some((field, values)) = varying else {
+        return Ok(true);
+    };
+    let Some(value) = values.iter().next() else {
+        return Ok(true);
+    };
+    journal.flush_matches();
+    journal.add_match(field, value)?;
+    let listed = unique_values(journal, field)?;
+    journal.flush_matches();
+    Ok(listed.len() <= 1)
+}
+
+/// For each host, the values of its [`HostKey::field`] selecting its entries
+///
+/// journald adds `_MACHINE_ID` to every entry, so machines are selected by
+/// machine ID. Entries imported without one, e.g. by systemd-journal-remote
+/// from exports that leave it out, are selected by hostname, with the
+/// hostnames of one host merged by `aliases`.
+///
+/// Matches cannot select entries without a field, so a hostname that also
+/// has machine IDs only counts as logged without one if its first or last
+/// entry lacks it. `DiscoveryStrategy::Scan` finds the others.
+///
+/// # Returns
+/// The selectors, or `None` if a hostname is logged both with and without a
+/// machine ID, since only reading the entries tells them apart
+fn host_selectors(
+    journal: &Journal,
+    hostnames: &HashSet<String>,
+    aliases: &HostAliases,
+) -> Result<Option<HashMap<HostKey, Vec<String>>>> {
+    let machines = unique_values(journal, "_MACHINE_ID")?;
+    let mut selectors: HashMap<HostKey, Vec<String>> = machines
+        .iter()
+        .map(|machine_id| (HostKey::Machine(machine_id.clone()), vec![machine_id.clone()]))
+        .collect();
+    
+    for hostname in hostnames {
+        if !machines.is_empty() {
+            journal.flush_matches();
+            journal.add_match("_HOSTNAME", hostname)?;
+            if !ends_lack_machine_id(journal)? {
+                continue;
+            }
+            // Matches on one field are OR'ed, so this selects the hostname's
+            // entries with any machine ID
+            for machine_id in &machines {
+                journal.add_match("_MACHINE_ID", machine_id)?;
+            }
+            journal.seek_head()?;
+            if journal.next()? {
+                journal.flush_matches();
+                return Ok(None);
+            }
+        }
+        let key = HostKey::Hostname(aliases.canonical(hostname).to_string());
+        selectors.entry(key).or_default().push(hostname.clone());
+    }
+    journal.flush_matches();
+    Ok(Some(selectors))
+}
+
+/// Whether the first or last entry the matches select has no machine ID
+fn ends_lack_machine_id(journal: &Journal) -> Result<bool> {
+    journal.seek_head()?;
+    if !journal.next()? {
+        return Ok(false);
+    }
+    if field_value(journal, "_MACHINE_ID")?.is_none() {
+        return Ok(true);
+    }
+    journal.seek_tail()?;
+    journal.previous()?;
+    Ok(field_value(journal, "_MACHINE_ID")?.is_none())
+}
+
+/// Record when `hostname` first and last appears among the entries where
+/// `field` is `value`
+///
+/// # Returns
+/// The receive times of those entries, or `None` if there are none
+fn probe_hostname(
+    journal: &Journal,
+    field: &str,
+    value: &str,
+    hostname: &str,
+    aliases: &HostAliases,
+    host: &mut Found,
+) -> Result<Option<(u64, u64)>> {
+    journal.flush_matches();
+    // Two matches on _HOSTNAME would be OR'ed, and the value is the hostname then
+    if field != "_HOSTNAME" {
+        journal.add_match(field, value)?;
+    }
+    journal.add_match("_HOSTNAME", hostname)?;
+    
+    journal.seek_head()?;
+    if !journal.next()? {
+        return Ok(None);
+    }
+    let first = journal.get_realtime_usec()?;
+    journal.seek_tail()?;
+    journal.previous()?;
+    let last = journal.get_realtime_usec()?;
+    
+    host.saw_hostname(aliases.canonical(hostname), first, last);
+    Ok(Some((first, last)))
+}
+
+/// Record the hostnames a machine logged under, probing each of them once
+///
+/// Walks the machine's entries from the oldest, jumping past the last entry
+/// under each hostname it finds, so a renamed machine costs a probe per name
+/// it used rather than one per hostname in the journal. A hostname used only
+/// while the machine also logged under an earlier one is missed;
+/// `DiscoveryStrategy::Scan` finds those.
+fn probe_machine_hostnames(
+    journal: &Journal,
+    machine_id: &str,
+    aliases: &HostAliases,
+    host: &mut Found,
+) -> Result<()> {
+    let mut probed = HashSet::new();
+    let mut after = None;
+    loop {
+        journal.flush_matches();
+        journal.add_match("_MACHINE_ID", machine_id)?;
+        match after {
+            Some(last) => journal.seek_realtime_usec(last)?,
+            None => journal.seek_head()?,
+        }
+        if !journal.next()? {
+            return Ok(());
+        }
+        let Some(hostname) = field_value(journal, "_HOSTNAME")? else {
+            return Ok(());
+        };
+        // Entries written out of time order can lead back to a hostname
+        if !probed.insert(hostname.clone()) {
+            return Ok(());
+        }
+        match probe_hostname(journal, "_MACHINE_ID", machine_id, &hostname, aliases, host)? {
+            Some((_, last)) => after = Some(last.saturating_add(1)),
+            None => return Ok(()),
+        }
+    }
+}
+
+/// List the units and hostnames under a match for each host
+fn hosts_by_listing(
+    journal: &Journal,
+    selectors: HashMap<HostKey, Vec<String>>,
+    aliases: &HostAliases,
+    control: &mut ScanControl,
+) -> Result<HashMap<HostKey, Found>> {
+    let listings = selectors.values().map(Vec::len).sum::<usize>();
+    
+    let mut found: HashMap<HostKey, Found> = HashMap::new();
+    let mut done = 0;
+    for (key, values) in selectors {
+        let field = key.field();
+        let host = found.entry(key).or_default();
+        for value in &values {
+            journal.flush_matches();
+            journal.add_match(field, value)?;
+            host.units.extend(unique_values(journal, "_SYSTEMD_UNIT")?);
+            let names = match field {
+                "_HOSTNAME" => HashSet::from([value.clone()]),
+                _ => unique_values(journal, "_HOSTNAME")?,
+            };
+            for hostname in &names {
+                probe_hostname(journal, field, value, hostname, aliases, host)?;
+            }
+            
+            done += 1;
+            control.advance(None, Some(done as f64 / listings as f64))?;
+        }
+    }
+    journal.flush_matches();
+    Ok(found)
+}
+
+/// Read every entry once, collecting the hostnames and units of each host
+/// with their entry counts and times, and with `details` their priorities
+/// and most recent message
+///
+/// With `duplicates`, copies of an entry are counted once.
+fn hosts_by_scanning(
+    journal: &Journal,
+    duplicates: bool,
+    aliases: &HostAliases,
+    control: &mut ScanControl,
+    details: bool,
+) -> Result<HashMap<HostKey, Found>> {
+    journal.flush_matches();
+    
+    // Progress is estimated against the receive times of the first and last entries
+    let range = match control.wants_progress() {
+        true => {
+            journal.seek_tail()?;
+            let last = if journal.previous()? { journal.get_realtime_usec()? } else { 0 };
+            journal.seek_head()?;
+            let first = if journal.next()? { journal.get_realtime_usec()? } else { 0 };
+            Some((first, last))
+        }
+        false => None,
+    };
+    
+    let mut found = HashMap::new();
+    let mut seen = duplicates.then(SeenEntries::default);
+    journal.seek_head()?;
+    while journal.next()? {
+        let received = journal.get_realtime_usec()?;
+        let copy = match &mut seen {
+            Some(seen) => seen.repeated(journal, received)?,
+            None => false,
+        };
+        if !copy {
+            record_entry(journal, received, aliases, details, &mut found)?;
+        }
+        
+        let fraction = range.and_then(|(first, last)| fraction_between(first, last, received));
+        control.advance(Some(received), fraction)?;
+    }
+    Ok(found)
+}
+
+/// Read the entries `filter` selects, collecting what each host logged
+/// as [`hosts_by_scanning`] does
+fn hosts_by_filtered_scan<S: JournalSource + ?Sized>(
+    source: &S,
+    filter: &Query,
+    aliases: &HostAliases,
+    control: &mut ScanControl,
+    details: bool,
+) -> Result<HashMap<HostKey, Found>> {
+    let mut found = HashMap::new();
+    scan_journal(source, filter, Direction::Forward, control, |journal, grep, _| {
+        if matches_text_filters(journal, filter, grep)? {
+            record_entry(journal, journal.get_realtime_usec()?, aliases, details, &mut found)?;
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+    Ok(found)
+}
+
+/// Add the entries after `cursor`, or every entry without one, to the hosts
+/// found before, as [`hosts_by_scanning`] would have found them
+///
+/// With `duplicates`, copies of an entry are counted once.
+///
+/// # Returns
+/// The hosts, the number of entries read and the cursor of the last entry read,
+/// or `cursor` if there were no new entries
+pub(crate) fn scan_after(
+    journal: &Journal,
+    duplicates: bool,
+    hosts: Hosts,
+    cursor: Option<&str>,
+    aliases: &HostAliases,
+    details: bool,
+) -> Result<(Hosts, u64, Option<String>)> {
+    journal.flush_matches();
+    let mut found = found_from(hosts);
+    let mut last = cursor.map(str::to_string);
+    let mut read = 0;
+    let mut seen = duplicates.then(SeenEntries::default);
+    
+    match cursor {
+        Some(cursor) => journal.seek_cursor(cursor)?,
+        None => journal.seek_head()?,
+    }
+    let mut first = true;
+    while journal.next()? {
+        // Seeking lands on the entry at the cursor itself, unless it is gone
+        let at_cursor = match cursor {
+            Some(cursor) if first => journal.test_cursor(cursor)?,
+            _ => false,
+        };
+        first = false;
+        // The entry at the cursor is still seen, so its copies are skipped
+        let received = journal.get_realtime_usec()?;
+        let copy = match &mut seen {
+            Some(seen) => seen.repeated(journal, received)?,
+            None => false,
+        };
+        if at_cursor || copy {
+            continue;
+        }
+        record_entry(journal, received, aliases, details, &mut found)?;
+        read += 1;
+        last = Some(journal.get_cursor()?);
+    }
+    Ok((hosts_from(found, details), read, last))
+}
+
+/// Turn hosts back into what discovery found out about them
+fn found_from(hosts: Hosts) -> HashMap<HostKey, Found> {
+    let mut found = HashMap::new();
+    for host in hosts.hosts {
+        let key = host.key();
+        let hostnames = host.hostnames
+            .into_iter()
+            .map(|seen| (seen.hostname, (seen.first_seen_utc, seen.last_seen_utc)))
+            .collect();
+        let stats = host.unit_stats
+            .unwrap_or_default()
+            .into_iter()
+            .map(|stats| (stats.unit.clone(), stats))
+            .collect();
+        found.insert(key, Found {
+            hostnames,
+            units: host.units.into_iter().collect(),
+            stats,
+        });
+    }
+    found
+}
+
+/// Count the current entry towards its host and unit
+fn record_entry(
+    journal: &Journal,
+    received: u64,
+    aliases: &HostAliases,
+    details: bool,
+    found: &mut HashMap<HostKey, Found>,
+) -> Result<()> {
+    let Some(hostname) = field_value(journal, "_HOSTNAME")? else {
+        return Ok(());
+    };
+    let hostname = aliases.canonical(&hostname).to_string();
+    let key = HostKey::new(field_value(journal, "_MACHINE_ID")?, &hostname);
+    
+    let host = found.entry(key).or_default();
+    host.saw_hostname(&hostname, received, received);
+    let Some(unit) = field_value(journal, "_SYSTEMD_UNIT")? else {
+        return Ok(());
+    };
     
-    Ok(Hosts { hosts })
+    let stats = host.stats.entry(unit.clone()).or_insert_with(|| UnitStats::new(unit));
+    let newest = received >= stats.last_seen_utc;
+    stats.entries += 1;
+    stats.first_seen_utc = stats.first_seen_utc.min(received);
+    stats.last_seen_utc = stats.last_seen_utc.max(received);
+    
+    if details {
+        let level = field_value(journal, "PRIORITY")?
+            .and_then(|priority| priority.parse::<usize>().ok())
+            .filter(|level| *level < 8);
+        if let Some(level) = level {
+            stats.priority_counts[level] += 1;
+        }
+        if newest {
+            stats.last_message = field_value(journal, "MESSAGE")?;
+        }
+    }
+    Ok(())
+}
+
+/// Ideally we could use sd_journal_enumerate_entries with a couple of filters
+/// to get the results, but according to the API docs:
+/// 
+/// "Note that these functions currently are not influenced by matches set with sd_journal_add_match() but 
+/// this might change in a later version of this software."
+/// 
+/// As such, we have to instead:
+/// - Query to get all the unique hosts and units
+/// - Check for each host+unit combination if it exists in the journal
+/// - Walk each host's hostnames, checking when each was first and last seen
+/// 
+/// This is... not great, but the best one can reasonably do with the API
+/// when reading the whole journal would take longer.
+fn hosts_by_probing(
+    journal: &Journal,
+    selectors: HashMap<HostKey, Vec<String>>,
+    aliases: &HostAliases,
+    control: &mut ScanControl,
+) -> Result<HashMap<HostKey, Found>> {
+    let units = unique_values(journal, "_SYSTEMD_UNIT")?;
+    
+    let mut found: HashMap<HostKey, Found> = HashMap::new();
+    
+    let pairs = selectors.values().map(Vec::len).sum::<usize>() * units.len();
+    let mut checked = 0;
+    for (key, values) in selectors {
+        let field = key.field();
+        let host = found.entry(key).or_default();
+        for value in &values {
+            match field {
+                "_HOSTNAME" => {
+                    probe_hostname(journal, field, value, value, aliases, host)?;
+                }
+                _ => probe_machine_hostnames(journal, value, aliases, host)?,
+            }
+            
+            // For each unit, check if the host+unit combination exists
+            for unit in &units {
+                journal.flush_matches();
+                journal.add_match(field, value)?;
+                journal.add_match("_SYSTEMD_UNIT", unit)?;
+                journal.seek_head()?;
+                if journal.next()? {
+                    host.units.insert(unit.clone());
+                }
+                
+                checked += 1;
+                control.advance(None, Some(checked as f64 / pairs as f64))?;
+            }
+        }
+    }
+    journal.flush_matches();
+    
+    Ok(found)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Host, Hosts};
+    use super::{
+        found_from, hosts_from, DiscoveryOptions, DiscoveryStrategy, Found, GroupNode, Host, HostKey, HostnameSeen, Hosts,
+        UnitStats,
+    };
+    use std::collections::HashMap;
     
     // These are unit tests that don't require actual journal files
     
@@ -219,6 +1116,9 @@ mod tests {
         let host = Host {
             hostname: "test-server".to_string(),
             units: vec!["sshd.service".to_string(), "nginx.service".to_string()],
+            machine_id: None,
+            hostnames: Vec::new(),
+            unit_stats: None,
         };
         
         assert_eq!(host.hostname, "test-server");
@@ -233,10 +1133,16 @@ mod tests {
                 Host {
                     hostname: "server1".to_string(),
                     units: vec!["sshd.service".to_string(), "nginx.service".to_string()],
+                    machine_id: None,
+                    hostnames: Vec::new(),
+                    unit_stats: None,
                 },
                 Host {
                     hostname: "server2".to_string(),
                     units: vec!["mysql.service".to_string()],
+                    machine_id: None,
+                    hostnames: Vec::new(),
+                    unit_stats: None,
                 },
             ],
         };
@@ -263,6 +1169,99 @@ mod tests {
         assert!(not_found.is_none());
     }
 
+    #[test]
+    fn test_hosts_by_machine_id() {
+        let mut renamed = Found::default();
+        renamed.saw_hostname("old-name", 100, 200);
+        renamed.saw_hostname("new-name", 300, 400);
+        renamed.units.insert("nginx.service".to_string());
+        let mut local_a = Found::default();
+        local_a.saw_hostname("localhost", 100, 400);
+        let mut local_b = Found::default();
+        local_b.saw_hostname("localhost", 150, 250);
+        
+        let hosts = hosts_from(HashMap::from([
+            (HostKey::Machine("aaaa".to_string()), renamed),
+            (HostKey::Machine("bbbb".to_string()), local_a),
+            (HostKey::Machine("cccc".to_string()), local_b),
+            (HostKey::Machine("dddd".to_string()), Found::default()),
+        ]), false);
+        
+        assert_eq!(hosts.hostnames(), vec!["localhost", "localhost", "new-name"]);
+        let renamed = hosts.find_machine("aaaa").unwrap();
+        assert_eq!(renamed.hostname, "new-name");
+        assert_eq!(renamed.hostnames, vec![
+            HostnameSeen { hostname: "old-name".to_string(), first_seen_utc: 100, last_seen_utc: 200 },
+            HostnameSeen { hostname: "new-name".to_string(), first_seen_utc: 300, last_seen_utc: 400 },
+        ]);
+        assert_eq!(hosts.find_host("old-name").and_then(|host| host.machine_id.as_deref()), Some("aaaa"));
+        assert!(hosts.find_machine("dddd").is_none(), "Machines without a hostname are left out");
+    }
+
+    #[test]
+    fn test_found_from_hosts() {
+        let mut renamed = Found::default();
+        renamed.saw_hostname("old-name", 100, 200);
+        renamed.saw_hostname("new-name", 300, 400);
+        let mut stats = UnitStats::new("nginx.service".to_string());
+        stats.entries = 2;
+        renamed.stats.insert("nginx.service".to_string(), stats);
+        let mut plain = Found::default();
+        plain.saw_hostname("server1", 100, 100);
+        plain.units.insert("sshd.service".to_string());
+        
+        for keep_stats in [false, true] {
+            let found = HashMap::from([
+                (HostKey::Machine("aaaa".to_string()), renamed.clone()),
+                (HostKey::Hostname("server1".to_string()), plain.clone()),
+            ]);
+            let hosts = hosts_from(found, keep_stats);
+            assert_eq!(hosts_from(found_from(hosts.clone()), keep_stats), hosts);
+        }
+    }
+
+    #[test]
+    fn test_unit_stats() {
+        let mut stats = UnitStats::new("nginx.service".to_string());
+        stats.priority_counts = [0, 1, 0, 2, 5, 0, 10, 3];
+        assert_eq!(stats.at_or_above(3), 3);
+        assert_eq!(stats.at_or_above(7), 21);
+        
+        let host = Host {
+            hostname: "server1".to_string(),
+            units: vec!["nginx.service".to_string()],
+            machine_id: None,
+            hostnames: Vec::new(),
+            unit_stats: Some(vec![stats]),
+        };
+        assert_eq!(host.stats("nginx.service").map(|stats| stats.at_or_above(0)), Some(0));
+        assert!(host.stats("sshd.service").is_none());
+        
+        let options = DiscoveryOptions::new().unit_stats(true);
+        assert!(options.unit_stats);
+        assert_eq!(options.strategy, DiscoveryStrategy::Auto);
+    }
+
+    #[test]
+    fn test_group_tree() {
+        let mut root = GroupNode::default();
+        root.record(["web".to_string(), "1000".to_string(), "app.service".to_string()]);
+        root.record(["web".to_string(), "1000".to_string()]);
+        root.record(["db".to_string(), "0".to_string(), "backup.service".to_string()]);
+        root.record(Vec::new());
+        
+        let groups = root.into_groups(&["_HOSTNAME", "_UID", "_SYSTEMD_USER_UNIT"]);
+        assert_eq!(groups.iter().map(|group| group.value.as_str()).collect::<Vec<_>>(), vec!["db", "web"]);
+        
+        let web = &groups[1];
+        assert_eq!((web.field.as_str(), web.entries), ("_HOSTNAME", 2));
+        let user = web.child("1000").unwrap();
+        assert_eq!((user.field.as_str(), user.entries), ("_UID", 2));
+        assert_eq!(user.child_values(), vec!["app.service"]);
+        assert_eq!(user.children[0].entries, 1);
+        assert!(user.children[0].children.is_empty());
+    }
+
     #[test]
     fn test_empty_hosts() {
         let hosts = Hosts::new();
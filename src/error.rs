@@ -5,6 +5,7 @@ pub type Result<T> = std::result::Result<T, JournalError>;
 
 /// Errors that can occur during journal operations
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
 pub enum JournalError {
     /// Invalid argument provided
     InvalidArgument,
@@ -28,6 +29,20 @@ pub enum JournalError {
     IoError,
     /// Data contains invalid UTF-8
     InvalidData,
+    /// A match pattern could not be compiled
+    InvalidPattern(String),
+    /// A time specification could not be parsed
+    InvalidTimeSpec(String),
+    /// A journalctl-style command line could not be parsed
+    InvalidExpression(String),
+    /// An index file could not be read
+    InvalidIndex(String),
+    /// A discovery checkpoint file could not be read
+    InvalidCheckpoint(String),
+    /// The operation was cancelled through its cancellation token
+    Cancelled,
+    /// The operation ran past its deadline
+    DeadlineExceeded,
     /// Unknown error code from systemd
     Unknown(i32),
 }
@@ -65,6 +80,13 @@ impl fmt::Display for JournalError {
             JournalError::BadMessage => write!(f, "Journal is corrupted"),
             JournalError::IoError => write!(f, "I/O error occurred"),
             JournalError::InvalidData => write!(f, "Data contains invalid UTF-8"),
+            JournalError::InvalidPattern(reason) => write!(f, "Invalid match pattern: {}", reason),
+            JournalError::InvalidTimeSpec(spec) => write!(f, "Invalid time specification: {:?}", spec),
+            JournalError::InvalidExpression(reason) => write!(f, "Invalid journalctl expression: {}", reason),
+            JournalError::InvalidIndex(reason) => write!(f, "Invalid journal index: {}", reason),
+            JournalError::InvalidCheckpoint(reason) => write!(f, "Invalid discovery checkpoint: {}", reason),
+            JournalError::Cancelled => write!(f, "Operation was cancelled"),
+            JournalError::DeadlineExceeded => write!(f, "Operation ran past its deadline"),
             JournalError::Unknown(code) => write!(f, "Unknown error code: {}", code),
         }
     }
@@ -6,6 +6,34 @@ pub struct SdJournal {
     _private: [u8; 0],
 }
 
+/// 128-bit ID as used by systemd for boot and machine IDs (`sd_id128_t`)
+#[repr(C, align(8))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SdId128 {
+    pub bytes: [u8; 16],
+}
+
+impl SdId128 {
+    /// Format as 32 lowercase hex digits, the way journal fields store IDs
+    pub fn to_hex(self) -> String {
+        self.bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Parse 32 hex digits, optionally in dashed UUID form
+    pub fn from_hex(s: &str) -> Option<Self> {
+        let digits: Vec<u8> = s.bytes().filter(|b| *b != b'-').collect();
+        if digits.len() != 32 {
+            return None;
+        }
+        let mut id = SdId128::default();
+        for (byte, pair) in id.bytes.iter_mut().zip(digits.chunks(2)) {
+            let pair = std::str::from_utf8(pair).ok()?;
+            *byte = u8::from_str_radix(pair, 16).ok()?;
+        }
+        Some(id)
+    }
+}
+
 // FFI bindings to systemd journal functions
 unsafe extern "C" {
     pub fn sd_journal_open_directory(
@@ -38,12 +66,18 @@ unsafe extern "C" {
         size: usize,
     ) -> c_int;
 
+    pub fn sd_journal_add_disjunction(j: *mut SdJournal) -> c_int;
+
+    pub fn sd_journal_add_conjunction(j: *mut SdJournal) -> c_int;
+
     pub fn sd_journal_flush_matches(j: *mut SdJournal);
 
     pub fn sd_journal_seek_head(j: *mut SdJournal) -> c_int;
 
     pub fn sd_journal_next(j: *mut SdJournal) -> c_int;
 
+    pub fn sd_journal_previous(j: *mut SdJournal) -> c_int;
+
     pub fn sd_journal_get_data(
         j: *mut SdJournal,
         field: *const c_char,
@@ -61,6 +95,36 @@ unsafe extern "C" {
         usec: *mut u64,
     ) -> c_int;
 
+    pub fn sd_journal_set_data_threshold(j: *mut SdJournal, sz: usize) -> c_int;
+
+    pub fn sd_journal_enumerate_available_data(
+        j: *mut SdJournal,
+        data: *mut *const c_void,
+        length: *mut usize,
+    ) -> c_int;
+
+    pub fn sd_journal_restart_data(j: *mut SdJournal);
+
+    pub fn sd_journal_get_monotonic_usec(
+        j: *mut SdJournal,
+        usec: *mut u64,
+        boot_id: *mut SdId128,
+    ) -> c_int;
+
+    pub fn sd_journal_seek_monotonic_usec(
+        j: *mut SdJournal,
+        boot_id: SdId128,
+        usec: u64,
+    ) -> c_int;
+
+    pub fn sd_journal_get_cursor(j: *mut SdJournal, cursor: *mut *mut c_char) -> c_int;
+
+    pub fn sd_journal_seek_cursor(j: *mut SdJournal, cursor: *const c_char) -> c_int;
+
+    pub fn sd_journal_test_cursor(j: *mut SdJournal, cursor: *const c_char) -> c_int;
+
+    pub fn sd_journal_get_usage(j: *mut SdJournal, bytes: *mut u64) -> c_int;
+
     /// Seek to the end of the journal (most recent entry)
     /// 
     /// This positions the journal cursor after the most recent available entry.
@@ -68,7 +132,6 @@ unsafe extern "C" {
     /// unless new entries are added to the journal.
     /// 
     /// Returns 0 on success or a negative errno-style error code.
-    #[allow(dead_code)]
     pub fn sd_journal_seek_tail(j: *mut SdJournal) -> c_int;
 
     /// Wait for changes to the journal
@@ -92,8 +155,15 @@ unsafe extern "C" {
     /// non-blocking alternative to sd_journal_wait().
     /// 
     /// Returns the same values as sd_journal_wait().
-    #[allow(dead_code)]
     pub fn sd_journal_process(j: *mut SdJournal) -> c_int;
+
+    /// Get a file descriptor that becomes readable when the journal changes
+    /// 
+    /// The first call sets up the inotify watches that let sd_journal_process()
+    /// pick up journal files created after the journal was opened.
+    /// 
+    /// Returns the file descriptor or a negative errno-style error code.
+    pub fn sd_journal_get_fd(j: *mut SdJournal) -> c_int;
 }
 
 /// Journal open flags
@@ -161,21 +231,45 @@ mod tests {
             sd_journal_open_files;
         let _add_match_fn: unsafe extern "C" fn(*mut SdJournal, *const c_void, usize) -> c_int = 
             sd_journal_add_match;
+        let _add_disjunction_fn: unsafe extern "C" fn(*mut SdJournal) -> c_int = 
+            sd_journal_add_disjunction;
+        let _add_conjunction_fn: unsafe extern "C" fn(*mut SdJournal) -> c_int = 
+            sd_journal_add_conjunction;
         let _flush_matches_fn: unsafe extern "C" fn(*mut SdJournal) = sd_journal_flush_matches;
         let _seek_head_fn: unsafe extern "C" fn(*mut SdJournal) -> c_int = sd_journal_seek_head;
         let _next_fn: unsafe extern "C" fn(*mut SdJournal) -> c_int = sd_journal_next;
+        let _previous_fn: unsafe extern "C" fn(*mut SdJournal) -> c_int = sd_journal_previous;
         let _get_data_fn: unsafe extern "C" fn(*mut SdJournal, *const c_char, *mut *const c_void, *mut usize) -> c_int = 
             sd_journal_get_data;
         let _seek_realtime_fn: unsafe extern "C" fn(*mut SdJournal, u64) -> c_int = 
             sd_journal_seek_realtime_usec;
         let _get_realtime_fn: unsafe extern "C" fn(*mut SdJournal, *mut u64) -> c_int = 
             sd_journal_get_realtime_usec;
+        let _get_monotonic_fn: unsafe extern "C" fn(*mut SdJournal, *mut u64, *mut SdId128) -> c_int = 
+            sd_journal_get_monotonic_usec;
+        let _seek_monotonic_fn: unsafe extern "C" fn(*mut SdJournal, SdId128, u64) -> c_int = 
+            sd_journal_seek_monotonic_usec;
+        let _set_threshold_fn: unsafe extern "C" fn(*mut SdJournal, usize) -> c_int = 
+            sd_journal_set_data_threshold;
+        let _enumerate_data_fn: unsafe extern "C" fn(*mut SdJournal, *mut *const c_void, *mut usize) -> c_int = 
+            sd_journal_enumerate_available_data;
+        let _restart_data_fn: unsafe extern "C" fn(*mut SdJournal) = sd_journal_restart_data;
+        let _get_cursor_fn: unsafe extern "C" fn(*mut SdJournal, *mut *mut c_char) -> c_int = 
+            sd_journal_get_cursor;
+        let _seek_cursor_fn: unsafe extern "C" fn(*mut SdJournal, *const c_char) -> c_int = 
+            sd_journal_seek_cursor;
+        let _test_cursor_fn: unsafe extern "C" fn(*mut SdJournal, *const c_char) -> c_int = 
+            sd_journal_test_cursor;
+        let _get_usage_fn: unsafe extern "C" fn(*mut SdJournal, *mut u64) -> c_int = 
+            sd_journal_get_usage;
         let _seek_tail_fn: unsafe extern "C" fn(*mut SdJournal) -> c_int = 
             sd_journal_seek_tail;
         let _wait_fn: unsafe extern "C" fn(*mut SdJournal, u64) -> c_int = 
             sd_journal_wait;
         let _process_fn: unsafe extern "C" fn(*mut SdJournal) -> c_int = 
             sd_journal_process;
+        let _get_fd_fn: unsafe extern "C" fn(*mut SdJournal) -> c_int = 
+            sd_journal_get_fd;
 
         // Verify we can create the types we need
         let _: *mut *mut SdJournal = journal_ptr_ptr;
@@ -184,6 +278,29 @@ mod tests {
         let _: u64 = 0;
     }
 
+    #[test]
+    fn test_id128_layout() {
+        // sd_id128_t is a 16-byte union with 64-bit members
+        assert_eq!(std::mem::size_of::<SdId128>(), 16);
+        assert_eq!(std::mem::align_of::<SdId128>(), 8);
+    }
+
+    #[test]
+    fn test_id128_hex_round_trip() {
+        let hex = "1234567890abcdef1234567890abcdef";
+        let id = SdId128::from_hex(hex).expect("valid hex should parse");
+        assert_eq!(id.bytes[0], 0x12);
+        assert_eq!(id.bytes[15], 0xef);
+        assert_eq!(id.to_hex(), hex);
+
+        // Dashed UUID form is accepted too
+        let uuid = SdId128::from_hex("12345678-90ab-cdef-1234-567890abcdef").unwrap();
+        assert_eq!(uuid, id);
+
+        assert!(SdId128::from_hex("1234").is_none());
+        assert!(SdId128::from_hex("zz34567890abcdef1234567890abcdef").is_none());
+    }
+
     #[test]
     fn test_flags() {
         // Verify flags have expected values (these are from systemd source)
@@ -233,7 +350,7 @@ mod tests {
         
         let journal_ptr: *mut SdJournal = ptr::null_mut();
         let match_data = b"_HOSTNAME=test-server";
-        let field_name = b"MESSAGE\0".as_ptr() as *const c_char;
+        let field_name = c"MESSAGE".as_ptr();
         let mut data_ptr: *const c_void = ptr::null();
         let mut data_len: usize = 0;
         
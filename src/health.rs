@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::aggregate::{aggregate, Aggregation};
+use crate::alias::HostAliases;
+use crate::control::ScanControl;
+use crate::discover::{discover_services_with_options, DiscoveryOptions, Host};
+use crate::error::{JournalError, Result};
+use crate::lifecycle::{unit_lifecycle_between, LifecycleKind, UnitTimeline};
+use crate::query::{Query, COREDUMP_MESSAGE_ID};
+use crate::source::JournalSource;
+
+/// How healthy a unit looked over the report window, from best to worst
+///
+/// - `Healthy`: nothing below applies.
+/// - `Silent`: the unit has not logged for longer than [`HealthOptions::silent_after`].
+/// - `Degraded`: the unit restarted or crashed, or its error rate reached
+///   [`HealthOptions::degraded_error_rate`].
+/// - `Failing`: the unit ended the window failed, dumped core, restarted at least
+///   [`HealthOptions::failing_restarts`] times, or its error rate reached
+///   [`HealthOptions::failing_error_rate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Health {
+    Healthy,
+    Silent,
+    Degraded,
+    Failing,
+}
+
+impl Health {
+    /// The name used in JSON output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Health::Healthy => "healthy",
+            Health::Silent => "silent",
+            Health::Degraded => "degraded",
+            Health::Failing => "failing",
+        }
+    }
+}
+
+/// How a unit fared on one host over the report window
+///
+/// Fields:
+/// - `unit`: The systemd unit name.
+/// - `entries`: Number of entries the unit logged in the window.
+/// - `errors`: How many of them have priority `err` (3) or more severe.
+/// - `error_rate`: `errors / entries`, or `0.0` without entries.
+/// - `restarts`: Automatic restarts `Restart=` scheduled in the window.
+/// - `crashes`: Main processes that exited unsuccessfully in the window: with a
+///   non-zero exit status, killed by a signal or dumped core.
+/// - `coredumps`: Core dumps systemd-coredump recorded for the unit in the window.
+/// - `last_failure`: Result of the last time the unit failed in the window, such as `exit-code`.
+/// - `last_log_utc`: Receive time of the unit's newest entry up to the end of the window,
+///   in microseconds since Unix epoch (UTC).
+/// - `since_last_log`: Time from `last_log_utc` to the end of the window.
+/// - `health`: The classification of all of the above.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnitHealth {
+    pub unit: String,
+    pub entries: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub restarts: u64,
+    pub crashes: u64,
+    pub coredumps: u64,
+    pub last_failure: Option<String>,
+    pub last_log_utc: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::schema::option_duration_usec"))]
+    pub since_last_log: Option<Duration>,
+    pub health: Health,
+}
+
+/// The units of one host in a [`HealthReport`]
+///
+/// Fields:
+/// - `hostname`: The hostname the host logged under most recently, as in [`Host`].
+/// - `machine_id`: The machine ID of the host, if its entries carry one.
+/// - `units`: Every unit the host has logged from, sorted by name.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostHealth {
+    pub hostname: String,
+    pub machine_id: Option<String>,
+    pub units: Vec<UnitHealth>,
+}
+
+impl HostHealth {
+    /// The worst health of the host's units, `Healthy` if it has none
+    pub fn health(&self) -> Health {
+        self.units.iter().map(|unit| unit.health).max().unwrap_or(Health::Healthy)
+    }
+}
+
+/// Health of every unit on every host over a time window
+///
+/// Serialized with a `schema_version` field, see [`crate::schema`].
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{health_report, parse_timestamp, Health, HealthOptions};
+///
+/// // The morning check: how did the last 24 hours go?
+/// let options = HealthOptions::new(parse_timestamp("-24h")?, parse_timestamp("now")?);
+/// let report = health_report("/var/log/journal", &options)?;
+///
+/// for (host, unit) in report.unhealthy() {
+///     println!("{} {}: {:?}, {} restarts, {:.1}% errors",
+///         host.hostname, unit.unit, unit.health, unit.restarts, unit.error_rate * 100.0);
+/// }
+/// if report.health() == Health::Failing {
+///     eprintln!("{} units failing", report.unhealthy().len());
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub struct HealthReport {
+    /// Start of the window, in microseconds since Unix epoch (UTC)
+    pub start_time_utc: u64,
+    /// End of the window, in microseconds since Unix epoch (UTC)
+    pub end_time_utc: u64,
+    /// Every host found in the journal, in discovery order
+    pub hosts: Vec<HostHealth>,
+}
+
+impl HealthReport {
+    /// The worst health of any unit, `Healthy` if there are none
+    pub fn health(&self) -> Health {
+        self.hosts.iter().map(HostHealth::health).max().unwrap_or(Health::Healthy)
+    }
+
+    /// Every unit that is not healthy, with its host, worst first
+    pub fn unhealthy(&self) -> Vec<(&HostHealth, &UnitHealth)> {
+        let mut unhealthy: Vec<_> = self
+            .hosts
+            .iter()
+            .flat_map(|host| host.units.iter().map(move |unit| (host, unit)))
+            .filter(|(_, unit)| unit.health != Health::Healthy)
+            .collect();
+        unhealthy.sort_by_key(|(_, unit)| std::cmp::Reverse(unit.health));
+        unhealthy
+    }
+
+    /// Find a unit of the host with the given hostname
+    pub fn find(&self, hostname: &str, unit: &str) -> Option<&UnitHealth> {
+        self.hosts
+            .iter()
+            .filter(|host| host.hostname == hostname)
+            .find_map(|host| host.units.iter().find(|health| health.unit == unit))
+    }
+
+    /// The report as a JSON object, in the encoding described in the `schema` module
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Health reports always serialize")
+    }
+}
+
+/// Thresholds and window of a [`HealthReport`]
+///
+/// Fields:
+/// - `start_time_utc`, `end_time_utc`: The window, in microseconds since Unix epoch (UTC).
+/// - `degraded_error_rate`: Error rate from which a unit is `Degraded`. Defaults to 5%.
+/// - `failing_error_rate`: Error rate from which a unit is `Failing`. Defaults to 50%.
+/// - `failing_restarts`: Restarts from which a unit is `Failing`. Defaults to 3.
+/// - `silent_after`: How long a unit can go without logging before it is `Silent`.
+///   Defaults to 24 hours.
+/// - `host_aliases`: Optional alias map normalizing hostnames, as in discovery.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthOptions {
+    pub start_time_utc: u64,
+    pub end_time_utc: u64,
+    pub degraded_error_rate: f64,
+    pub failing_error_rate: f64,
+    pub failing_restarts: u64,
+    pub silent_after: Duration,
+    pub host_aliases: Option<HostAliases>,
+}
+
+impl HealthOptions {
+    /// Create options for a window with the default thresholds
+    pub fn new(start_time_utc: u64, end_time_utc: u64) -> Self {
+        HealthOptions {
+            start_time_utc,
+            end_time_utc,
+            degraded_error_rate: 0.05,
+            failing_error_rate: 0.5,
+            failing_restarts: 3,
+            silent_after: Duration::from_secs(24 * 60 * 60),
+            host_aliases: None,
+        }
+    }
+
+    /// Set the error rates from which a unit is `Degraded` and `Failing`
+    pub fn error_rates(mut self, degraded: f64, failing: f64) -> Self {
+        self.degraded_error_rate = degraded;
+        self.failing_error_rate = failing;
+        self
+    }
+
+    /// Set the number of restarts from which a unit is `Failing`
+    pub fn failing_restarts(mut self, restarts: u64) -> Self {
+        self.failing_restarts = restarts;
+        self
+    }
+
+    /// Set how long a unit can go without logging before it is `Silent`
+    pub fn silent_after(mut self, duration: Duration) -> Self {
+        self.silent_after = duration;
+        self
+    }
+
+    /// Normalize hostnames with an alias map
+    pub fn host_aliases(mut self, aliases: HostAliases) -> Self {
+        self.host_aliases = Some(aliases);
+        self
+    }
+
+    /// Classify a unit from its numbers
+    fn classify(&self, unit: &UnitHealth, ended_failed: bool) -> Health {
+        if ended_failed
+            || unit.coredumps > 0
+            || unit.restarts >= self.failing_restarts
+            || (unit.entries > 0 && unit.error_rate >= self.failing_error_rate)
+        {
+            Health::Failing
+        } else if unit.restarts > 0
+            || unit.crashes > 0
+            || (unit.entries > 0 && unit.error_rate >= self.degraded_error_rate)
+        {
+            Health::Degraded
+        } else if unit.since_last_log.is_none_or(|since| since > self.silent_after) {
+            Health::Silent
+        } else {
+            Health::Healthy
+        }
+    }
+}
+
+/// Report the health of every unit on every host over a window
+///
+/// Hosts and units come from [`crate::discover_services`], so units that did not
+/// log in the window are reported too. For each of them:
+/// - entries and errors are counted from the unit's own entries in the window,
+/// - restarts, crashes and failures come from the service manager's messages
+///   about the unit, as in [`crate::unit_lifecycle`],
+/// - core dumps are systemd-coredump's messages naming the unit (`COREDUMP_UNIT`),
+/// - the last log time of a unit that did not log in the window is looked up
+///   before the window.
+///
+/// The service manager's and systemd-coredump's messages are matched to hosts by
+/// machine ID, or by hostname for hosts whose entries carry no machine ID.
+///
+/// # Returns
+/// The report, or `JournalError::InvalidArgument` if the window ends before it starts
+pub fn health_report<S: JournalSource + ?Sized>(source: &S, options: &HealthOptions) -> Result<HealthReport> {
+    let (start, end) = (options.start_time_utc, options.end_time_utc);
+    if end < start {
+        return Err(JournalError::InvalidArgument);
+    }
+    let aliases = options.host_aliases.clone().unwrap_or_default();
+    let discovery = DiscoveryOptions::new().host_aliases(aliases.clone());
+
+    let hosts = discover_services_with_options(source, &discovery, &mut ScanControl::new())?;
+    let window = discover_services_with_options(
+        source,
+        &discovery.clone().unit_stats(true).filter(Query::new(start, end)),
+        &mut ScanControl::new(),
+    )?;
+
+    // Units that did not log in the window get their last log time from one
+    // read of the entries before it
+    let quiet = hosts.hosts.iter().any(|host| {
+        let in_window = window.hosts.iter().find(|other| other.key() == host.key());
+        host.units.iter().any(|unit| in_window.and_then(|other| other.stats(unit)).is_none())
+    });
+    let before = match quiet {
+        true => Some(discover_services_with_options(
+            source,
+            &discovery.unit_stats(true).filter(Query::new(0, start)),
+            &mut ScanControl::new(),
+        )?),
+        false => None,
+    };
+    let coredumps = coredump_counts(source, start, end, &aliases)?;
+
+    // One lifecycle read per unit over the window, shared by the hosts running it
+    let journal = source.open_journal()?;
+    let mut timelines: HashMap<&str, UnitTimeline> = HashMap::new();
+    for unit in hosts.all_units() {
+        timelines.insert(unit, unit_lifecycle_between(&journal, unit, start, end)?);
+    }
+
+    let mut report = Vec::new();
+    for host in &hosts.hosts {
+        let in_window = window.hosts.iter().find(|other| other.key() == host.key());
+        let before_window = before
+            .as_ref()
+            .and_then(|before| before.hosts.iter().find(|other| other.key() == host.key()));
+        let mut units = Vec::new();
+        for unit in &host.units {
+            let stats = in_window.and_then(|other| other.stats(unit));
+            let last_log_utc = stats
+                .or_else(|| before_window.and_then(|other| other.stats(unit)))
+                .map(|stats| stats.last_seen_utc);
+
+            let mut timeline = timelines.get(unit.as_str()).cloned().unwrap_or_else(|| UnitTimeline {
+                unit: unit.clone(),
+                events: Vec::new(),
+            });
+            timeline = match &host.machine_id {
+                Some(machine_id) => timeline.for_machine(machine_id),
+                None => {
+                    timeline.events.retain(|event| {
+                        event.hostname.as_deref().is_some_and(|hostname| {
+                            let hostname = aliases.canonical(hostname);
+                            host.hostnames.iter().any(|seen| seen.hostname == hostname)
+                        })
+                    });
+                    timeline
+                }
+            };
+            let ended_failed = timeline.events.iter().rev().find_map(|event| match event.kind {
+                LifecycleKind::Failed { .. } | LifecycleKind::StartFailed { .. } => Some(true),
+                LifecycleKind::Started | LifecycleKind::Succeeded => Some(false),
+                _ => None,
+            });
+
+            let entries = stats.map_or(0, |stats| stats.entries);
+            let errors = stats.map_or(0, |stats| stats.at_or_above(3));
+            let mut health = UnitHealth {
+                unit: unit.clone(),
+                entries,
+                errors,
+                error_rate: if entries == 0 { 0.0 } else { errors as f64 / entries as f64 },
+                restarts: timeline.restarts().count() as u64,
+                crashes: crashes(&timeline),
+                coredumps: coredumps.count(host, unit),
+                last_failure: timeline.last_failure().map(str::to_string),
+                last_log_utc,
+                since_last_log: last_log_utc.map(|last| Duration::from_micros(end.saturating_sub(last))),
+                health: Health::Healthy,
+            };
+            health.health = options.classify(&health, ended_failed.unwrap_or(false));
+            units.push(health);
+        }
+        report.push(HostHealth {
+            hostname: host.hostname.clone(),
+            machine_id: host.machine_id.clone(),
+            units,
+        });
+    }
+
+    Ok(HealthReport {
+        start_time_utc: start,
+        end_time_utc: end,
+        hosts: report,
+    })
+}
+
+/// Count the main processes that exited unsuccessfully
+fn crashes(timeline: &UnitTimeline) -> u64 {
+    timeline
+        .events
+        .iter()
+        .filter(|event| match &event.kind {
+            LifecycleKind::ProcessExited { command, exit_code, exit_status } => {
+                command.as_deref().is_none_or(|command| command == "ExecStart")
+                    && (exit_code.as_deref().is_some_and(|code| code != "exited")
+                        || exit_status.is_some_and(|status| status != 0))
+            }
+            _ => false,
+        })
+        .count() as u64
+}
+
+/// systemd-coredump's messages in the window, counted per unit and per
+/// machine ID or, without one, per canonical hostname
+#[derive(Default)]
+struct CoredumpCounts {
+    by_machine: HashMap<(String, String), u64>,
+    by_hostname: HashMap<(String, String), u64>,
+}
+
+impl CoredumpCounts {
+    /// Core dumps of `unit` on `host`, matched as in [`health_report`]
+    fn count(&self, host: &Host, unit: &str) -> u64 {
+        match &host.machine_id {
+            Some(machine_id) => self.by_machine.get(&(machine_id.clone(), unit.to_string())).copied().unwrap_or(0),
+            None => host
+                .hostnames
+                .iter()
+                .filter_map(|seen| self.by_hostname.get(&(seen.hostname.clone(), unit.to_string())))
+                .sum(),
+        }
+    }
+}
+
+/// Count systemd-coredump's messages in the window
+fn coredump_counts<S: JournalSource + ?Sized>(
+    source: &S,
+    start: u64,
+    end: u64,
+    aliases: &HostAliases,
+) -> Result<CoredumpCounts> {
+    let query = Query::new(start, end).add_match("MESSAGE_ID", COREDUMP_MESSAGE_ID);
+    let aggregation = Aggregation::new()
+        .group_by("_MACHINE_ID")
+        .group_by("_HOSTNAME")
+        .group_by("COREDUMP_UNIT");
+
+    let mut counts = CoredumpCounts::default();
+    for row in aggregate(source, query, &aggregation)? {
+        let [machine_id, hostname, Some(unit)] = &row.group[..] else {
+            continue;
+        };
+        if let Some(machine_id) = machine_id {
+            *counts.by_machine.entry((machine_id.clone(), unit.clone())).or_insert(0) += row.count;
+        }
+        if let Some(hostname) = hostname {
+            let key = (aliases.canonical(hostname).to_string(), unit.clone());
+            *counts.by_hostname.entry(key).or_insert(0) += row.count;
+        }
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(entries: u64, errors: u64, restarts: u64, since_last_log: Option<Duration>) -> UnitHealth {
+        UnitHealth {
+            unit: "app.service".to_string(),
+            entries,
+            errors,
+            error_rate: if entries == 0 { 0.0 } else { errors as f64 / entries as f64 },
+            restarts,
+            crashes: 0,
+            coredumps: 0,
+            last_failure: None,
+            last_log_utc: since_last_log.map(|_| 0),
+            since_last_log,
+            health: Health::Healthy,
+        }
+    }
+
+    #[test]
+    fn test_classification() {
+        let options = HealthOptions::new(0, 0).silent_after(Duration::from_secs(3600));
+        let recent = Some(Duration::from_secs(60));
+
+        assert_eq!(options.classify(&unit(100, 0, 0, recent), false), Health::Healthy);
+        assert_eq!(options.classify(&unit(100, 5, 0, recent), false), Health::Degraded);
+        assert_eq!(options.classify(&unit(100, 50, 0, recent), false), Health::Failing);
+        assert_eq!(options.classify(&unit(100, 0, 1, recent), false), Health::Degraded);
+        assert_eq!(options.classify(&unit(100, 0, 3, recent), false), Health::Failing);
+        assert_eq!(options.classify(&unit(100, 0, 0, recent), true), Health::Failing);
+        assert_eq!(options.classify(&unit(0, 0, 0, Some(Duration::from_secs(7200))), false), Health::Silent);
+        assert_eq!(options.classify(&unit(0, 0, 0, None), false), Health::Silent);
+
+        let mut crashed = unit(100, 0, 0, recent);
+        crashed.crashes = 1;
+        assert_eq!(options.classify(&crashed, false), Health::Degraded);
+        crashed.coredumps = 1;
+        assert_eq!(options.classify(&crashed, false), Health::Failing);
+    }
+
+    fn report() -> HealthReport {
+        let mut failing = unit(10, 1, 4, Some(Duration::from_micros(1500)));
+        failing.unit = "api \"v2\".service".to_string();
+        failing.last_failure = Some("exit-code".to_string());
+        failing.health = Health::Failing;
+        HealthReport {
+            start_time_utc: 100,
+            end_time_utc: 200,
+            hosts: vec![HostHealth {
+                hostname: "web1".to_string(),
+                machine_id: None,
+                units: vec![unit(0, 0, 0, None), failing],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_report_summary() {
+        let report = report();
+        assert_eq!(report.health(), Health::Failing);
+        assert_eq!(report.unhealthy().len(), 1);
+        assert_eq!(report.find("web1", "app.service").map(|unit| unit.entries), Some(0));
+        assert_eq!(report.find("web2", "app.service"), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_report_json() {
+        assert_eq!(
+            report().to_json(),
+            concat!(
+                r#"{"schema_version":1,"start_time_utc":100,"end_time_utc":200,"hosts":[{"hostname":"web1","machine_id":null,"units":["#,
+                r#"{"unit":"app.service","entries":0,"errors":0,"error_rate":0.0,"restarts":0,"crashes":0,"coredumps":0,"#,
+                r#""last_failure":null,"last_log_utc":null,"since_last_log":null,"health":"healthy"},"#,
+                r#"{"unit":"api \"v2\".service","entries":10,"errors":1,"error_rate":0.1,"restarts":4,"crashes":0,"coredumps":0,"#,
+                r#""last_failure":"exit-code","last_log_utc":0,"since_last_log":1500,"health":"failing"}]}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_reversed_window_rejected() {
+        let result = health_report("/nonexistent", &HealthOptions::new(200, 100));
+        assert_eq!(result, Err(JournalError::InvalidArgument));
+    }
+}
@@ -0,0 +1,795 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use crate::aggregate::{aggregate, bucket_start, AggregateCount, Aggregation};
+use crate::error::{JournalError, Result};
+use crate::journal::Journal;
+use crate::query::{field_value, pin_boot, query_journal, receive_range, Entry, Query, TimestampSource, UnitMatch};
+use crate::source::{JournalFiles, JournalSource};
+
+/// Marks an index file, with the version of its format
+const MAGIC: &[u8; 8] = b"JQINDEX1";
+
+/// Fields the index counts entries by
+const INDEXED_FIELDS: [&str; 3] = ["_HOSTNAME", "_SYSTEMD_UNIT", "PRIORITY"];
+
+/// Bloom filter bits per distinct trigram and hash functions per trigram,
+/// for about 1% false positives
+const BITS_PER_TRIGRAM: usize = 10;
+const HASHES: u32 = 7;
+
+/// Most hash functions per trigram an index file may ask for
+const MAX_HASHES: u32 = 32;
+
+/// An on-disk index of journal files for fast historical search
+///
+/// For each journal file the index holds entry counts per time bucket,
+/// host (`_HOSTNAME`), unit (`_SYSTEMD_UNIT`) and priority (`PRIORITY`),
+/// and a bloom filter of the three-byte sequences in its messages.
+/// Queries use it to skip files that cannot match the query's time range or
+/// `message_contains` term, and aggregations answer counts by those fields
+/// from it without reading the journal.
+///
+/// The index is only consulted for sources wrapped with
+/// [`JournalIndex::source`], which [`crate::query_journal`], [`crate::aggregate`]
+/// and [`crate::QueryCache`] then use like any other source.
+/// [`JournalIndex::query_journal`] and [`JournalIndex::aggregate`] are
+/// shorthands for that.
+///
+/// The index is built incrementally: [`JournalIndex::update`] only reads
+/// files that are new or have changed since they were indexed. Files that
+/// are not indexed, or have changed since, are always read, so results are
+/// the same as without the index. An index belongs to one source, since
+/// files are identified by the paths the source reports.
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{Aggregation, JournalIndex, Query};
+/// use std::path::Path;
+///
+/// let journal_dir = Path::new("/var/log/journal");
+/// let mut index = JournalIndex::open("/var/cache/journal.index")?;
+/// index.update(journal_dir)?;
+/// index.save()?;
+///
+/// let journal = index.source(journal_dir);
+/// let query = Query::all().message_contains("segfault");
+/// let entries = journald_query::query_journal(&journal, query)?;
+///
+/// let per_unit = Aggregation::new().group_by("_SYSTEMD_UNIT");
+/// let counts = journald_query::aggregate(&journal, Query::all().priority(3), &per_unit)?;
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalIndex {
+    path: PathBuf,
+    bucket_width: u64,
+    files: BTreeMap<PathBuf, FileIndex>,
+}
+
+/// What the index knows about one journal file
+#[derive(Debug, Clone, PartialEq)]
+struct FileIndex {
+    size: u64,
+    modified: Option<Duration>,
+    entries: u64,
+    first_utc: u64,
+    last_utc: u64,
+    counts: Vec<(CountKey, u64)>,
+    bloom: Bloom,
+}
+
+/// The time bucket and indexed field values a count applies to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct CountKey {
+    bucket: u64,
+    hostname: Option<String>,
+    unit: Option<String>,
+    priority: Option<String>,
+}
+
+/// A source read with the help of a [`JournalIndex`], see [`JournalIndex::source`]
+#[derive(Debug, Clone, Copy)]
+pub struct Indexed<'a, S> {
+    source: S,
+    index: &'a JournalIndex,
+}
+
+impl<S: JournalSource> JournalSource for Indexed<'_, S> {
+    fn open_journal(&self) -> Result<Journal> {
+        self.source.open_journal()
+    }
+
+    fn journal_files(&self) -> Result<Vec<PathBuf>> {
+        self.source.journal_files()
+    }
+
+    fn has_duplicates(&self) -> bool {
+        self.source.has_duplicates()
+    }
+
+    fn index(&self) -> Option<&JournalIndex> {
+        Some(self.index)
+    }
+}
+
+/// A bloom filter over byte strings
+#[derive(Debug, Clone, PartialEq)]
+struct Bloom {
+    bits: Vec<u64>,
+    hashes: u32,
+}
+
+impl JournalIndex {
+    /// Load the index stored at `path`, or start an empty one if there is none
+    ///
+    /// Time buckets are one minute wide unless set with [`JournalIndex::bucket_width`].
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        match File::open(&path) {
+            Ok(file) => read_index(path, &mut BufReader::new(file)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(JournalIndex {
+                path,
+                bucket_width: 60_000_000,
+                files: BTreeMap::new(),
+            }),
+            Err(_) => Err(JournalError::IoError),
+        }
+    }
+
+    /// Set the width of the time buckets entries are counted in
+    ///
+    /// Aggregations can be answered from the index when their time range
+    /// and bucket width are multiples of this width. Changing it drops the
+    /// files indexed so far, so they are read again by the next update.
+    pub fn bucket_width(mut self, width: Duration) -> Self {
+        let width = u64::try_from(width.as_micros()).unwrap_or(u64::MAX).max(1);
+        if width != self.bucket_width {
+            self.bucket_width = width;
+            self.files.clear();
+        }
+        self
+    }
+
+    /// Index the source's journal files that are new or have changed, and
+    /// forget files that are gone
+    ///
+    /// Files that cannot be read are left out of the index.
+    ///
+    /// # Returns
+    /// The number of files read
+    pub fn update<S: JournalSource + ?Sized>(&mut self, source: &S) -> Result<usize> {
+        let files = source.journal_files()?;
+        let present: HashSet<&PathBuf> = files.iter().collect();
+        self.files.retain(|path, _| present.contains(path));
+
+        let mut indexed = 0;
+        for path in &files {
+            if self.files.get(path).is_some_and(|file| file.is_current(path)) {
+                continue;
+            }
+            match index_file(path, self.bucket_width)? {
+                Some(file) => {
+                    self.files.insert(path.clone(), file);
+                    indexed += 1;
+                }
+                None => {
+                    self.files.remove(path);
+                }
+            }
+        }
+        Ok(indexed)
+    }
+
+    /// Write the index to its path
+    ///
+    /// The index is written to a temporary file first, so readers never see
+    /// a partly written index.
+    pub fn save(&self) -> Result<()> {
+        let mut partial = self.path.clone().into_os_string();
+        partial.push(".tmp");
+        let partial = PathBuf::from(partial);
+
+        let file = File::create(&partial).map_err(|_| JournalError::IoError)?;
+        let mut writer = BufWriter::new(file);
+        write_index(self, &mut writer)
+            .and_then(|_| writer.flush())
+            .map_err(|_| JournalError::IoError)?;
+        fs::rename(&partial, &self.path).map_err(|_| JournalError::IoError)
+    }
+
+    /// The journal files in the index
+    pub fn indexed_files(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Read `source` with the help of this index
+    ///
+    /// Queries and aggregations on the returned source return the same
+    /// results as on `source` itself, see [`JournalIndex::query_journal`]
+    /// and [`JournalIndex::aggregate`] for how the index is used.
+    pub fn source<S: JournalSource>(&self, source: S) -> Indexed<'_, S> {
+        Indexed { source, index: self }
+    }
+
+    /// Query journal entries, skipping files the index rules out
+    ///
+    /// Returns the same entries as [`crate::query_journal`]. Files whose
+    /// entries are all outside the query's time range, or whose messages
+    /// cannot contain the `message_contains` term, are not read. Queries
+    /// with `context` read every file, since context can cross files.
+    pub fn query_journal<S: JournalSource + ?Sized>(&self, source: &S, query: Query) -> Result<Vec<Entry>> {
+        query_journal(&self.source(source), query)
+    }
+
+    /// Count journal entries, from the index where possible
+    ///
+    /// Returns the same counts as [`crate::aggregate`]. Counts come from the
+    /// index without reading the journal when:
+    /// - the aggregation groups by `_HOSTNAME`, `_SYSTEMD_UNIT` and `PRIORITY` only,
+    ///   and its bucket width is a multiple of the index's;
+    /// - the query filters by `hostname`, `unit` (matched exactly) and `priority` only;
+    /// - the query's time range starts and ends on bucket boundaries and is
+    ///   compared with receive times.
+    ///
+    /// Files that are not indexed or have changed since are read, and other
+    /// aggregations run on the files the index does not rule out.
+    pub fn aggregate<S: JournalSource + ?Sized>(
+        &self,
+        source: &S,
+        query: Query,
+        aggregation: &Aggregation,
+    ) -> Result<Vec<AggregateCount>> {
+        aggregate(&self.source(source), query, aggregation)
+    }
+
+    /// The files of `source` that can hold entries the query selects
+    ///
+    /// # Returns
+    /// The files, or `None` for queries with `context`, which can cross files.
+    /// Boot offsets in the query are pinned to boot IDs first, since they
+    /// count the boots of the whole source.
+    pub(crate) fn files_for<S: JournalSource + ?Sized>(
+        &self,
+        source: &S,
+        query: &mut Query,
+    ) -> Result<Option<JournalFiles>> {
+        if query.context.is_some() {
+            return Ok(None);
+        }
+        pin_boot(source, query)?;
+        let files = source
+            .journal_files()?
+            .into_iter()
+            .filter(|path| self.may_match(path, query))
+            .collect();
+        Ok(Some(JournalFiles { files, duplicates: source.has_duplicates() }))
+    }
+
+    /// Count journal entries from the index, reading only the files that are
+    /// not indexed or have changed since
+    ///
+    /// # Returns
+    /// The counts, or `None` if the index cannot answer the aggregation
+    pub(crate) fn counts<S: JournalSource + ?Sized>(
+        &self,
+        source: &S,
+        query: &Query,
+        aggregation: &Aggregation,
+    ) -> Result<Option<Vec<AggregateCount>>> {
+        if source.has_duplicates() || !self.answers(query, aggregation) {
+            return Ok(None);
+        }
+        let width = aggregation.bucket.map(|width| u64::try_from(width.as_micros()).unwrap_or(u64::MAX));
+
+        let mut counts: BTreeMap<(Option<u64>, Vec<Option<String>>), u64> = BTreeMap::new();
+        let mut unindexed = Vec::new();
+        for path in source.journal_files()? {
+            let Some(file) = self.files.get(&path).filter(|file| file.is_current(&path)) else {
+                unindexed.push(path);
+                continue;
+            };
+            for (key, count) in &file.counts {
+                if !key.matches(query) {
+                    continue;
+                }
+                let group = aggregation.group_by.iter().map(|field| key.field(field).cloned()).collect();
+                let bucket = width.map(|width| bucket_start(key.bucket, width));
+                *counts.entry((bucket, group)).or_insert(0) += count;
+            }
+        }
+
+        if !unindexed.is_empty() {
+            let files = JournalFiles { files: unindexed, duplicates: false };
+            for row in aggregate(&files, query.clone(), aggregation)? {
+                *counts.entry((row.bucket_start_utc, row.group)).or_insert(0) += row.count;
+            }
+        }
+
+        Ok(Some(counts
+            .into_iter()
+            .map(|((bucket_start_utc, group), count)| AggregateCount {
+                group,
+                bucket_start_utc,
+                count,
+            })
+            .collect()))
+    }
+
+    /// Whether the file can hold entries the query selects
+    fn may_match(&self, path: &Path, query: &Query) -> bool {
+        let Some(file) = self.files.get(path).filter(|file| file.is_current(path)) else {
+            return true;
+        };
+        if file.entries == 0 {
+            return false;
+        }
+        if let Some((start, end)) = receive_range(query) {
+            if file.last_utc < start || file.first_utc > end {
+                return false;
+            }
+        }
+        match &query.message_contains {
+            Some(term) => term.as_bytes().windows(3).all(|trigram| file.bloom.contains(trigram)),
+            None => true,
+        }
+    }
+
+    /// Whether the index alone can answer the aggregation
+    fn answers(&self, query: &Query, aggregation: &Aggregation) -> bool {
+        let width = self.bucket_width;
+        let aligned_start = query.start_time_utc.is_multiple_of(width);
+        let aligned_end = query.end_time_utc == u64::MAX || (query.end_time_utc + 1).is_multiple_of(width);
+        let indexed_groups = aggregation.group_by.iter().all(|field| INDEXED_FIELDS.contains(&field.as_str()));
+        let aligned_buckets = aggregation.bucket.is_none_or(|bucket| {
+            let bucket = u64::try_from(bucket.as_micros()).unwrap_or(u64::MAX);
+            bucket > 0 && bucket.is_multiple_of(width)
+        });
+        let indexed_filters = query.user_units.is_empty()
+            && ((query.unit.is_none() && query.units.is_empty()) || query.unit_match == UnitMatch::Exact)
+            && query.matches.is_empty()
+            && query.identifiers.is_empty()
+            && query.message_contains.is_none()
+            && query.grep.is_none()
+            && query.boot.is_none()
+            && query.boot_window.is_none()
+            && query.timestamp_source == TimestampSource::Receive;
+
+        aligned_start && aligned_end && indexed_groups && aligned_buckets && indexed_filters
+    }
+}
+
+impl FileIndex {
+    /// Whether the file is unchanged since it was indexed
+    fn is_current(&self, path: &Path) -> bool {
+        fs::metadata(path).is_ok_and(|metadata| {
+            metadata.len() == self.size && modified_since_epoch(&metadata) == self.modified
+        })
+    }
+}
+
+impl CountKey {
+    fn field(&self, field: &str) -> Option<&String> {
+        match field {
+            "_HOSTNAME" => self.hostname.as_ref(),
+            "_SYSTEMD_UNIT" => self.unit.as_ref(),
+            "PRIORITY" => self.priority.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Whether the entries counted under this key pass the query's filters
+    fn matches(&self, query: &Query) -> bool {
+        let in_range = self.bucket >= query.start_time_utc && self.bucket <= query.end_time_utc;
+        let hostname = match (&query.hostname, &query.host_aliases) {
+            (None, _) => true,
+            (Some(hostname), Some(aliases)) => {
+                self.hostname.as_deref().is_some_and(|name| aliases.same_host(name, hostname))
+            }
+            (Some(_), None) => query.hostname == self.hostname,
+        };
+        let unit = (query.unit.is_none() && query.units.is_empty())
+            || self.unit.as_ref().is_some_and(|unit| query.unit.as_ref() == Some(unit) || query.units.contains(unit));
+        let priority = query.priority.is_none_or(|(most, least)| {
+            (most..=least.min(7)).any(|level| self.priority.as_deref() == Some(level.to_string().as_str()))
+        });
+        in_range && hostname && unit && priority
+    }
+}
+
+impl Bloom {
+    fn with_capacity(items: usize) -> Self {
+        let words = (items * BITS_PER_TRIGRAM).div_ceil(64).max(1);
+        Bloom {
+            bits: vec![0; words],
+            hashes: HASHES,
+        }
+    }
+
+    fn insert(&mut self, item: &[u8]) {
+        for bit in self.positions(item) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    fn contains(&self, item: &[u8]) -> bool {
+        self.positions(item).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Bit positions for an item, by double hashing
+    fn positions(&self, item: &[u8]) -> impl Iterator<Item = usize> {
+        let hash = fnv1a(item);
+        let step = hash.rotate_left(32) | 1;
+        let bits = self.bits.len() as u64 * 64;
+        (0..u64::from(self.hashes)).map(move |i| (hash.wrapping_add(i.wrapping_mul(step)) % bits) as usize)
+    }
+}
+
+/// 64-bit FNV-1a, which unlike the standard library's hasher is stable
+/// across releases, so filters can be stored
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn modified_since_epoch(metadata: &fs::Metadata) -> Option<Duration> {
+    metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()
+}
+
+/// Read a journal file into a file index, or `None` if it cannot be read
+fn index_file(path: &Path, bucket_width: u64) -> Result<Option<FileIndex>> {
+    // Taken before reading, so entries written meanwhile are caught next time
+    let Ok(metadata) = fs::metadata(path) else {
+        return Ok(None);
+    };
+    let Ok(journal) = Journal::open_files(vec![path]) else {
+        return Ok(None);
+    };
+
+    let mut counts: HashMap<CountKey, u64> = HashMap::new();
+    let mut trigrams = HashSet::new();
+    let mut entries = 0;
+    let (mut first_utc, mut last_utc) = (u64::MAX, 0);
+
+    journal.seek_head()?;
+    while journal.next()? {
+        let received = journal.get_realtime_usec()?;
+        entries += 1;
+        first_utc = first_utc.min(received);
+        last_utc = last_utc.max(received);
+
+        let key = CountKey {
+            bucket: bucket_start(received, bucket_width),
+            hostname: field_value(&journal, "_HOSTNAME")?,
+            unit: field_value(&journal, "_SYSTEMD_UNIT")?,
+            priority: field_value(&journal, "PRIORITY")?,
+        };
+        *counts.entry(key).or_insert(0) += 1;
+
+        if let Some(message) = field_value(&journal, "MESSAGE")? {
+            trigrams.extend(message.as_bytes().windows(3).map(|trigram| [trigram[0], trigram[1], trigram[2]]));
+        }
+    }
+
+    let mut bloom = Bloom::with_capacity(trigrams.len());
+    for trigram in &trigrams {
+        bloom.insert(trigram);
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort();
+
+    Ok(Some(FileIndex {
+        size: metadata.len(),
+        modified: modified_since_epoch(&metadata),
+        entries,
+        first_utc: first_utc.min(last_utc),
+        last_utc,
+        counts,
+        bloom,
+    }))
+}
+
+fn write_index<W: Write>(index: &JournalIndex, out: &mut W) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    write_u64(out, index.bucket_width)?;
+    write_u64(out, index.files.len() as u64)?;
+    for (path, file) in &index.files {
+        write_bytes(out, path.as_os_str().as_bytes())?;
+        write_u64(out, file.size)?;
+        match file.modified {
+            Some(modified) => {
+                out.write_all(&[1])?;
+                write_u64(out, modified.as_secs())?;
+                write_u64(out, u64::from(modified.subsec_nanos()))?;
+            }
+            None => out.write_all(&[0])?,
+        }
+        write_u64(out, file.entries)?;
+        write_u64(out, file.first_utc)?;
+        write_u64(out, file.last_utc)?;
+
+        write_u64(out, file.counts.len() as u64)?;
+        for (key, count) in &file.counts {
+            write_u64(out, key.bucket)?;
+            for value in [&key.hostname, &key.unit, &key.priority] {
+                write_optional(out, value)?;
+            }
+            write_u64(out, *count)?;
+        }
+
+        write_u64(out, u64::from(file.bloom.hashes))?;
+        write_u64(out, file.bloom.bits.len() as u64)?;
+        for word in &file.bloom.bits {
+            write_u64(out, *word)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_index<R: Read>(path: PathBuf, input: &mut R) -> Result<JournalIndex> {
+    let mut magic = [0; 8];
+    read_exact(input, &mut magic)?;
+    if &magic != MAGIC {
+        return Err(JournalError::InvalidIndex("not an index file, or an unsupported version".to_string()));
+    }
+
+    let bucket_width = read_u64(input)?;
+    if bucket_width == 0 {
+        return Err(JournalError::InvalidIndex("zero bucket width".to_string()));
+    }
+    let mut files = BTreeMap::new();
+    for _ in 0..read_u64(input)? {
+        let file_path = PathBuf::from(std::ffi::OsStr::from_bytes(&read_bytes(input)?));
+        let size = read_u64(input)?;
+        let modified = match read_u8(input)? {
+            0 => None,
+            _ => {
+                let secs = read_u64(input)?;
+                let nanos = u32::try_from(read_u64(input)?)
+                    .ok()
+                    .filter(|nanos| *nanos < 1_000_000_000)
+                    .ok_or_else(|| JournalError::InvalidIndex("invalid modification time".to_string()))?;
+                Some(Duration::new(secs, nanos))
+            }
+        };
+        let entries = read_u64(input)?;
+        let first_utc = read_u64(input)?;
+        let last_utc = read_u64(input)?;
+
+        let mut counts = Vec::new();
+        for _ in 0..read_u64(input)? {
+            let key = CountKey {
+                bucket: read_u64(input)?,
+                hostname: read_optional(input)?,
+                unit: read_optional(input)?,
+                priority: read_optional(input)?,
+            };
+            counts.push((key, read_u64(input)?));
+        }
+
+        let hashes = u32::try_from(read_u64(input)?)
+            .ok()
+            .filter(|hashes| (1..=MAX_HASHES).contains(hashes))
+            .ok_or_else(|| JournalError::InvalidIndex("invalid bloom filter hash count".to_string()))?;
+        let words = read_u64(input)?;
+        if words == 0 {
+            return Err(JournalError::InvalidIndex("empty bloom filter".to_string()));
+        }
+        let bits = (0..words).map(|_| read_u64(input)).collect::<Result<_>>()?;
+
+        files.insert(file_path, FileIndex {
+            size,
+            modified,
+            entries,
+            first_utc,
+            last_utc,
+            counts,
+            bloom: Bloom { bits, hashes },
+        });
+    }
+
+    Ok(JournalIndex { path, bucket_width, files })
+}
+
+pub(crate) fn write_u64<W: Write>(out: &mut W, value: u64) -> io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+pub(crate) fn write_bytes<W: Write>(out: &mut W, bytes: &[u8]) -> io::Result<()> {
+    write_u64(out, bytes.len() as u64)?;
+    out.write_all(bytes)
+}
+
+pub(crate) fn write_optional<W: Write>(out: &mut W, value: &Option<String>) -> io::Result<()> {
+    match value {
+        Some(value) => {
+            out.write_all(&[1])?;
+            write_bytes(out, value.as_bytes())
+        }
+        None => out.write_all(&[0]),
+    }
+}
+
+fn read_exact<R: Read>(input: &mut R, buffer: &mut [u8]) -> Result<()> {
+    input.read_exact(buffer).map_err(|error| match error.kind() {
+        io::ErrorKind::UnexpectedEof => JournalError::InvalidIndex("truncated".to_string()),
+        _ => JournalError::IoError,
+    })
+}
+
+pub(crate) fn read_u8<R: Read>(input: &mut R) -> Result<u8> {
+    let mut buffer = [0; 1];
+    read_exact(input, &mut buffer)?;
+    Ok(buffer[0])
+}
+
+pub(crate) fn read_u64<R: Read>(input: &mut R) -> Result<u64> {
+    let mut buffer = [0; 8];
+    read_exact(input, &mut buffer)?;
+    Ok(u64::from_le_bytes(buffer))
+}
+
+pub(crate) fn read_bytes<R: Read>(input: &mut R) -> Result<Vec<u8>> {
+    let len = read_u64(input)?;
+    let mut bytes = Vec::new();
+    input.take(len).read_to_end(&mut bytes).map_err(|_| JournalError::IoError)?;
+    if bytes.len() as u64 != len {
+        return Err(JournalError::InvalidIndex("truncated".to_string()));
+    }
+    Ok(bytes)
+}
+
+pub(crate) fn read_optional<R: Read>(input: &mut R) -> Result<Option<String>> {
+    match read_u8(input)? {
+        0 => Ok(None),
+        _ => String::from_utf8(read_bytes(input)?)
+            .map(Some)
+            .map_err(|_| JournalError::InvalidIndex("field value is not UTF-8".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(bucket: u64, hostname: &str, unit: &str, priority: &str) -> CountKey {
+        CountKey {
+            bucket,
+            hostname: Some(hostname.to_string()),
+            unit: Some(unit.to_string()),
+            priority: Some(priority.to_string()),
+        }
+    }
+
+    fn sample_index() -> JournalIndex {
+        let mut bloom = Bloom::with_capacity(16);
+        for trigram in b"Connection refused".windows(3) {
+            bloom.insert(trigram);
+        }
+        let mut files = BTreeMap::new();
+        files.insert(PathBuf::from("/var/log/journal/system.journal"), FileIndex {
+            size: 8388608,
+            modified: Some(Duration::new(1640995200, 500)),
+            entries: 3,
+            first_utc: 1640995200000000,
+            last_utc: 1640995290000000,
+            counts: vec![
+                (key(1640995200000000, "web-server", "nginx.service", "6"), 2),
+                (CountKey { bucket: 1640995260000000, hostname: None, unit: None, priority: None }, 1),
+            ],
+            bloom,
+        });
+        JournalIndex {
+            path: PathBuf::from("/var/cache/journal.index"),
+            bucket_width: 60_000_000,
+            files,
+        }
+    }
+
+    #[test]
+    fn test_bloom_filter() {
+        let mut bloom = Bloom::with_capacity(100);
+        for trigram in b"Failed to start nginx.service".windows(3) {
+            bloom.insert(trigram);
+        }
+        assert!(b"nginx".windows(3).all(|trigram| bloom.contains(trigram)));
+        assert!(!b"postgres".windows(3).all(|trigram| bloom.contains(trigram)));
+    }
+
+    #[test]
+    fn test_fnv1a_is_stable() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let index = sample_index();
+        let mut bytes = Vec::new();
+        write_index(&index, &mut bytes).unwrap();
+        let read = read_index(index.path.clone(), &mut bytes.as_slice()).unwrap();
+        assert_eq!(read, index);
+
+        assert!(matches!(
+            read_index(index.path.clone(), &mut &bytes[..bytes.len() - 1]),
+            Err(JournalError::InvalidIndex(_))
+        ));
+        assert!(matches!(
+            read_index(index.path.clone(), &mut &b"NOTINDEX"[..]),
+            Err(JournalError::InvalidIndex(_))
+        ));
+
+        // Nanoseconds past a whole second would carry into the seconds
+        let mtime = [1640995200u64.to_le_bytes(), 500u64.to_le_bytes()].concat();
+        let at = bytes.windows(16).position(|window| window == mtime).unwrap() + 8;
+        for nanos in [1_000_000_000, u64::from(u32::MAX) + 1, u64::MAX] {
+            let mut corrupt = bytes.clone();
+            corrupt[at..at + 8].copy_from_slice(&nanos.to_le_bytes());
+            corrupt[at - 8..at].copy_from_slice(&u64::MAX.to_le_bytes());
+            assert!(matches!(
+                read_index(index.path.clone(), &mut corrupt.as_slice()),
+                Err(JournalError::InvalidIndex(_))
+            ));
+        }
+
+        // A hash count of zero would match everything, a huge one would hang lookups
+        let words = index.files.values().next().unwrap().bloom.bits.len() as u64;
+        let bloom = [u64::from(HASHES).to_le_bytes(), words.to_le_bytes()].concat();
+        let at = bytes.windows(16).position(|window| window == bloom).unwrap();
+        for hashes in [0, u64::from(MAX_HASHES) + 1, u64::from(u32::MAX), u64::from(u32::MAX) + 7] {
+            let mut corrupt = bytes.clone();
+            corrupt[at..at + 8].copy_from_slice(&hashes.to_le_bytes());
+            assert!(matches!(
+                read_index(index.path.clone(), &mut corrupt.as_slice()),
+                Err(JournalError::InvalidIndex(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_count_key_matches() {
+        let key = key(1640995200000000, "web-server", "nginx.service", "3");
+        assert!(key.matches(&Query::all()));
+        assert!(key.matches(&Query::all().hostname("web-server").unit("nginx.service").priority(3)));
+        assert!(!key.matches(&Query::all().hostname("db-server")));
+        assert!(!key.matches(&Query::all().priority(2)));
+        let either = Query { units: vec!["nginx.service".to_string()], ..Query::all().unit("sshd.service") };
+        assert!(key.matches(&either));
+        assert!(!key.matches(&Query { units: vec!["php-fpm.service".to_string()], ..either }));
+        assert!(!key.matches(&Query::new(1640995260000000, u64::MAX)));
+        assert_eq!(key.field("_SYSTEMD_UNIT").map(String::as_str), Some("nginx.service"));
+        assert_eq!(key.field("_COMM"), None);
+    }
+
+    #[test]
+    fn test_answers() {
+        let index = sample_index();
+        let minute = Duration::from_secs(60);
+        let per_unit = Aggregation::new().group_by("_SYSTEMD_UNIT").bucket(minute * 5);
+        assert!(index.answers(&Query::all().hostname("web-server").priority(3), &per_unit));
+        assert!(index.answers(&Query::new(1640995200000000, 1640995259999999), &per_unit));
+
+        // Unaligned ranges and buckets
+        assert!(!index.answers(&Query::new(1640995200000001, u64::MAX), &per_unit));
+        assert!(!index.answers(&Query::new(0, 1640995200000000), &per_unit));
+        assert!(!index.answers(&Query::all(), &Aggregation::new().bucket(Duration::from_secs(90))));
+
+        // Fields and filters the index does not hold
+        assert!(!index.answers(&Query::all(), &Aggregation::new().group_by("_COMM")));
+        assert!(!index.answers(&Query::all().message_contains("error"), &per_unit));
+        assert!(!index.answers(&Query::all().identifier("sshd"), &per_unit));
+        assert!(!index.answers(&Query::all().unit("nginx.service").unit_match(UnitMatch::Journalctl), &per_unit));
+    }
+
+    #[test]
+    fn test_changed_width_drops_files() {
+        let index = sample_index().bucket_width(Duration::from_secs(60));
+        assert_eq!(index.indexed_files().count(), 1);
+        let index = index.bucket_width(Duration::from_secs(300));
+        assert_eq!(index.indexed_files().count(), 0);
+    }
+}
@@ -280,6 +280,46 @@ impl Journal {
         Ok(())
     }
 
+    /// Start a new group of matches, OR'ed with the previous group
+    /// 
+    /// Matches added after this call form an alternative to the ones added
+    /// before it, like `+` on the `journalctl` command line.
+    /// 
+    /// # Examples
+    /// ```no_run
+    /// # use journald_query::Journal;
+    /// # let journal = Journal::open_directory("/var/log/journal")?;
+    /// // Entries from nginx on web-1, or anything from web-2
+    /// journal.add_match("_HOSTNAME", "web-1")?;
+    /// journal.add_match("_SYSTEMD_UNIT", "nginx.service")?;
+    /// journal.add_disjunction()?;
+    /// journal.add_match("_HOSTNAME", "web-2")?;
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn add_disjunction(&self) -> Result<()> {
+        let result = unsafe { ffi::sd_journal_add_disjunction(self.handle) };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
+        
+        Ok(())
+    }
+
+    /// Close the current groups of matches, AND'ing them with what follows
+    /// 
+    /// Use this to combine a set of alternatives added with
+    /// [`Journal::add_disjunction`] with further required matches.
+    pub fn add_conjunction(&self) -> Result<()> {
+        let result = unsafe { ffi::sd_journal_add_conjunction(self.handle) };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
+        
+        Ok(())
+    }
+
     /// Clear all match filters
     /// 
     /// After calling this, all journal entries will be available for iteration.
@@ -322,6 +362,48 @@ impl Journal {
         Ok(())
     }
 
+    /// Seek to the end of the journal
+    /// 
+    /// This positions the read pointer after the last entry.
+    /// Call `previous()` to move back to the last entry.
+    /// 
+    /// # Examples
+    /// ```no_run
+    /// # use journald_query::Journal;
+    /// # let journal = Journal::open_directory("/var/log/journal")?;
+    /// journal.seek_tail()?;
+    /// if journal.previous()? {
+    ///     println!("Last entry at {}", journal.get_realtime_usec()?);
+    /// }
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn seek_tail(&self) -> Result<()> {
+        let result = unsafe {
+            ffi::sd_journal_seek_tail(self.handle)
+        };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
+        
+        Ok(())
+    }
+
+    /// Move back to the previous journal entry
+    /// 
+    /// Returns `true` if there is a previous entry, `false` if at the beginning.
+    pub fn previous(&self) -> Result<bool> {
+        let result = unsafe {
+            ffi::sd_journal_previous(self.handle)
+        };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
+        
+        Ok(result > 0)
+    }
+
     /// Advance to the next journal entry
     /// 
     /// Returns `true` if there is a next entry, `false` if at the end.
@@ -371,6 +453,29 @@ impl Journal {
     /// # Ok::<(), journald_query::JournalError>(())
     /// ```
     pub fn get_field(&self, field: &str) -> Result<Option<String>> {
+        match self.get_field_bytes(field)? {
+            Some(data) => {
+                let data_str = std::str::from_utf8(data)
+                    .map_err(|_| JournalError::InvalidData)?;
+                Ok(Some(data_str.to_string()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Like `get_field()`, but converts data that is not valid UTF-8 lossily
+    /// 
+    /// Values truncated by the data threshold can end mid-character, so
+    /// queries read fields through this method.
+    pub(crate) fn get_field_lossy(&self, field: &str) -> Result<Option<String>> {
+        Ok(self.get_field_bytes(field)?
+            .map(|data| String::from_utf8_lossy(data).into_owned()))
+    }
+
+    /// Raw data for a field of the current entry, borrowed from the journal
+    /// 
+    /// The slice is only valid until the journal moves to another entry.
+    fn get_field_bytes(&self, field: &str) -> Result<Option<&[u8]>> {
         let field_cstr = CString::new(field)
             .map_err(|_| JournalError::InvalidArgument)?;
         
@@ -394,15 +499,86 @@ impl Journal {
             return Err(JournalError::from_errno(result));
         }
         
-        // Convert the raw data to a string
+        // Convert the raw data to a slice
         let data_slice = unsafe {
             std::slice::from_raw_parts(data as *const u8, length)
         };
         
-        let data_str = std::str::from_utf8(data_slice)
-            .map_err(|_| JournalError::InvalidData)?;
+        Ok(Some(data_slice))
+    }
+
+    /// Get every field of the current journal entry
+    /// 
+    /// Each value is returned as `FIELD=value`, like `get_field()`. Data that
+    /// is not valid UTF-8 (including values cut short by the data threshold)
+    /// is converted lossily.
+    /// 
+    /// # Examples
+    /// ```no_run
+    /// # use journald_query::Journal;
+    /// # let journal = Journal::open_directory("/var/log/journal")?;
+    /// journal.seek_head()?;
+    /// if journal.next()? {
+    ///     for field in journal.get_all_fields()? {
+    ///         println!("{}", field);
+    ///     }
+    /// }
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn get_all_fields(&self) -> Result<Vec<String>> {
+        unsafe {
+            ffi::sd_journal_restart_data(self.handle);
+        }
+        
+        let mut fields = Vec::new();
+        loop {
+            let mut data: *const c_void = ptr::null();
+            let mut length: usize = 0;
+            
+            let result = unsafe {
+                ffi::sd_journal_enumerate_available_data(self.handle, &mut data, &mut length)
+            };
+            
+            if result < 0 {
+                return Err(JournalError::from_errno(result));
+            }
+            
+            if result == 0 || data.is_null() {
+                break;
+            }
+            
+            let slice = unsafe {
+                std::slice::from_raw_parts(data as *const u8, length)
+            };
+            fields.push(String::from_utf8_lossy(slice).into_owned());
+        }
+        
+        Ok(fields)
+    }
+
+    /// Limit how many bytes of each field are returned
+    /// 
+    /// Fields larger than the threshold may be truncated when read, which
+    /// bounds the cost of huge payloads. Pass 0 to disable the limit.
+    /// The default threshold is 64K.
+    /// 
+    /// # Examples
+    /// ```no_run
+    /// # use journald_query::Journal;
+    /// # let journal = Journal::open_directory("/var/log/journal")?;
+    /// journal.set_data_threshold(4096)?;
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn set_data_threshold(&self, bytes: usize) -> Result<()> {
+        let result = unsafe {
+            ffi::sd_journal_set_data_threshold(self.handle, bytes)
+        };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
         
-        Ok(Some(data_str.to_string()))
+        Ok(())
     }
 
     /// Seek to a specific timestamp in the journal
@@ -480,6 +656,243 @@ impl Journal {
         
         Ok(timestamp)
     }
+
+    /// Get the monotonic timestamp and boot ID of the current journal entry
+    /// 
+    /// The monotonic timestamp counts microseconds since the boot identified
+    /// by the returned boot ID, and is unaffected by wallclock adjustments.
+    /// 
+    /// # Returns
+    /// A tuple of (microseconds since boot, boot ID as 32 hex digits)
+    /// 
+    /// # Examples
+    /// ```no_run
+    /// use journald_query::Journal;
+    /// 
+    /// let journal = Journal::open_directory("/var/log/journal")?;
+    /// journal.seek_head()?;
+    /// if journal.next()? {
+    ///     let (monotonic, boot_id) = journal.get_monotonic_usec()?;
+    ///     println!("{}us into boot {}", monotonic, boot_id);
+    /// }
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn get_monotonic_usec(&self) -> Result<(u64, String)> {
+        let mut timestamp: u64 = 0;
+        let mut boot_id = ffi::SdId128::default();
+        
+        let result = unsafe {
+            ffi::sd_journal_get_monotonic_usec(self.handle, &mut timestamp, &mut boot_id)
+        };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
+        
+        Ok((timestamp, boot_id.to_hex()))
+    }
+
+    /// Get the cursor of the current journal entry
+    /// 
+    /// A cursor is an opaque string that identifies an entry uniquely, even
+    /// across journal files, and can be used to return to it later with
+    /// [`Journal::seek_cursor`].
+    /// 
+    /// # Examples
+    /// ```no_run
+    /// use journald_query::Journal;
+    /// 
+    /// let journal = Journal::open_directory("/var/log/journal")?;
+    /// journal.seek_head()?;
+    /// if journal.next()? {
+    ///     let cursor = journal.get_cursor()?;
+    ///     println!("First entry: {}", cursor);
+    /// }
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn get_cursor(&self) -> Result<String> {
+        let mut cursor: *mut std::os::raw::c_char = ptr::null_mut();
+        
+        let result = unsafe {
+            ffi::sd_journal_get_cursor(self.handle, &mut cursor)
+        };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
+        
+        // The cursor is allocated by libsystemd and must be freed by us
+        let value = unsafe {
+            let value = std::ffi::CStr::from_ptr(cursor).to_string_lossy().into_owned();
+            libc::free(cursor as *mut c_void);
+            value
+        };
+        
+        Ok(value)
+    }
+
+    /// Seek to the entry identified by a cursor
+    /// 
+    /// Call `next()` or `previous()` afterwards to position on the entry.
+    /// If the entry no longer exists, the closest entry is used.
+    /// 
+    /// # Examples
+    /// ```no_run
+    /// use journald_query::Journal;
+    /// 
+    /// let journal = Journal::open_directory("/var/log/journal")?;
+    /// # let cursor = "s=0;i=1";
+    /// journal.seek_cursor(cursor)?;
+    /// journal.next()?;
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn seek_cursor(&self, cursor: &str) -> Result<()> {
+        let cursor = CString::new(cursor).map_err(|_| JournalError::InvalidArgument)?;
+        
+        let result = unsafe {
+            ffi::sd_journal_seek_cursor(self.handle, cursor.as_ptr())
+        };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
+        
+        Ok(())
+    }
+
+    /// Check whether the current entry is the one a cursor identifies
+    /// 
+    /// Cursors from `get_cursor()` are not guaranteed to be byte-identical
+    /// for the same entry, e.g. after the journal was reopened, so compare
+    /// them with this rather than as strings.
+    /// 
+    /// # Examples
+    /// ```no_run
+    /// use journald_query::Journal;
+    /// 
+    /// let journal = Journal::open_directory("/var/log/journal")?;
+    /// # let cursor = "s=0;i=1";
+    /// journal.seek_cursor(cursor)?;
+    /// if journal.next()? && journal.test_cursor(cursor)? {
+    ///     println!("The entry is still there");
+    /// }
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn test_cursor(&self, cursor: &str) -> Result<bool> {
+        let cursor = CString::new(cursor).map_err(|_| JournalError::InvalidArgument)?;
+        
+        let result = unsafe {
+            ffi::sd_journal_test_cursor(self.handle, cursor.as_ptr())
+        };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
+        
+        Ok(result > 0)
+    }
+
+    /// Get the disk space used by the journal files, in bytes
+    /// 
+    /// # Examples
+    /// ```no_run
+    /// use journald_query::Journal;
+    /// 
+    /// let journal = Journal::open_directory("/var/log/journal")?;
+    /// println!("{} MiB", journal.get_usage()? / 1024 / 1024);
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn get_usage(&self) -> Result<u64> {
+        let mut bytes: u64 = 0;
+        
+        let result = unsafe {
+            ffi::sd_journal_get_usage(self.handle, &mut bytes)
+        };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
+        
+        Ok(bytes)
+    }
+
+    /// Watch the journal for new files
+    /// 
+    /// Sets up the inotify watches behind `sd_journal_get_fd()`, after which
+    /// [`Journal::process`] picks up journal files created since the journal
+    /// was opened, such as those of a new remote host or after rotation.
+    pub fn watch(&self) -> Result<()> {
+        let result = unsafe { ffi::sd_journal_get_fd(self.handle) };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
+        
+        Ok(())
+    }
+
+    /// Process changes to the journal without blocking
+    /// 
+    /// Returns `true` if journal files were added or removed. New files are
+    /// only noticed after [`Journal::watch`].
+    /// 
+    /// # Examples
+    /// ```no_run
+    /// use journald_query::Journal;
+    /// 
+    /// let journal = Journal::open_directory("/var/log/journal")?;
+    /// journal.watch()?;
+    /// journal.seek_tail()?;
+    /// loop {
+    ///     journal.process()?;
+    ///     while journal.next()? {
+    ///         println!("{}", journal.get_cursor()?);
+    ///     }
+    ///     std::thread::sleep(std::time::Duration::from_secs(1));
+    /// }
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn process(&self) -> Result<bool> {
+        let result = unsafe { ffi::sd_journal_process(self.handle) };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
+        
+        // SD_JOURNAL_INVALIDATE
+        Ok(result == 2)
+    }
+
+    /// Seek to a monotonic timestamp within a specific boot
+    /// 
+    /// # Arguments
+    /// * `boot_id` - Boot ID as 32 hex digits (as found in `_BOOT_ID`)
+    /// * `timestamp_usec` - Microseconds since that boot started
+    /// 
+    /// # Examples
+    /// ```no_run
+    /// use journald_query::Journal;
+    /// 
+    /// let journal = Journal::open_directory("/var/log/journal")?;
+    /// 
+    /// // Jump to 30 seconds after the boot started
+    /// journal.seek_monotonic_usec("1234567890abcdef1234567890abcdef", 30_000_000)?;
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn seek_monotonic_usec(&self, boot_id: &str, timestamp_usec: u64) -> Result<()> {
+        let boot_id = ffi::SdId128::from_hex(boot_id)
+            .ok_or(JournalError::InvalidArgument)?;
+        
+        let result = unsafe {
+            ffi::sd_journal_seek_monotonic_usec(self.handle, boot_id, timestamp_usec)
+        };
+        
+        if result < 0 {
+            return Err(JournalError::from_errno(result));
+        }
+        
+        Ok(())
+    }
 }
 
 impl Drop for Journal {
@@ -0,0 +1,550 @@
+use crate::boot::BootSelector;
+use crate::error::{JournalError, Result};
+use crate::matcher::CaseSensitivity;
+use crate::query::{Query, UnitMatch};
+use crate::timespec::parse_timestamp;
+
+/// Number of entries `-n` shows when given without a count
+const DEFAULT_LINES: usize = 10;
+
+/// Syslog priority names, indexed by level
+const PRIORITY_NAMES: [&str; 8] = ["emerg", "alert", "crit", "err", "warning", "notice", "info", "debug"];
+
+/// Parse a `journalctl` command line into a [`Query`]
+///
+/// The input is split like a shell would, honoring single quotes, double
+/// quotes and backslash escapes, and then handed to [`parse_journalctl_args`].
+///
+/// # Examples
+/// ```
+/// use journald_query::parse_journalctl;
+///
+/// let query = parse_journalctl("_SYSTEMD_UNIT=a.service _PID=12 + _HOSTNAME=b")?;
+/// assert_eq!(query.matches.len(), 2);
+///
+/// let query = parse_journalctl("-u nginx.service -p err -S '2024-03-01 10:00' -n 50 -r")?;
+/// assert_eq!(query.unit.as_deref(), Some("nginx.service"));
+/// assert_eq!(query.priority, Some((0, 3)));
+/// assert_eq!(query.limit, Some(50));
+/// assert!(query.reverse);
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+pub fn parse_journalctl(input: &str) -> Result<Query> {
+    parse_journalctl_args(split_args(input)?)
+}
+
+/// Parse already split `journalctl` arguments into a [`Query`]
+///
+/// Supported options:
+/// - `-u`/`--unit`, `--user-unit`: unit filters, matched like `journalctl` does
+///   (see [`UnitMatch::Journalctl`]).
+/// - `-t`/`--identifier`: syslog identifier filter.
+/// - `-p`/`--priority`: a level (`err`, `3`) or a range (`err..warning`).
+/// - `-b`/`--boot`: an optional offset (`-1`, `0`, `2`) or boot ID.
+/// - `-S`/`--since`, `-U`/`--until`: time specs, see [`crate::timespec::parse_timestamp_at`].
+/// - `-g`/`--grep`, `--case-sensitive[=BOOL]`: regular expression filter on `MESSAGE`.
+/// - `-n`/`--lines`: an optional count of most recent entries (`10` by default, `all` for no limit).
+/// - `-r`/`--reverse`: newest entries first.
+///
+/// Other arguments are `FIELD=VALUE` matches, with `+` separating alternatives.
+/// Short options can be grouped (`-rn5`) and values attached (`-uname`,
+/// `--unit=name`). Anything else is rejected with `JournalError::InvalidExpression`.
+pub fn parse_journalctl_args<I, S>(args: I) -> Result<Query>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let args: Vec<String> = args.into_iter().map(Into::into).collect();
+    let mut parser = Parser {
+        args: args.iter().map(String::as_str).collect(),
+        index: 0,
+        query: Query::all(),
+    };
+    parser.run()?;
+    Ok(parser.query)
+}
+
+/// Options understood by the parser, by short and long name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Opt {
+    Unit,
+    UserUnit,
+    Identifier,
+    Priority,
+    Boot,
+    Since,
+    Until,
+    Grep,
+    CaseSensitive,
+    Lines,
+    Reverse,
+}
+
+/// Whether an option takes a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arity {
+    Flag,
+    Required,
+    Optional,
+}
+
+impl Opt {
+    fn from_short(c: char) -> Option<Opt> {
+        Some(match c {
+            'u' => Opt::Unit,
+            't' => Opt::Identifier,
+            'p' => Opt::Priority,
+            'b' => Opt::Boot,
+            'S' => Opt::Since,
+            'U' => Opt::Until,
+            'g' => Opt::Grep,
+            'n' => Opt::Lines,
+            'r' => Opt::Reverse,
+            _ => return None,
+        })
+    }
+
+    fn from_long(name: &str) -> Option<Opt> {
+        Some(match name {
+            "unit" => Opt::Unit,
+            "user-unit" => Opt::UserUnit,
+            "identifier" => Opt::Identifier,
+            "priority" => Opt::Priority,
+            "boot" => Opt::Boot,
+            "since" => Opt::Since,
+            "until" => Opt::Until,
+            "grep" => Opt::Grep,
+            "case-sensitive" => Opt::CaseSensitive,
+            "lines" => Opt::Lines,
+            "reverse" => Opt::Reverse,
+            _ => return None,
+        })
+    }
+
+    fn arity(self) -> Arity {
+        match self {
+            Opt::Reverse => Arity::Flag,
+            Opt::Boot | Opt::Lines | Opt::CaseSensitive => Arity::Optional,
+            _ => Arity::Required,
+        }
+    }
+
+    /// Whether a separate argument can serve as this option's optional value
+    ///
+    /// Like `journalctl`, `-b` and `-n` only take the next argument if it
+    /// looks like their value, so `-b _PID=1` still works.
+    fn accepts_separate(self, arg: &str) -> bool {
+        match self {
+            Opt::Boot => arg.parse::<i64>().is_ok() || is_boot_id(arg),
+            Opt::Lines => arg == "all" || arg.parse::<usize>().is_ok(),
+            _ => false,
+        }
+    }
+}
+
+struct Parser<'a> {
+    args: Vec<&'a str>,
+    index: usize,
+    query: Query,
+}
+
+impl<'a> Parser<'a> {
+    fn run(&mut self) -> Result<()> {
+        while let Some(arg) = self.next_arg() {
+            if arg == "+" {
+                self.query = std::mem::take(&mut self.query).add_disjunction();
+            } else if let Some(long) = arg.strip_prefix("--") {
+                self.long_option(long)?;
+            } else if arg.len() > 1 && arg.starts_with('-') {
+                self.short_options(&arg[1..])?;
+            } else {
+                self.field_match(arg)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn next_arg(&mut self) -> Option<&'a str> {
+        let arg = self.args.get(self.index).copied();
+        self.index += 1;
+        arg
+    }
+
+    fn long_option(&mut self, long: &str) -> Result<()> {
+        let (name, attached) = match long.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (long, None),
+        };
+        let opt = Opt::from_long(name)
+            .ok_or_else(|| invalid(format!("unknown option --{}", name)))?;
+
+        let value = match (opt.arity(), attached) {
+            (Arity::Flag, Some(_)) => {
+                return Err(invalid(format!("option --{} takes no value", name)));
+            }
+            (_, Some(value)) => Some(value),
+            (arity, None) => self.separate_value(opt, arity, &format!("--{}", name))?,
+        };
+        self.apply(opt, value)
+    }
+
+    fn short_options(&mut self, cluster: &'a str) -> Result<()> {
+        for (position, c) in cluster.char_indices() {
+            let opt = Opt::from_short(c)
+                .ok_or_else(|| invalid(format!("unknown option -{}", c)))?;
+            let rest = &cluster[position + c.len_utf8()..];
+
+            match opt.arity() {
+                Arity::Flag => self.apply(opt, None)?,
+                arity => {
+                    // The rest of the group is the value, as in `-n5` or `-uname`
+                    let value = match rest {
+                        "" => self.separate_value(opt, arity, &format!("-{}", c))?,
+                        rest => Some(rest),
+                    };
+                    return self.apply(opt, value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn separate_value(&mut self, opt: Opt, arity: Arity, name: &str) -> Result<Option<&'a str>> {
+        match arity {
+            Arity::Flag => Ok(None),
+            Arity::Required => self
+                .next_arg()
+                .map(Some)
+                .ok_or_else(|| invalid(format!("option {} requires a value", name))),
+            Arity::Optional => match self.args.get(self.index) {
+                Some(arg) if opt.accepts_separate(arg) => Ok(self.next_arg()),
+                _ => Ok(None),
+            },
+        }
+    }
+
+    fn apply(&mut self, opt: Opt, value: Option<&str>) -> Result<()> {
+        let query = &mut self.query;
+        match (opt, value) {
+            // Repeated units and identifiers are OR'ed, as in journalctl
+            (Opt::Unit, Some(unit)) => {
+                match query.unit {
+                    Some(_) => query.units.push(unit.to_string()),
+                    None => query.unit = Some(unit.to_string()),
+                }
+                query.unit_match = UnitMatch::Journalctl;
+            }
+            (Opt::UserUnit, Some(unit)) => {
+                query.user_units.push(unit.to_string());
+                query.unit_match = UnitMatch::Journalctl;
+            }
+            (Opt::Identifier, Some(identifier)) => query.identifiers.push(identifier.to_string()),
+            (Opt::Priority, Some(spec)) => query.priority = Some(parse_priority(spec)?),
+            (Opt::Boot, spec) => query.boot = Some(parse_boot(spec.unwrap_or("0"))?),
+            (Opt::Since, Some(spec)) => query.start_time_utc = parse_timestamp(spec)?,
+            (Opt::Until, Some(spec)) => query.end_time_utc = parse_timestamp(spec)?,
+            (Opt::Grep, Some(pattern)) => {
+                *query = std::mem::take(query).grep(pattern);
+            }
+            (Opt::CaseSensitive, value) => {
+                let case = match parse_bool(value.unwrap_or("true"))? {
+                    true => CaseSensitivity::Sensitive,
+                    false => CaseSensitivity::Insensitive,
+                };
+                *query = std::mem::take(query).case_sensitivity(case);
+            }
+            (Opt::Lines, None) => query.limit = Some(DEFAULT_LINES),
+            (Opt::Lines, Some("all")) => query.limit = None,
+            (Opt::Lines, Some(count)) => {
+                let count = count
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid line count {:?}", count)))?;
+                query.limit = Some(count);
+            }
+            (Opt::Reverse, _) => query.reverse = true,
+            (opt, None) => return Err(invalid(format!("option {:?} requires a value", opt))),
+        }
+        Ok(())
+    }
+
+    fn field_match(&mut self, arg: &str) -> Result<()> {
+        let (field, value) = arg
+            .split_once('=')
+            .filter(|(field, _)| is_field_name(field))
+            .ok_or_else(|| invalid(format!("expected FIELD=VALUE, got {:?}", arg)))?;
+        self.query = std::mem::take(&mut self.query).add_match(field, value);
+        Ok(())
+    }
+}
+
+fn invalid<S: Into<String>>(reason: S) -> JournalError {
+    JournalError::InvalidExpression(reason.into())
+}
+
+/// Check for a valid journal field name: uppercase letters, digits and
+/// underscores, not starting with a digit
+fn is_field_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Check for a 128-bit ID, with or without dashes
+fn is_boot_id(arg: &str) -> bool {
+    let hex: String = arg.chars().filter(|&c| c != '-').collect();
+    hex.len() == 32 && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn parse_boot(spec: &str) -> Result<BootSelector> {
+    if let Ok(offset) = spec.parse::<i64>() {
+        return Ok(BootSelector::Offset(offset));
+    }
+    if is_boot_id(spec) {
+        let id = spec.chars().filter(|&c| c != '-').collect::<String>().to_ascii_lowercase();
+        return Ok(BootSelector::Id(id));
+    }
+    Err(invalid(format!("invalid boot {:?}", spec)))
+}
+
+/// Parse `-p` values: a single level means that level or more severe
+fn parse_priority(spec: &str) -> Result<(u8, u8)> {
+    match spec.split_once("..") {
+        Some((most, least)) => {
+            let (a, b) = (parse_level(most)?, parse_level(least)?);
+            Ok((a.min(b), a.max(b)))
+        }
+        None => Ok((0, parse_level(spec)?)),
+    }
+}
+
+fn parse_level(level: &str) -> Result<u8> {
+    if let Some(index) = PRIORITY_NAMES.iter().position(|name| name.eq_ignore_ascii_case(level)) {
+        return Ok(index as u8);
+    }
+    level
+        .parse::<u8>()
+        .ok()
+        .filter(|&n| n <= 7)
+        .ok_or_else(|| invalid(format!("invalid priority {:?}", level)))
+}
+
+fn parse_bool(value: &str) -> Result<bool> {
+    match value {
+        "1" | "yes" | "y" | "true" | "t" | "on" => Ok(true),
+        "0" | "no" | "n" | "false" | "f" | "off" => Ok(false),
+        _ => Err(invalid(format!("invalid boolean {:?}", value))),
+    }
+}
+
+/// Split a command line into arguments like a POSIX shell would
+fn split_args(input: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if let Some(arg) = current.take() {
+                    args.push(arg);
+                }
+            }
+            '\'' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => return Err(invalid("unterminated single quote")),
+                    }
+                }
+            }
+            '"' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => arg.push(c),
+                            Some(c) => {
+                                arg.push('\\');
+                                arg.push(c);
+                            }
+                            None => return Err(invalid("unterminated double quote")),
+                        },
+                        Some(c) => arg.push(c),
+                        None => return Err(invalid("unterminated double quote")),
+                    }
+                }
+            }
+            '\\' => {
+                let c = chars.next().ok_or_else(|| invalid("trailing backslash"))?;
+                current.get_or_insert_with(String::new).push(c);
+            }
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(arg) = current {
+        args.push(arg);
+    }
+
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(group: &[(&str, &str)]) -> Vec<(String, String)> {
+        group.iter().map(|(f, v)| (f.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(split_args("  -u   a.service ").unwrap(), vec!["-u", "a.service"]);
+        assert_eq!(
+            split_args(r#"-S '2024-03-01 10:00' MESSAGE="a \"b\"" x\ y"#).unwrap(),
+            vec!["-S", "2024-03-01 10:00", r#"MESSAGE=a "b""#, "x y"]
+        );
+        assert_eq!(split_args("-g ''").unwrap(), vec!["-g", ""]);
+        assert!(split_args("-g 'unclosed").is_err());
+    }
+
+    #[test]
+    fn test_field_matches_and_disjunction() {
+        let query = parse_journalctl("_SYSTEMD_UNIT=a.service _PID=12 + _HOSTNAME=b").unwrap();
+        assert_eq!(query.matches, vec![
+            pairs(&[("_SYSTEMD_UNIT", "a.service"), ("_PID", "12")]),
+            pairs(&[("_HOSTNAME", "b")]),
+        ]);
+
+        // Values may contain '='
+        let query = parse_journalctl("MESSAGE=a=b").unwrap();
+        assert_eq!(query.matches, vec![pairs(&[("MESSAGE", "a=b")])]);
+    }
+
+    #[test]
+    fn test_invalid_field_matches() {
+        for input in ["nginx", "lower=case", "=value", "1ABC=x"] {
+            assert!(
+                matches!(parse_journalctl(input), Err(JournalError::InvalidExpression(_))),
+                "{:?} should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_unit_and_identifier() {
+        let query = parse_journalctl("-u nginx.service -t sshd").unwrap();
+        assert_eq!(query.unit.as_deref(), Some("nginx.service"));
+        assert_eq!(query.unit_match, UnitMatch::Journalctl);
+        assert_eq!(query.identifiers, vec!["sshd"]);
+
+        let query = parse_journalctl("--user-unit backup.service").unwrap();
+        assert_eq!(query.user_units, vec!["backup.service"]);
+        assert_eq!(query.unit_match, UnitMatch::Journalctl);
+
+        let query = parse_journalctl("--unit=nginx.service --identifier sshd").unwrap();
+        assert_eq!(query.unit.as_deref(), Some("nginx.service"));
+        assert_eq!(query.identifiers, vec!["sshd"]);
+
+        assert!(parse_journalctl("-u").is_err());
+    }
+
+    #[test]
+    fn test_repeated_units_and_identifiers() {
+        let query = parse_journalctl("-u a.service -u b.service").unwrap();
+        assert_eq!(query.unit.as_deref(), Some("a.service"));
+        assert_eq!(query.units, vec!["b.service"]);
+        assert_eq!(query.unit_match, UnitMatch::Journalctl);
+
+        let query = parse_journalctl("-t x -t y").unwrap();
+        assert_eq!(query.identifiers, vec!["x", "y"]);
+
+        let query = parse_journalctl("--user-unit a.service --user-unit=b.service -uc.service").unwrap();
+        assert_eq!(query.user_units, vec!["a.service", "b.service"]);
+        assert_eq!(query.unit.as_deref(), Some("c.service"));
+        assert!(query.units.is_empty());
+    }
+
+    #[test]
+    fn test_priority() {
+        assert_eq!(parse_journalctl("-p err").unwrap().priority, Some((0, 3)));
+        assert_eq!(parse_journalctl("-p 4").unwrap().priority, Some((0, 4)));
+        assert_eq!(parse_journalctl("-p warning..err").unwrap().priority, Some((3, 4)));
+        assert_eq!(parse_journalctl("--priority=crit..notice").unwrap().priority, Some((2, 5)));
+        assert!(parse_journalctl("-p 8").is_err());
+        assert!(parse_journalctl("-p loud").is_err());
+    }
+
+    #[test]
+    fn test_boot() {
+        assert_eq!(parse_journalctl("-b").unwrap().boot, Some(BootSelector::Offset(0)));
+        assert_eq!(parse_journalctl("-b -1").unwrap().boot, Some(BootSelector::Offset(-1)));
+        assert_eq!(parse_journalctl("-b-2").unwrap().boot, Some(BootSelector::Offset(-2)));
+        assert_eq!(parse_journalctl("--boot=3").unwrap().boot, Some(BootSelector::Offset(3)));
+        assert_eq!(
+            parse_journalctl("-b 12345678-90AB-CDEF-1234-567890ABCDEF").unwrap().boot,
+            Some(BootSelector::Id("1234567890abcdef1234567890abcdef".to_string()))
+        );
+
+        // A following match is not mistaken for the boot
+        let query = parse_journalctl("-b _PID=1").unwrap();
+        assert_eq!(query.boot, Some(BootSelector::Offset(0)));
+        assert_eq!(query.matches, vec![pairs(&[("_PID", "1")])]);
+    }
+
+    #[test]
+    fn test_time_range() {
+        let query = parse_journalctl("-S @1640995200 --until='@1640995260'").unwrap();
+        assert_eq!(query.start_time_utc, 1640995200000000);
+        assert_eq!(query.end_time_utc, 1640995260000000);
+
+        assert!(matches!(
+            parse_journalctl("-S whenever"),
+            Err(JournalError::InvalidTimeSpec(_))
+        ));
+        for spec in ["+1000000000y", "@99999999999999", "99999999999-01-01"] {
+            assert!(matches!(
+                parse_journalctl_args(["-S", spec]),
+                Err(JournalError::InvalidTimeSpec(_))
+            ));
+            assert!(matches!(
+                parse_journalctl_args(["-U", spec]),
+                Err(JournalError::InvalidTimeSpec(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_grep() {
+        let query = parse_journalctl("-g 'timeout|refused' --case-sensitive").unwrap();
+        let grep = query.grep.unwrap();
+        assert_eq!(grep.patterns, vec!["timeout|refused"]);
+        assert_eq!(grep.case, CaseSensitivity::Sensitive);
+
+        let query = parse_journalctl("--grep=Error --case-sensitive=no").unwrap();
+        assert_eq!(query.grep.unwrap().case, CaseSensitivity::Insensitive);
+    }
+
+    #[test]
+    fn test_lines_and_reverse() {
+        assert_eq!(parse_journalctl("-n").unwrap().limit, Some(10));
+        assert_eq!(parse_journalctl("-n 50").unwrap().limit, Some(50));
+        assert_eq!(parse_journalctl("--lines=all").unwrap().limit, None);
+
+        let query = parse_journalctl("-rn5").unwrap();
+        assert_eq!(query.limit, Some(5));
+        assert!(query.reverse);
+
+        // `-n` followed by a match keeps the default count
+        let query = parse_journalctl("-n _PID=1").unwrap();
+        assert_eq!(query.limit, Some(10));
+        assert_eq!(query.matches.len(), 1);
+
+        assert!(parse_journalctl("--reverse=yes").is_err());
+        assert!(parse_journalctl("-x").is_err());
+        assert!(parse_journalctl("--follow").is_err());
+    }
+}
@@ -1,6 +1,22 @@
+pub mod aggregate;
+pub mod alias;
+pub mod boot;
+pub mod cache;
+pub mod checkpoint;
+pub mod control;
 pub mod discover;
+pub mod health;
+pub mod index;
+pub mod journalctl;
+pub mod lifecycle;
+pub mod matcher;
+pub mod parallel;
 pub mod query;
+pub mod schema;
+pub mod source;
 pub mod tail;
+pub mod timespec;
+pub mod watch;
 
 // Core FFI bindings and types
 mod ffi;
@@ -9,6 +25,25 @@ pub mod error;
 
 pub use journal::Journal;
 pub use error::{JournalError, Result};
-pub use discover::{discover_services, Host, Hosts};
-pub use query::{query_journal, Query, Entry};
+pub use aggregate::{aggregate, Aggregation, AggregateCount};
+pub use alias::HostAliases;
+pub use boot::{list_boots, Boot, BootSelector};
+pub use cache::{CacheStats, QueryCache};
+pub use checkpoint::DiscoveryCheckpoint;
+pub use control::{CancellationToken, Progress, ScanControl};
+pub use index::{Indexed, JournalIndex};
+pub use health::{health_report, Health, HealthOptions, HealthReport, HostHealth, UnitHealth};
+pub use discover::{
+    discover_groups, discover_groups_with_options, discover_services, discover_services_matching,
+    discover_services_with_control, discover_services_with_options,
+    DiscoveryOptions, DiscoveryStrategy, FieldGroup, Host, HostnameSeen, Hosts, UnitStats,
+};
+pub use query::{query_journal, query_journal_with_control, Query, Entry, Context, Projection, UnitMatch, WindowMode, TimestampSource};
+pub use journalctl::{parse_journalctl, parse_journalctl_args};
+pub use lifecycle::{unit_lifecycle, LifecycleEvent, LifecycleKind, UnitTimeline};
+pub use matcher::{CaseSensitivity, FieldMatch, Grep};
+pub use parallel::{query_journal_parallel, query_journal_parallel_with_control};
+pub use source::{JournalSource, MultiJournal};
+pub use timespec::{parse_timestamp, parse_timespan, TimeZone};
 pub use tail::{TailConfig, JournalTail, JournalIterator};
+pub use watch::{DiscoveryEvent, DiscoveryEvents, DiscoveryWatcher};
@@ -0,0 +1,312 @@
+use std::time::Duration;
+use crate::error::Result;
+use crate::journal::Journal;
+use crate::query::field_value;
+
+/// `MESSAGE_ID`s of the service manager's messages about units, from the systemd catalog
+const STARTING: &str = "7d4958e842da4a758f6c1cdc7b36dcc5";
+const STARTED: &str = "39f53479d3a045ac8e11786248231fbf";
+const START_FAILED: &str = "be02cf6855d2428ba40df7e9d022f03d";
+const STOPPING: &str = "de5b426a63be47a7b6ac3eaac82e2f6f";
+const STOPPED: &str = "9d1aaa27d60140bd96365438aad20286";
+const SUCCEEDED: &str = "7ad2d189f7e94e70a38c781354912448";
+const FAILED: &str = "d9b373ed55a64feb8242e02dbe79a49c";
+const RESTART_SCHEDULED: &str = "5eb03494b6584870a536b337290809b3";
+const PROCESS_EXITED: &str = "98e322203f7a4ed290d09fe03c09fe15";
+
+const MESSAGE_IDS: [&str; 9] = [
+    STARTING,
+    STARTED,
+    START_FAILED,
+    STOPPING,
+    STOPPED,
+    SUCCEEDED,
+    FAILED,
+    RESTART_SCHEDULED,
+    PROCESS_EXITED,
+];
+
+/// What the service manager reported about a unit
+///
+/// - `Starting`: a start job began ("Starting nginx.service...").
+/// - `Started`: a start job finished successfully.
+/// - `StartFailed`: a start job failed, with its `JOB_RESULT` ("Failed to start nginx.service.").
+/// - `Stopping`: a stop job began.
+/// - `Stopped`: a stop job finished, also when stopping for a restart.
+/// - `Succeeded`: the unit finished and is inactive ("Deactivated successfully").
+/// - `Failed`: the unit entered the failed state, with its `UNIT_RESULT` such as
+///   `exit-code`, `signal` or `timeout` ("Failed with result 'exit-code'.").
+/// - `RestartScheduled`: `Restart=` scheduled an automatic restart, with the restart
+///   counter (`N_RESTARTS`) if the systemd version logs it.
+/// - `ProcessExited`: a process of the unit exited. `command` is the `Exec*=` setting
+///   it ran for (`ExecStart` for the main process), `exit_code` how it ended
+///   (`exited`, `killed` or `dumped`) and `exit_status` its exit status or signal number.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum LifecycleKind {
+    Starting,
+    Started,
+    StartFailed {
+        job_result: Option<String>,
+    },
+    Stopping,
+    Stopped,
+    Succeeded,
+    Failed {
+        result: Option<String>,
+    },
+    RestartScheduled {
+        restart_count: Option<u64>,
+    },
+    ProcessExited {
+        command: Option<String>,
+        exit_code: Option<String>,
+        exit_status: Option<i32>,
+    },
+}
+
+/// One step in the life of a unit
+///
+/// Fields:
+/// - `timestamp_utc`: Receive time of the message, in microseconds since Unix epoch (UTC).
+/// - `hostname`: Host of the service manager that logged it (`_HOSTNAME`), if present.
+/// - `machine_id`: Machine ID of that host (`_MACHINE_ID`), if present.
+/// - `kind`: What happened, with the details systemd logged as fields.
+/// - `message`: The message as logged (`MESSAGE`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LifecycleEvent {
+    pub timestamp_utc: u64,
+    pub hostname: Option<String>,
+    pub machine_id: Option<String>,
+    pub kind: LifecycleKind,
+    pub message: String,
+}
+
+/// The lifecycle events of a unit, oldest first
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{unit_lifecycle, Journal};
+///
+/// let journal = Journal::open_directory("/var/log/journal")?;
+/// let timeline = unit_lifecycle(&journal, "auto-restart.service")?.for_host("web-server");
+///
+/// if let Some(span) = timeline.restart_span() {
+///     println!("restarted {} times in {} minutes, last exit status {:?}",
+///         timeline.restarts().count(), span.as_secs() / 60, timeline.last_exit_status());
+/// }
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnitTimeline {
+    pub unit: String,
+    pub events: Vec<LifecycleEvent>,
+}
+
+impl UnitTimeline {
+    /// Keep only the events logged from `hostname`
+    pub fn for_host(mut self, hostname: &str) -> Self {
+        self.events.retain(|event| event.hostname.as_deref() == Some(hostname));
+        self
+    }
+
+    /// Keep only the events logged from the machine with `machine_id`
+    pub fn for_machine(mut self, machine_id: &str) -> Self {
+        self.events.retain(|event| event.machine_id.as_deref() == Some(machine_id));
+        self
+    }
+
+    /// Keep only the events received from `start` to `end` (inclusive), in
+    /// microseconds since Unix epoch (UTC)
+    pub fn between(mut self, start: u64, end: u64) -> Self {
+        self.events.retain(|event| (start..=end).contains(&event.timestamp_utc));
+        self
+    }
+
+    /// The automatic restarts scheduled by `Restart=`
+    pub fn restarts(&self) -> impl Iterator<Item = &LifecycleEvent> {
+        self.events
+            .iter()
+            .filter(|event| matches!(event.kind, LifecycleKind::RestartScheduled { .. }))
+    }
+
+    /// Time from the first scheduled restart to the last one
+    pub fn restart_span(&self) -> Option<Duration> {
+        let first = self.restarts().next()?.timestamp_utc;
+        let last = self.restarts().last()?.timestamp_utc;
+        Some(Duration::from_micros(last.saturating_sub(first)))
+    }
+
+    /// Exit status of the last main process (`ExecStart=`) that exited
+    ///
+    /// Processes whose `Exec*=` setting is not logged count as main processes.
+    pub fn last_exit_status(&self) -> Option<i32> {
+        self.events.iter().rev().find_map(|event| match &event.kind {
+            LifecycleKind::ProcessExited { command, exit_status, .. }
+                if command.as_deref().is_none_or(|command| command == "ExecStart") =>
+            {
+                *exit_status
+            }
+            _ => None,
+        })
+    }
+
+    /// Result of the last time the unit failed, such as `exit-code`
+    pub fn last_failure(&self) -> Option<&str> {
+        self.events.iter().rev().find_map(|event| match &event.kind {
+            LifecycleKind::Failed { result } => Some(result.as_deref()),
+            _ => None,
+        })?
+    }
+}
+
+/// Read the lifecycle of a system unit from the service manager's messages
+///
+/// Walks the messages PID 1 logged about `unit` (`_PID=1` and `UNIT=`),
+/// recognized by their `MESSAGE_ID`, like the ones `journalctl -u` adds to
+/// the unit's own output. Messages from every host in the journal are
+/// included; narrow them with [`UnitTimeline::for_host`] or
+/// [`UnitTimeline::for_machine`]. Any matches set on
+/// the journal are flushed.
+pub fn unit_lifecycle(journal: &Journal, unit: &str) -> Result<UnitTimeline> {
+    unit_lifecycle_between(journal, unit, 0, u64::MAX)
+}
+
+/// Read the lifecycle of a system unit as [`unit_lifecycle`] does, from the
+/// messages received from `start` to `end` (inclusive) only
+///
+/// Seeks to `start` rather than reading the unit's whole history.
+pub(crate) fn unit_lifecycle_between(journal: &Journal, unit: &str, start: u64, end: u64) -> Result<UnitTimeline> {
+    journal.flush_matches();
+    journal.add_match("UNIT", unit)?;
+    journal.add_match("_PID", "1")?;
+    for message_id in MESSAGE_IDS {
+        journal.add_match("MESSAGE_ID", message_id)?;
+    }
+
+    let mut events = Vec::new();
+    let read = read_events(journal, start, end, &mut events);
+    journal.flush_matches();
+    read?;
+
+    events.sort_by_key(|event| event.timestamp_utc);
+    Ok(UnitTimeline {
+        unit: unit.to_string(),
+        events,
+    })
+}
+
+fn read_events(journal: &Journal, start: u64, end: u64, events: &mut Vec<LifecycleEvent>) -> Result<()> {
+    journal.seek_realtime_usec(start)?;
+    while journal.next()? {
+        let received = journal.get_realtime_usec()?;
+        if received > end {
+            break;
+        }
+        if received < start {
+            continue;
+        }
+        let Some(message_id) = field_value(journal, "MESSAGE_ID")? else {
+            continue;
+        };
+        let Some(kind) = lifecycle_kind(journal, &message_id)? else {
+            continue;
+        };
+        events.push(LifecycleEvent {
+            timestamp_utc: received,
+            hostname: field_value(journal, "_HOSTNAME")?,
+            machine_id: field_value(journal, "_MACHINE_ID")?,
+            kind,
+            message: field_value(journal, "MESSAGE")?.unwrap_or_default(),
+        });
+    }
+    Ok(())
+}
+
+/// Turn the current message into a lifecycle event kind, reading the fields it carries
+fn lifecycle_kind(journal: &Journal, message_id: &str) -> Result<Option<LifecycleKind>> {
+    let number = |field: &str| -> Result<Option<i64>> {
+        Ok(field_value(journal, field)?.and_then(|value| value.trim().parse().ok()))
+    };
+
+    let kind = match message_id {
+        STARTING => LifecycleKind::Starting,
+        STARTED => LifecycleKind::Started,
+        START_FAILED => LifecycleKind::StartFailed {
+            job_result: field_value(journal, "JOB_RESULT")?,
+        },
+        STOPPING => LifecycleKind::Stopping,
+        STOPPED => LifecycleKind::Stopped,
+        SUCCEEDED => LifecycleKind::Succeeded,
+        FAILED => LifecycleKind::Failed {
+            result: field_value(journal, "UNIT_RESULT")?,
+        },
+        RESTART_SCHEDULED => LifecycleKind::RestartScheduled {
+            restart_count: number("N_RESTARTS")?.and_then(|count| u64::try_from(count).ok()),
+        },
+        PROCESS_EXITED => LifecycleKind::ProcessExited {
+            command: field_value(journal, "COMMAND")?,
+            exit_code: field_value(journal, "EXIT_CODE")?,
+            exit_status: number("EXIT_STATUS")?.and_then(|status| i32::try_from(status).ok()),
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(timestamp_utc: u64, hostname: &str, kind: LifecycleKind) -> LifecycleEvent {
+        LifecycleEvent {
+            timestamp_utc,
+            hostname: Some(hostname.to_string()),
+            machine_id: None,
+            kind,
+            message: String::new(),
+        }
+    }
+
+    fn exited(status: i32, command: Option<&str>) -> LifecycleKind {
+        LifecycleKind::ProcessExited {
+            command: command.map(str::to_string),
+            exit_code: Some("exited".to_string()),
+            exit_status: Some(status),
+        }
+    }
+
+    #[test]
+    fn test_timeline_summary() {
+        let minute = 60_000_000;
+        let timeline = UnitTimeline {
+            unit: "app.service".to_string(),
+            events: vec![
+                event(0, "web1", LifecycleKind::Started),
+                event(minute, "web1", exited(1, Some("ExecStart"))),
+                event(minute + 1, "web1", LifecycleKind::Failed { result: Some("exit-code".to_string()) }),
+                event(minute + 2, "web1", LifecycleKind::RestartScheduled { restart_count: Some(1) }),
+                event(2 * minute, "web2", LifecycleKind::RestartScheduled { restart_count: Some(1) }),
+                event(5 * minute, "web1", exited(2, None)),
+                event(5 * minute + 1, "web1", exited(0, Some("ExecStopPost"))),
+                event(5 * minute + 2, "web1", LifecycleKind::RestartScheduled { restart_count: Some(2) }),
+            ],
+        };
+
+        assert_eq!(timeline.restarts().count(), 3);
+        assert_eq!(timeline.last_exit_status(), Some(2), "Only main processes count");
+        assert_eq!(timeline.last_failure(), Some("exit-code"));
+
+        let web1 = timeline.clone().for_host("web1");
+        assert_eq!(web1.restarts().count(), 2);
+        assert_eq!(web1.restart_span(), Some(Duration::from_secs(4 * 60)));
+
+        let early = timeline.between(0, minute + 2);
+        assert_eq!(early.restarts().count(), 1);
+        assert_eq!(early.restart_span(), Some(Duration::ZERO));
+        assert_eq!(early.last_exit_status(), Some(1));
+        assert_eq!(early.clone().for_host("web3").restart_span(), None);
+    }
+}
@@ -0,0 +1,193 @@
+use crate::error::{JournalError, Result};
+use regex::{Regex, RegexBuilder};
+
+/// How letter case is treated when matching patterns
+///
+/// - `Sensitive`: patterns match exactly as written.
+/// - `Insensitive`: patterns ignore letter case.
+/// - `Smart`: a pattern is case-insensitive unless it contains an uppercase
+///   letter, mirroring the default behavior of `journalctl --grep`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum CaseSensitivity {
+    Sensitive,
+    Insensitive,
+    #[default]
+    Smart,
+}
+
+/// A regular expression filter applied to one field of each entry
+///
+/// Fields:
+/// - `patterns`: Regular expressions to look for. An entry matches if any pattern matches.
+/// - `field`: Field the patterns are matched against (defaults to `MESSAGE`).
+/// - `case`: Case handling applied to every pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Grep {
+    pub patterns: Vec<String>,
+    pub field: String,
+    pub case: CaseSensitivity,
+}
+
+/// Position of a pattern match within a field value
+///
+/// Fields:
+/// - `field`: Name of the field that was matched (e.g. `MESSAGE`).
+/// - `start`: Byte offset where the match starts.
+/// - `end`: Byte offset just past the end of the match.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldMatch {
+    pub field: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Grep {
+    /// Create a grep filter for a single pattern against `MESSAGE`
+    pub fn new<S: Into<String>>(pattern: S) -> Self {
+        Grep {
+            patterns: vec![pattern.into()],
+            field: "MESSAGE".to_string(),
+            case: CaseSensitivity::default(),
+        }
+    }
+
+    /// Compile the patterns, reporting the first invalid one as an error
+    pub(crate) fn compile(&self) -> Result<CompiledGrep> {
+        let regexes = self
+            .patterns
+            .iter()
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(ignores_case(pattern, self.case))
+                    .build()
+                    .map_err(|e| JournalError::InvalidPattern(e.to_string()))
+            })
+            .collect::<Result<Vec<Regex>>>()?;
+
+        Ok(CompiledGrep {
+            field: self.field.clone(),
+            regexes,
+        })
+    }
+}
+
+/// Decide whether a pattern should ignore case under the given mode
+fn ignores_case(pattern: &str, case: CaseSensitivity) -> bool {
+    match case {
+        CaseSensitivity::Sensitive => false,
+        CaseSensitivity::Insensitive => true,
+        CaseSensitivity::Smart => !has_uppercase(pattern),
+    }
+}
+
+/// Check for uppercase letters, skipping escape sequences such as `\S` or `\W`
+fn has_uppercase(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+/// A grep filter with its patterns compiled, ready to run against entries
+#[derive(Debug, Clone)]
+pub(crate) struct CompiledGrep {
+    pub(crate) field: String,
+    regexes: Vec<Regex>,
+}
+
+impl CompiledGrep {
+    /// Find every match of every pattern in `value`, ordered by position
+    ///
+    /// An empty result means the value does not match.
+    pub(crate) fn find(&self, value: &str) -> Vec<FieldMatch> {
+        let mut matches: Vec<FieldMatch> = self
+            .regexes
+            .iter()
+            .flat_map(|regex| regex.find_iter(value))
+            .map(|m| FieldMatch {
+                field: self.field.clone(),
+                start: m.start(),
+                end: m.end(),
+            })
+            .collect();
+
+        matches.sort_by_key(|m| (m.start, m.end));
+        matches.dedup();
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grep(patterns: &[&str], case: CaseSensitivity) -> CompiledGrep {
+        Grep {
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            field: "MESSAGE".to_string(),
+            case,
+        }
+        .compile()
+        .expect("patterns should compile")
+    }
+
+    #[test]
+    fn test_case_sensitive_matching() {
+        let compiled = grep(&["error"], CaseSensitivity::Sensitive);
+        assert!(compiled.find("Error: Connection timeout").is_empty());
+        assert_eq!(compiled.find("an error occurred").len(), 1);
+    }
+
+    #[test]
+    fn test_case_insensitive_matching() {
+        let compiled = grep(&["ERROR"], CaseSensitivity::Insensitive);
+        let matches = compiled.find("Error: Connection timeout");
+        assert_eq!(matches, vec![FieldMatch { field: "MESSAGE".to_string(), start: 0, end: 5 }]);
+    }
+
+    #[test]
+    fn test_smart_case_matching() {
+        // Lowercase pattern ignores case
+        let lower = grep(&["error"], CaseSensitivity::Smart);
+        assert_eq!(lower.find("Error: Connection timeout").len(), 1);
+
+        // Any uppercase letter makes the pattern case-sensitive
+        let upper = grep(&["Error"], CaseSensitivity::Smart);
+        assert!(upper.find("ERROR: Connection timeout").is_empty());
+        assert_eq!(upper.find("Error: Connection timeout").len(), 1);
+
+        // Escape sequences like \S do not count as uppercase
+        let escaped = grep(&[r"disk\Susage"], CaseSensitivity::Smart);
+        assert_eq!(escaped.find("Disk usage critical").len(), 0);
+        assert_eq!(escaped.find("DISK-USAGE critical").len(), 1);
+    }
+
+    #[test]
+    fn test_multi_pattern_positions() {
+        let compiled = grep(&["timeout", "conn\\w+"], CaseSensitivity::Smart);
+        let matches = compiled.find("Error: Connection timeout after 30 seconds");
+
+        let spans: Vec<(usize, usize)> = matches.iter().map(|m| (m.start, m.end)).collect();
+        assert_eq!(spans, vec![(7, 17), (18, 25)]);
+    }
+
+    #[test]
+    fn test_duplicate_spans_collapsed() {
+        let compiled = grep(&["HTTP", "HTTP"], CaseSensitivity::Sensitive);
+        assert_eq!(compiled.find("HTTP GET /api/health - 200 OK").len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let result = Grep::new("(unclosed").compile();
+        assert!(matches!(result, Err(JournalError::InvalidPattern(_))));
+    }
+}
@@ -0,0 +1,358 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+use crate::control::{CancellationToken, ScanControl};
+use crate::error::{JournalError, Result};
+use crate::journal::Journal;
+use crate::query::{apply_limit, pin_boot, query_journal_with_control, receive_range, Entry, Query};
+use crate::source::{entry_identity, JournalFiles, JournalSource};
+
+/// Query journal entries on several threads
+///
+/// Returns the same entries as [`query_journal`], but the source's journal
+/// files are split by the time range they cover into up to `workers` groups.
+/// Each group is queried on its own thread and the results are merged by
+/// timestamp. Files whose first and last entries show they end before or
+/// start after the query's time range are skipped, so a narrow query over
+/// many archived files only reads the files it needs.
+///
+/// Queries with `context` run on the calling thread, since context can cross
+/// from one file to the next.
+///
+/// # Returns
+/// The matching entries, or `JournalError::InvalidArgument` if `workers` is zero
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{query_journal_parallel, Query};
+/// use std::path::Path;
+/// use std::thread;
+///
+/// let workers = thread::available_parallelism().map_or(4, |n| n.get());
+/// let query = Query::all().since("-7d")?.unit("nginx.service");
+///
+/// let entries = query_journal_parallel(Path::new("/var/log/journal"), query, workers)?;
+/// println!("{} entries", entries.len());
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+pub fn query_journal_parallel<S: JournalSource + ?Sized>(
+    source: &S,
+    query: Query,
+    workers: usize,
+) -> Result<Vec<Entry>> {
+    query_journal_parallel_with_control(source, query, workers, &mut ScanControl::new())
+}
+
+/// Query journal entries on several threads, with cancellation, a deadline
+/// and progress reporting
+///
+/// Like [`query_journal_parallel`], but stops every worker with
+/// `JournalError::Cancelled` or `JournalError::DeadlineExceeded` as `control`
+/// directs. Progress counts the entries read by all workers together and
+/// carries no position or estimate. See [`ScanControl`].
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{query_journal_parallel_with_control, CancellationToken, Query, ScanControl};
+/// use std::path::Path;
+/// use std::time::Duration;
+///
+/// let token = CancellationToken::new();
+/// let mut control = ScanControl::new()
+///     .cancellation(token.clone())
+///     .timeout(Duration::from_secs(30));
+///
+/// let entries = query_journal_parallel_with_control(Path::new("/var/log/journal"), Query::all(), 8, &mut control)?;
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+pub fn query_journal_parallel_with_control<S: JournalSource + ?Sized>(
+    source: &S,
+    mut query: Query,
+    workers: usize,
+    control: &mut ScanControl,
+) -> Result<Vec<Entry>> {
+    if workers == 0 {
+        return Err(JournalError::InvalidArgument);
+    }
+    if query.context.is_some() {
+        return query_journal_with_control(source, query, control);
+    }
+
+    // An indexed source only reads the files that can hold matches
+    if let Some(index) = source.index() {
+        if let Some(files) = index.files_for(source, &mut query)? {
+            return query_journal_parallel_with_control(&files, query, workers, control);
+        }
+    }
+
+    // Boot offsets count the boots of the whole source
+    control.check()?;
+    pin_boot(source, &mut query)?;
+
+    let groups = split_files(source.journal_files()?, &query, workers);
+    let duplicates = source.has_duplicates();
+    control.check()?;
+
+    // Workers stop on a shared token, cancelled when the caller's control
+    // says to stop or when one of them fails
+    let stop = CancellationToken::new();
+    let deadline = control.deadline_at();
+    let scanned: Vec<_> = groups.iter().map(|_| AtomicU64::new(0)).collect();
+
+    let (stopped, results) = thread::scope(|scope| {
+        let handles: Vec<_> = groups
+            .into_iter()
+            .zip(&scanned)
+            .map(|(files, scanned)| {
+                let query = query.clone();
+                let stop = stop.clone();
+                scope.spawn(move || {
+                    let mut control = ScanControl::new()
+                        .cancellation(stop.clone())
+                        .progress_interval(PROGRESS_INTERVAL)
+                        .on_progress(|progress| scanned.store(progress.entries_scanned, Ordering::Relaxed));
+                    if let Some(deadline) = deadline {
+                        control = control.deadline(deadline);
+                    }
+                    let result = query_journal_with_control(&JournalFiles { files, duplicates }, query, &mut control);
+                    if result.is_err() {
+                        stop.cancel();
+                    }
+                    result
+                })
+            })
+            .collect();
+
+        let mut stopped = Ok(());
+        while !handles.iter().all(|handle| handle.is_finished()) {
+            if stopped.is_ok() {
+                stopped = control.update(scanned.iter().map(|count| count.load(Ordering::Relaxed)).sum());
+                if stopped.is_err() {
+                    stop.cancel();
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        let results: Vec<_> = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|payload| panic::resume_unwind(payload)))
+            .collect();
+        (stopped, results)
+    });
+    stopped?;
+
+    // A failing worker cancels the others, so its error is the one to report
+    let mut lists = Vec::with_capacity(results.len());
+    let mut cancelled = None;
+    for result in results {
+        match result {
+            Ok(list) => lists.push(list),
+            Err(JournalError::Cancelled) => cancelled = Some(JournalError::Cancelled),
+            Err(error) => return Err(error),
+        }
+    }
+    if let Some(error) = cancelled {
+        return Err(error);
+    }
+
+    let mut entries = merge_ordered(lists, query.reverse, duplicates);
+    if let Some(limit) = query.limit {
+        apply_limit(&mut entries, limit, query.reverse);
+    }
+
+    control.update(scanned.iter().map(|count| count.load(Ordering::Relaxed)).sum())?;
+    control.finish();
+    Ok(entries)
+}
+
+/// Entries a worker reads between progress updates
+const PROGRESS_INTERVAL: u64 = 100;
+
+/// How often the calling thread checks its control while workers run
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Group the files that can hold entries in the query's range into at most
+/// `workers` groups of neighbouring time ranges
+fn split_files(files: Vec<PathBuf>, query: &Query, workers: usize) -> Vec<Vec<PathBuf>> {
+    let (start, end) = receive_range(query).unwrap_or((0, u64::MAX));
+
+    let mut ranges: Vec<_> = file_ranges(files, workers)
+        .into_iter()
+        .filter(|((first, last), _)| *first <= end && *last >= start)
+        .collect();
+    ranges.sort();
+
+    let size = ranges.len().div_ceil(workers).max(1);
+    let mut groups = Vec::new();
+    let mut ranges = ranges.into_iter().peekable();
+    while ranges.peek().is_some() {
+        groups.push(ranges.by_ref().take(size).map(|(_, file)| file).collect());
+    }
+    groups
+}
+
+/// Read the time range of each file, spread over `workers` threads
+///
+/// Files that are empty or cannot be read are left out.
+fn file_ranges(files: Vec<PathBuf>, workers: usize) -> Vec<((u64, u64), PathBuf)> {
+    let size = files.len().div_ceil(workers).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = files
+            .chunks(size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .filter_map(|file| file_range(file).map(|range| (range, file.clone())))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_else(|payload| panic::resume_unwind(payload)))
+            .collect()
+    })
+}
+
+/// Receive times of the first and last entries in a journal file
+///
+/// `None` if the file is empty or cannot be read, as files that cannot be
+/// read are skipped when a directory is opened.
+fn file_range(file: &Path) -> Option<(u64, u64)> {
+    let journal = Journal::open_files(vec![file]).ok()?;
+    journal.seek_head().ok()?;
+    if !journal.next().ok()? {
+        return None;
+    }
+    let first = journal.get_realtime_usec().ok()?;
+
+    journal.seek_tail().ok()?;
+    if !journal.previous().ok()? {
+        return None;
+    }
+    let last = journal.get_realtime_usec().ok()?;
+
+    Some((first.min(last), first.max(last)))
+}
+
+/// Merge entry lists that are each oldest first, or newest first if `reverse`
+///
+/// Entries with equal timestamps keep the order of their lists. With
+/// `duplicates`, entries already taken from another list are dropped.
+fn merge_ordered(lists: Vec<Vec<Entry>>, reverse: bool, duplicates: bool) -> Vec<Entry> {
+    let count = lists.len();
+    let order = |entry: &Entry, index: usize| {
+        if reverse {
+            Reverse((u64::MAX - entry.timestamp_utc, count - index))
+        } else {
+            Reverse((entry.timestamp_utc, index))
+        }
+    };
+
+    let mut lists: Vec<_> = lists.into_iter().map(|list| list.into_iter().peekable()).collect();
+    let mut heap = BinaryHeap::new();
+    for (index, list) in lists.iter_mut().enumerate() {
+        if let Some(entry) = list.peek() {
+            heap.push((order(entry, index), index));
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+    while let Some((_, index)) = heap.pop() {
+        let Some(entry) = lists[index].next() else {
+            continue;
+        };
+        if let Some(next) = lists[index].peek() {
+            heap.push((order(next, index), index));
+        }
+
+        let copy = duplicates
+            && entry.cursor.as_deref().is_some_and(|cursor| !seen.insert(entry_identity(cursor)));
+        if !copy {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp_utc: u64, cursor: &str) -> Entry {
+        Entry {
+            hostname: None,
+            unit: None,
+            timestamp_utc,
+            message: cursor.to_string(),
+            matches: Vec::new(),
+            monotonic_usec: None,
+            boot_id: None,
+            fields: Default::default(),
+            cursor: Some(cursor.to_string()),
+            is_context: false,
+        }
+    }
+
+    fn messages(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.message.as_str()).collect()
+    }
+
+    #[test]
+    fn test_merge_ordered() {
+        let lists = vec![
+            vec![entry(1, "s=1;b=a;t=1"), entry(4, "s=1;b=a;t=4"), entry(5, "s=1;b=a;t=5")],
+            vec![entry(2, "s=2;b=a;t=2"), entry(4, "s=2;b=b;t=4")],
+            vec![],
+        ];
+        let merged = merge_ordered(lists, false, false);
+        assert_eq!(messages(&merged), vec!["s=1;b=a;t=1", "s=2;b=a;t=2", "s=1;b=a;t=4", "s=2;b=b;t=4", "s=1;b=a;t=5"]);
+    }
+
+    #[test]
+    fn test_merge_ordered_reverse() {
+        let lists = vec![
+            vec![entry(5, "s=1;t=5"), entry(1, "s=1;t=1")],
+            vec![entry(5, "s=2;t=5b"), entry(3, "s=2;t=3")],
+        ];
+        let merged = merge_ordered(lists, true, false);
+        assert_eq!(messages(&merged), vec!["s=2;t=5b", "s=1;t=5", "s=2;t=3", "s=1;t=1"]);
+    }
+
+    #[test]
+    fn test_merge_ordered_drops_copies() {
+        let lists = vec![
+            vec![entry(1, "s=1;i=1;b=a;m=1;t=1;x=1"), entry(2, "s=1;i=2;b=a;m=2;t=2;x=2")],
+            vec![entry(1, "s=2;i=7;b=a;m=1;t=1;x=1"), entry(3, "s=2;i=8;b=a;m=3;t=3;x=3")],
+        ];
+        let merged = merge_ordered(lists.clone(), false, true);
+        assert_eq!(messages(&merged), vec![
+            "s=1;i=1;b=a;m=1;t=1;x=1",
+            "s=1;i=2;b=a;m=2;t=2;x=2",
+            "s=2;i=8;b=a;m=3;t=3;x=3",
+        ]);
+        assert_eq!(merge_ordered(lists, false, false).len(), 4);
+    }
+
+    #[test]
+    fn test_zero_workers_rejected() {
+        let result = query_journal_parallel(Path::new("/nonexistent"), Query::all(), 0);
+        assert_eq!(result, Err(JournalError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_cancelled_before_start() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut control = ScanControl::new().cancellation(token);
+        let result = query_journal_parallel_with_control(Path::new("/nonexistent"), Query::all(), 4, &mut control);
+        assert_eq!(result, Err(JournalError::Cancelled));
+    }
+}
@@ -1,22 +1,203 @@
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::ControlFlow;
+use std::time::Duration;
 use crate::journal::Journal;
 use crate::error::JournalError;
+use crate::alias::{hostnames_of, HostAliases};
+use crate::boot::BootSelector;
+use crate::control::{fraction_between, ScanControl};
+use crate::matcher::{CaseSensitivity, CompiledGrep, FieldMatch, Grep};
+use crate::source::{JournalSource, SeenEntries};
+use crate::timespec::parse_timestamp;
 
 /// Represents a query for filtering journal entries.
 ///
 /// Fields:
-/// - `hostname`: Optional hostname to filter by (`_HOSTNAME` field).
-/// - `unit`: Optional systemd unit to filter by (`_SYSTEMD_UNIT` field).
+/// - `hostname`: Optional hostname to filter by (`_HOSTNAME` field, see `host_aliases`).
+/// - `host_aliases`: Optional alias map; the hostname filter then selects entries logged
+///   under any name of the same host.
+/// - `unit`: Optional systemd unit to filter by (`_SYSTEMD_UNIT` field, see `unit_match`).
+/// - `units`: Further systemd units, as given by repeating `journalctl -u`. Entries of any
+///   of `unit` and `units` are kept.
+/// - `user_units`: Systemd user units to filter by (`_SYSTEMD_USER_UNIT` field, see
+///   `unit_match`). Entries of any of them or of a system unit filter are kept.
+/// - `unit_match`: How `unit`, `units` and `user_units` are matched.
 /// - `start_time_utc`: Start of the time range (inclusive), in microseconds since Unix epoch (UTC).
 /// - `end_time_utc`: End of the time range (inclusive), in microseconds since Unix epoch (UTC).
 /// - `message_contains`: Optional substring to match within the `MESSAGE` field.
-#[derive(Debug, Clone, PartialEq)]
+/// - `grep`: Optional regular expression filter, similar to `journalctl --grep`.
+/// - `boot`: Optional boot to restrict the query to (`_BOOT_ID` field), similar to `journalctl -b`.
+/// - `boot_window`: Optional (start, end) range in microseconds since boot (inclusive), matched
+///   against the monotonic clock of the selected boot (the current boot if `boot` is unset).
+/// - `projection`: Which fields are materialized on each returned `Entry`.
+/// - `data_threshold`: Optional limit in bytes on how much of each field is read.
+/// - `window_mode`: How strictly the scan stops at the end of the time range.
+/// - `timestamp_source`: Which timestamp the time range, ordering and `Entry::timestamp_utc` use.
+/// - `matches`: Field matches in the style of `journalctl FIELD=VALUE`. The inner lists are
+///   alternatives (`+` on the command line); within one, matches on the same field are OR'ed
+///   and matches on different fields are AND'ed.
+/// - `identifiers`: Syslog identifiers to filter by (`SYSLOG_IDENTIFIER` field). Entries with
+///   any of them are kept.
+/// - `priority`: Optional (most, least) severe `PRIORITY` to keep (inclusive), where `0` is
+///   `emerg` and `7` is `debug`.
+/// - `limit`: Optional maximum number of entries, keeping the most recent ones like `journalctl -n`.
+/// - `reverse`: Return the newest entries first, like `journalctl -r`.
+/// - `context`: Optional entries to return around each `message_contains` or `grep` match.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
 pub struct Query {
     pub hostname: Option<String>,
+    pub host_aliases: Option<HostAliases>,
     pub unit: Option<String>,
+    pub units: Vec<String>,
+    pub user_units: Vec<String>,
+    pub unit_match: UnitMatch,
     pub start_time_utc: u64,
     pub end_time_utc: u64,
     pub message_contains: Option<String>,
+    pub grep: Option<Grep>,
+    pub boot: Option<BootSelector>,
+    pub boot_window: Option<(u64, u64)>,
+    pub projection: Projection,
+    pub data_threshold: Option<usize>,
+    pub window_mode: WindowMode,
+    pub timestamp_source: TimestampSource,
+    pub matches: Vec<Vec<(String, String)>>,
+    pub identifiers: Vec<String>,
+    pub priority: Option<(u8, u8)>,
+    pub limit: Option<usize>,
+    pub reverse: bool,
+    pub context: Option<Context>,
+}
+
+/// Selects which fields a query materializes on each `Entry`
+///
+/// - `Default`: `_HOSTNAME`, `_SYSTEMD_UNIT` and `MESSAGE`.
+/// - `Fields`: exactly the listed fields. `_HOSTNAME`, `_SYSTEMD_UNIT` and `MESSAGE`
+///   fill their dedicated `Entry` slots, and any others go into `Entry::fields`.
+/// - `All`: every field of the entry.
+/// - `TimestampsOnly`: no fields at all, only the timestamps. Useful for counting.
+///
+/// Fields needed for `message_contains` or `grep` are still read for
+/// filtering, but only appear on the entry if they are projected.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Projection {
+    #[default]
+    Default,
+    Fields(Vec<String>),
+    All,
+    TimestampsOnly,
+}
+
+/// Controls which entries a unit filter selects
+///
+/// - `Exact`: only entries logged by the unit's own processes (`_SYSTEMD_UNIT`, or
+///   `_SYSTEMD_USER_UNIT` for user units).
+/// - `Journalctl`: also the service manager's messages about the unit, such as
+///   "Started", "Stopped" and "Failed", plus coredumps and messages from privileged
+///   processes about it. This is what `journalctl -u` and `journalctl --user-unit` show.
+///
+/// For a system unit, `Journalctl` matches `_SYSTEMD_UNIT`, or `UNIT` on messages from
+/// PID 1, or `OBJECT_SYSTEMD_UNIT` on messages from root (`_UID=0`), or `COREDUMP_UNIT`
+/// on systemd-coredump's messages from root. For a user unit it matches
+/// `_SYSTEMD_USER_UNIT`, or `USER_UNIT`, or `OBJECT_SYSTEMD_USER_UNIT` on messages from
+/// root, or `COREDUMP_USER_UNIT` on systemd-coredump's messages from root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum UnitMatch {
+    #[default]
+    Exact,
+    Journalctl,
+}
+
+/// `MESSAGE_ID` of systemd-coredump's "Process dumped core" message
+pub(crate) const COREDUMP_MESSAGE_ID: &str = "fc2e22bc6ee647b6b90729ab34a250b1";
+
+impl UnitMatch {
+    /// The alternative sets of matches selecting a system unit, as in journalctl
+    fn unit_matches(self, unit: &str) -> Vec<Vec<(String, String)>> {
+        let mut alternatives = vec![vec![field("_SYSTEMD_UNIT", unit)]];
+        if self == UnitMatch::Journalctl {
+            alternatives.push(vec![
+                field("MESSAGE_ID", COREDUMP_MESSAGE_ID),
+                field("_UID", "0"),
+                field("COREDUMP_UNIT", unit),
+            ]);
+            alternatives.push(vec![field("_PID", "1"), field("UNIT", unit)]);
+            alternatives.push(vec![field("_UID", "0"), field("OBJECT_SYSTEMD_UNIT", unit)]);
+        }
+        alternatives
+    }
+
+    /// The alternative sets of matches selecting a user unit, as in journalctl
+    /// without the restriction to the current user
+    fn user_unit_matches(self, unit: &str) -> Vec<Vec<(String, String)>> {
+        let mut alternatives = vec![vec![field("_SYSTEMD_USER_UNIT", unit)]];
+        if self == UnitMatch::Journalctl {
+            alternatives.push(vec![field("USER_UNIT", unit)]);
+            alternatives.push(vec![
+                field("MESSAGE_ID", COREDUMP_MESSAGE_ID),
+                field("_UID", "0"),
+                field("COREDUMP_USER_UNIT", unit),
+            ]);
+            alternatives.push(vec![field("_UID", "0"), field("OBJECT_SYSTEMD_USER_UNIT", unit)]);
+        }
+        alternatives
+    }
+}
+
+fn field(name: &str, value: &str) -> (String, String) {
+    (name.to_string(), value.to_string())
+}
+
+/// Entries returned around each match of `message_contains` or `grep`
+///
+/// - `Entries { before, after }`: up to `before` entries preceding and `after` entries
+///   following each match, among the entries the other filters select, like `grep -B`/`-A`.
+/// - `Surrounding(window)`: every entry from the same host, of any unit, received
+///   within `window` before or after each match.
+///
+/// Context entries have `Entry::is_context` set and are not subject to the text
+/// filters or the time range. An entry near several matches is returned once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Context {
+    Entries { before: usize, after: usize },
+    Surrounding(#[cfg_attr(feature = "serde", serde(with = "crate::schema::duration_usec"))] Duration),
+}
+
+/// Controls where a query starts and stops scanning the journal
+///
+/// - `Strict`: seek to the start of the range and stop at the first entry past its end.
+///   This is the fastest mode, but when files or hosts with skewed clocks are
+///   interleaved, a single out-of-order timestamp ends the scan early.
+/// - `Tolerant(tolerance)`: start scanning `tolerance` before the range and keep going
+///   until entries are `tolerance` past its end, skipping anything outside the range.
+///   Results are sorted by timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum WindowMode {
+    #[default]
+    Strict,
+    Tolerant(#[cfg_attr(feature = "serde", serde(with = "crate::schema::duration_usec"))] Duration),
+}
+
+/// Selects which timestamp a query filters and orders entries by
+///
+/// - `Receive`: when journald received the entry (`__REALTIME_TIMESTAMP`).
+/// - `Source`: when the originating process logged it (`_SOURCE_REALTIME_TIMESTAMP`),
+///   falling back to the receive time for entries without one. Results are sorted by
+///   this timestamp.
+///
+/// The journal can only seek by receive time, so `Source` is best combined with
+/// `WindowMode::Tolerant` to pick up entries that were received late.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum TimestampSource {
+    #[default]
+    Receive,
+    Source,
 }
 
 /// Represents a single journal entry returned by a query.
@@ -24,14 +205,50 @@ pub struct Query {
 /// Fields:
 /// - `hostname`: Hostname from the `_HOSTNAME` field, if present.
 /// - `unit`: Systemd unit from the `_SYSTEMD_UNIT` field, if present.
-/// - `timestamp_utc`: Timestamp of the entry in microseconds since Unix epoch (UTC).
+/// - `timestamp_utc`: Timestamp of the entry in microseconds since Unix epoch (UTC), taken
+///   from the query's `timestamp_source`.
 /// - `message`: The log message (`MESSAGE` field).
+/// - `matches`: Positions of `grep` pattern matches, empty when the query has no `grep`.
+/// - `monotonic_usec`: Microseconds since the entry's boot started, if available.
+/// - `boot_id`: The boot the entry was logged in (`_BOOT_ID`), if available.
+/// - `fields`: Projected fields without a dedicated slot, keyed by field name.
+/// - `cursor`: The journal cursor of the entry, if available.
+/// - `is_context`: Whether the entry was only returned as context around a match.
+///
+/// `hostname`, `unit` and `message` are `None`/empty when the query's projection leaves them out.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Entry {
     pub hostname: Option<String>,
     pub unit: Option<String>,
     pub timestamp_utc: u64,
     pub message: String,
+    pub matches: Vec<FieldMatch>,
+    pub monotonic_usec: Option<u64>,
+    pub boot_id: Option<String>,
+    pub fields: BTreeMap<String, String>,
+    pub cursor: Option<String>,
+    pub is_context: bool,
+}
+
+impl Entry {
+    /// The entry timestamp as a `chrono` UTC date-time
+    ///
+    /// `None` if the timestamp is past the dates `chrono` can represent.
+    #[cfg(feature = "chrono")]
+    pub fn datetime(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        i64::try_from(self.timestamp_utc)
+            .ok()
+            .and_then(chrono::DateTime::from_timestamp_micros)
+    }
+
+    /// The entry timestamp as a `time` UTC date-time
+    ///
+    /// `None` if the timestamp is past the dates `time` can represent.
+    #[cfg(feature = "time")]
+    pub fn offset_datetime(&self) -> Option<time::OffsetDateTime> {
+        time::OffsetDateTime::from_unix_timestamp_nanos(i128::from(self.timestamp_utc) * 1_000).ok()
+    }
 }
 
 impl Query {
@@ -39,43 +256,359 @@ impl Query {
     pub fn new(start_time_utc: u64, end_time_utc: u64) -> Self {
         Query {
             hostname: None,
+            host_aliases: None,
             unit: None,
+            units: Vec::new(),
+            user_units: Vec::new(),
+            unit_match: UnitMatch::Exact,
             start_time_utc,
             end_time_utc,
             message_contains: None,
+            grep: None,
+            boot: None,
+            boot_window: None,
+            projection: Projection::Default,
+            data_threshold: None,
+            window_mode: WindowMode::Strict,
+            timestamp_source: TimestampSource::Receive,
+            matches: Vec::new(),
+            identifiers: Vec::new(),
+            priority: None,
+            limit: None,
+            reverse: false,
+            context: None,
         }
     }
 
+    /// Create a query over all time, to be narrowed with `since` and `until`
+    pub fn all() -> Self {
+        Query::new(0, u64::MAX)
+    }
+
+    /// Set the start of the time range from a journalctl-style time spec
+    ///
+    /// Accepts specs such as `"-1h"`, `"yesterday"`, `"2024-03-01 10:00:00"`,
+    /// RFC 3339 timestamps with offsets, and `"@1640995200"`. Calendar times
+    /// without an offset are read in the local time zone. See
+    /// [`crate::timespec::parse_timestamp_at`] for the full list.
+    ///
+    /// # Examples
+    /// ```
+    /// use journald_query::Query;
+    ///
+    /// let query = Query::all().since("-1h")?.until("now")?;
+    /// let open_ended = Query::all().since("2024-03-01T10:00:00+01:00")?;
+    /// assert_eq!(open_ended.end_time_utc, u64::MAX);
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn since(mut self, spec: &str) -> Result<Self, JournalError> {
+        self.start_time_utc = parse_timestamp(spec)?;
+        Ok(self)
+    }
+
+    /// Set the end of the time range from a journalctl-style time spec
+    pub fn until(mut self, spec: &str) -> Result<Self, JournalError> {
+        self.end_time_utc = parse_timestamp(spec)?;
+        Ok(self)
+    }
+
     /// Filter by hostname
     pub fn hostname<S: Into<String>>(mut self, hostname: S) -> Self {
         self.hostname = Some(hostname.into());
         self
     }
 
+    /// Match the hostname filter against every name of the host
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use journald_query::{query_journal, HostAliases, Query};
+    ///
+    /// // Selects entries from "web1" and "web1.example.com" alike
+    /// let aliases = HostAliases::new().strip_domains(true);
+    /// let query = Query::all().hostname("web1").host_aliases(aliases);
+    /// let entries = query_journal("/var/log/journal", query)?;
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn host_aliases(mut self, aliases: HostAliases) -> Self {
+        self.host_aliases = Some(aliases);
+        self
+    }
+
     /// Filter by systemd unit
     pub fn unit<S: Into<String>>(mut self, unit: S) -> Self {
         self.unit = Some(unit.into());
         self
     }
 
+    /// Filter by systemd user unit
+    ///
+    /// Can be given several times; entries of any of the user units are kept.
+    pub fn user_unit<S: Into<String>>(mut self, unit: S) -> Self {
+        self.user_units.push(unit.into());
+        self
+    }
+
+    /// Choose how `unit`, `units` and `user_units` are matched
+    ///
+    /// # Examples
+    /// ```
+    /// use journald_query::{Query, UnitMatch};
+    ///
+    /// // Like `journalctl -u failing.service`, including systemd's
+    /// // "Failed to start" messages about it
+    /// let query = Query::all()
+    ///     .unit("failing.service")
+    ///     .unit_match(UnitMatch::Journalctl);
+    /// ```
+    pub fn unit_match(mut self, mode: UnitMatch) -> Self {
+        self.unit_match = mode;
+        self
+    }
+
+    /// Filter by boot
+    ///
+    /// # Examples
+    /// ```
+    /// use journald_query::{BootSelector, Query};
+    ///
+    /// // Everything from the previous boot
+    /// let query = Query::all().boot(BootSelector::Offset(-1));
+    /// ```
+    pub fn boot(mut self, boot: BootSelector) -> Self {
+        self.boot = Some(boot);
+        self
+    }
+
+    /// Restrict the query to a window relative to the start of the boot
+    ///
+    /// The window uses the monotonic clock, so it is not thrown off by
+    /// wallclock jumps such as NTP corrections early in boot. Without an
+    /// explicit `boot`, the current boot is used.
+    ///
+    /// # Examples
+    /// ```
+    /// use journald_query::{BootSelector, Query};
+    /// use std::time::Duration;
+    ///
+    /// // The first 30 seconds of the previous boot
+    /// let query = Query::all()
+    ///     .boot(BootSelector::Offset(-1))
+    ///     .boot_window(Duration::ZERO, Duration::from_secs(30));
+    /// ```
+    pub fn boot_window(mut self, start: Duration, end: Duration) -> Self {
+        let usec = |duration: Duration| u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+        self.boot_window = Some((usec(start), usec(end)));
+        self
+    }
+
+    /// Choose which fields are materialized on each entry
+    ///
+    /// # Examples
+    /// ```
+    /// use journald_query::{Projection, Query};
+    ///
+    /// let query = Query::all().projection(Projection::Fields(vec![
+    ///     "MESSAGE".to_string(),
+    ///     "PRIORITY".to_string(),
+    ///     "_PID".to_string(),
+    /// ]));
+    /// ```
+    pub fn projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Limit how many bytes of each field are read
+    ///
+    /// Larger fields may come back truncated. This bounds the cost of
+    /// entries with huge payloads.
+    pub fn data_threshold(mut self, bytes: usize) -> Self {
+        self.data_threshold = Some(bytes);
+        self
+    }
+
+    /// Choose how strictly the scan stops at the end of the time range
+    ///
+    /// # Examples
+    /// ```
+    /// use journald_query::{Query, WindowMode};
+    /// use std::time::Duration;
+    ///
+    /// // Tolerate up to five minutes of clock skew between merged files
+    /// let query = Query::all()
+    ///     .since("-1h")?
+    ///     .window_mode(WindowMode::Tolerant(Duration::from_secs(300)));
+    /// # Ok::<(), journald_query::JournalError>(())
+    /// ```
+    pub fn window_mode(mut self, mode: WindowMode) -> Self {
+        self.window_mode = mode;
+        self
+    }
+
+    /// Choose which timestamp the time range and ordering use
+    pub fn timestamp_source(mut self, source: TimestampSource) -> Self {
+        self.timestamp_source = source;
+        self
+    }
+
+    /// Require a field to have a value, like `FIELD=VALUE` on the `journalctl` command line
+    ///
+    /// The match joins the current group of alternatives. Matches on the
+    /// same field are OR'ed, matches on different fields are AND'ed.
+    ///
+    /// # Examples
+    /// ```
+    /// use journald_query::Query;
+    ///
+    /// // _SYSTEMD_UNIT=a.service _PID=12 + _HOSTNAME=b
+    /// let query = Query::all()
+    ///     .add_match("_SYSTEMD_UNIT", "a.service")
+    ///     .add_match("_PID", "12")
+    ///     .add_disjunction()
+    ///     .add_match("_HOSTNAME", "b");
+    /// assert_eq!(query.matches.len(), 2);
+    /// ```
+    pub fn add_match<F: Into<String>, V: Into<String>>(mut self, field: F, value: V) -> Self {
+        if self.matches.is_empty() {
+            self.matches.push(Vec::new());
+        }
+        if let Some(group) = self.matches.last_mut() {
+            group.push((field.into(), value.into()));
+        }
+        self
+    }
+
+    /// Start a new group of matches, OR'ed with the previous ones (`+` in `journalctl`)
+    pub fn add_disjunction(mut self) -> Self {
+        if self.matches.last().is_some_and(|group| !group.is_empty()) {
+            self.matches.push(Vec::new());
+        }
+        self
+    }
+
+    /// Filter by syslog identifier (`journalctl -t`)
+    ///
+    /// Can be given several times; entries with any of the identifiers are kept.
+    pub fn identifier<S: Into<String>>(mut self, identifier: S) -> Self {
+        self.identifiers.push(identifier.into());
+        self
+    }
+
+    /// Keep entries of the given priority or more severe (`journalctl -p`)
+    ///
+    /// Priorities follow syslog: `0` is `emerg`, `3` is `err`, `7` is `debug`.
+    pub fn priority(mut self, level: u8) -> Self {
+        self.priority = Some((0, level));
+        self
+    }
+
+    /// Keep entries with a priority between `most` and `least` severe, inclusive
+    pub fn priority_range(mut self, most: u8, least: u8) -> Self {
+        self.priority = Some((most, least));
+        self
+    }
+
+    /// Return at most `count` entries, keeping the most recent ones (`journalctl -n`)
+    pub fn limit(mut self, count: usize) -> Self {
+        self.limit = Some(count);
+        self
+    }
+
+    /// Return the newest entries first (`journalctl -r`)
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    /// Return entries before and after each text match (like `grep -B`/`-A`)
+    ///
+    /// # Examples
+    /// ```
+    /// use journald_query::{Context, Query};
+    ///
+    /// // Each error with the 5 entries that led up to it
+    /// let query = Query::all().grep("error").context(5, 0);
+    /// assert_eq!(query.context, Some(Context::Entries { before: 5, after: 0 }));
+    /// ```
+    pub fn context(mut self, before: usize, after: usize) -> Self {
+        self.context = Some(Context::Entries { before, after });
+        self
+    }
+
+    /// Return every entry from the same host within `window` of each text match
+    ///
+    /// # Examples
+    /// ```
+    /// use journald_query::Query;
+    /// use std::time::Duration;
+    ///
+    /// // Everything the host logged in the 30 seconds around each panic
+    /// let query = Query::all().grep("panic").surrounding(Duration::from_secs(30));
+    /// ```
+    pub fn surrounding(mut self, window: Duration) -> Self {
+        self.context = Some(Context::Surrounding(window));
+        self
+    }
+
     /// Filter by message content (substring match)
     pub fn message_contains<S: Into<String>>(mut self, message: S) -> Self {
         self.message_contains = Some(message.into());
         self
     }
+
+    /// Filter by a regular expression (like `journalctl --grep`)
+    ///
+    /// Calling this more than once adds further patterns; an entry is kept
+    /// if any of them matches. Patterns use smart case by default.
+    pub fn grep<S: Into<String>>(mut self, pattern: S) -> Self {
+        match &mut self.grep {
+            Some(grep) => grep.patterns.push(pattern.into()),
+            None => self.grep = Some(Grep::new(pattern)),
+        }
+        self
+    }
+
+    /// Match `grep` patterns against `field` instead of `MESSAGE`
+    pub fn grep_field<S: Into<String>>(mut self, field: S) -> Self {
+        self.grep_mut().field = field.into();
+        self
+    }
+
+    /// Set how `grep` patterns treat letter case
+    pub fn case_sensitivity(mut self, case: CaseSensitivity) -> Self {
+        self.grep_mut().case = case;
+        self
+    }
+
+    fn grep_mut(&mut self) -> &mut Grep {
+        self.grep.get_or_insert_with(|| Grep {
+            patterns: Vec::new(),
+            ..Grep::new("")
+        })
+    }
+}
+
+impl Default for Query {
+    /// A query over all time with no filters
+    fn default() -> Self {
+        Query::all()
+    }
 }
 
 /// Query journal entries with the given filters
 /// 
 /// This function applies the specified filters and returns all matching journal entries
-/// within the time range, sorted by timestamp.
+/// within the time range, sorted by timestamp. See [`WindowMode`] for how the end of
+/// the range is detected when timestamps are out of order.
 /// 
 /// # Arguments
-/// * `journal_dir` - Directory containing journal files
+/// * `source` - Journal directory, or a [`crate::MultiJournal`] over several
 /// * `query` - Query parameters including time range and optional filters
 /// 
 /// # Returns
-/// A vector of matching entries sorted by timestamp
+/// A vector of matching entries sorted by timestamp, or `JournalError::NotFound`
+/// if the query selects a boot offset that is not in the journal
 /// 
 /// # Examples
 /// ```no_run
@@ -95,62 +628,762 @@ impl Query {
 /// }
 /// # Ok::<(), journald_query::JournalError>(())
 /// ```
-pub fn query_journal(journal_dir: &Path, query: Query) -> Result<Vec<Entry>, JournalError> {
-    let journal = Journal::open_directory(journal_dir)?;
+pub fn query_journal<S: JournalSource + ?Sized>(source: &S, query: Query) -> Result<Vec<Entry>, JournalError> {
+    query_journal_with_control(source, query, &mut ScanControl::new())
+}
+
+/// Query journal entries, with cancellation, a deadline and progress reporting
+///
+/// Like [`query_journal`], but stops with `JournalError::Cancelled` or
+/// `JournalError::DeadlineExceeded` as `control` directs, and reports its
+/// progress to the callback set on `control`. See [`ScanControl`].
+pub fn query_journal_with_control<S: JournalSource + ?Sized>(
+    source: &S,
+    mut query: Query,
+    control: &mut ScanControl,
+) -> Result<Vec<Entry>, JournalError> {
+    // An indexed source only reads the files that can hold matches
+    if let Some(index) = source.index() {
+        if let Some(files) = index.files_for(source, &mut query)? {
+            return query_journal_with_control(&files, query, control);
+        }
+    }
+    
+    // Entries come in journal order, which only matches timestamp order
+    // when the clocks agree. Boot windows keep monotonic order.
+    let sorted = query.boot_window.is_none()
+        && (query.window_mode != WindowMode::Strict
+            || query.timestamp_source != TimestampSource::Receive);
+    
+    // A limit is served from the end of the range, stopping early unless
+    // the entries still need sorting
+    let direction = if query.reverse || query.limit.is_some() {
+        Direction::Backward
+    } else {
+        Direction::Forward
+    };
+    let early_limit = query.limit.filter(|_| !sorted);
+    
+    // Context entries skip the text filters
+    let context_query = Query {
+        message_contains: None,
+        grep: None,
+        ..query.clone()
+    };
+    let mut context_journal = None;
+    
+    let mut entries = Vec::new();
+    let mut seen = HashMap::new();
+    let mut hits = 0;
+    
+    scan_journal(source, &query, direction, control, |journal, grep, position| {
+        if let Some(mut entry) = read_entry(journal, &query, grep, position.timestamp)? {
+            let cursor = journal.get_cursor()?;
+            entry.monotonic_usec = Some(position.monotonic_usec);
+            entry.boot_id = Some(position.boot_id);
+            entry.cursor = Some(cursor.clone());
+            
+            let (preceding, following) = match query.context {
+                None => (Vec::new(), Vec::new()),
+                Some(Context::Entries { before, after }) => {
+                    neighbours(journal, &context_query, &cursor, before, after)?
+                }
+                Some(Context::Surrounding(window)) => match field_value(journal, "_HOSTNAME")? {
+                    Some(hostname) => {
+                        let context_journal = match context_journal {
+                            Some(ref journal) => journal,
+                            None => context_journal.insert(open_context_journal(source, &query)?),
+                        };
+                        surrounding(context_journal, &context_query, &hostname, &cursor, window)?
+                    }
+                    None => (Vec::new(), Vec::new()),
+                },
+            };
+            
+            // Keep the group in scan order, so a single reversal at the
+            // end puts everything in journal order
+            let mut group = preceding;
+            group.push(entry);
+            group.extend(following);
+            if direction == Direction::Backward {
+                group.reverse();
+            }
+            merge_group(&mut entries, &mut seen, group);
+            hits += 1;
+        }
+        if early_limit.is_some_and(|limit| hits >= limit) {
+            return Ok(ControlFlow::Break(()));
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+    
+    if sorted {
+        entries.sort_by_key(|entry| entry.timestamp_utc);
+    } else if direction == Direction::Backward {
+        entries.reverse();
+    }
+    
+    // Entries are now oldest first
+    if query.reverse {
+        entries.reverse();
+    }
+    if let Some(limit) = query.limit {
+        apply_limit(&mut entries, limit, query.reverse);
+    }
+    
+    Ok(entries)
+}
+
+/// Append a match and its context, skipping entries that are already present
+///
+/// A match that was already returned as context of an earlier match
+/// replaces the context entry in place.
+fn merge_group(entries: &mut Vec<Entry>, seen: &mut HashMap<String, usize>, group: Vec<Entry>) {
+    for entry in group {
+        let Some(cursor) = entry.cursor.clone() else {
+            entries.push(entry);
+            continue;
+        };
+        match seen.get(&cursor) {
+            Some(&index) => {
+                if !entry.is_context {
+                    entries[index] = entry;
+                }
+            }
+            None => {
+                seen.insert(cursor, entries.len());
+                entries.push(entry);
+            }
+        }
+    }
+}
+
+/// Keep `limit` matches, newest ones unless `reverse`, with the context next to them
+///
+/// `entries` are in output order: oldest first, or newest first if `reverse`.
+pub(crate) fn apply_limit(entries: &mut Vec<Entry>, limit: usize, reverse: bool) {
+    if reverse {
+        let mut hits = 0;
+        let Some(last) = entries.iter().position(|entry| {
+            hits += usize::from(!entry.is_context);
+            hits > limit
+        }) else {
+            return;
+        };
+        entries.truncate(last);
+    } else {
+        let mut hits = 0;
+        let Some(first) = entries.iter().rposition(|entry| {
+            hits += usize::from(!entry.is_context);
+            hits > limit
+        }) else {
+            return;
+        };
+        entries.drain(..=first);
+    }
+}
+
+/// Read up to `before` entries preceding and `after` entries following the match
+/// at `cursor`, leaving the journal positioned on the match again
+fn neighbours(
+    journal: &Journal,
+    query: &Query,
+    cursor: &str,
+    before: usize,
+    after: usize,
+) -> Result<(Vec<Entry>, Vec<Entry>), JournalError> {
+    let mut preceding = Vec::new();
+    while preceding.len() < before && journal.previous()? {
+        preceding.extend(context_entry(journal, query)?);
+    }
+    preceding.reverse();
+    
+    journal.seek_cursor(cursor)?;
+    journal.next()?;
+    let mut following = Vec::new();
+    while following.len() < after && journal.next()? {
+        following.extend(context_entry(journal, query)?);
+    }
+    
+    journal.seek_cursor(cursor)?;
+    journal.next()?;
+    Ok((preceding, following))
+}
+
+/// Read the entries from `hostname` received within `window` of the match at `cursor`
+fn surrounding(
+    journal: &Journal,
+    query: &Query,
+    hostname: &str,
+    cursor: &str,
+    window: Duration,
+) -> Result<(Vec<Entry>, Vec<Entry>), JournalError> {
+    let window = u64::try_from(window.as_micros()).unwrap_or(u64::MAX);
+    
+    journal.flush_matches();
+    journal.add_match("_HOSTNAME", hostname)?;
+    journal.seek_cursor(cursor)?;
+    if !journal.next()? {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    let received = journal.get_realtime_usec()?;
+    
+    let mut preceding = Vec::new();
+    while journal.previous()? {
+        if journal.get_realtime_usec()? < received.saturating_sub(window) {
+            break;
+        }
+        preceding.extend(context_entry(journal, query)?);
+    }
+    preceding.reverse();
+    
+    journal.seek_cursor(cursor)?;
+    journal.next()?;
+    let mut following = Vec::new();
+    while journal.next()? {
+        if journal.get_realtime_usec()? > received.saturating_add(window) {
+            break;
+        }
+        following.extend(context_entry(journal, query)?);
+    }
+    
+    Ok((preceding, following))
+}
+
+/// Open a second handle on the journal for reading surrounding entries
+fn open_context_journal<S: JournalSource + ?Sized>(source: &S, query: &Query) -> Result<Journal, JournalError> {
+    let journal = source.open_journal()?;
+    if let Some(threshold) = query.data_threshold {
+        journal.set_data_threshold(threshold)?;
+    }
+    Ok(journal)
+}
+
+/// Read the current entry as context for a match
+fn context_entry(journal: &Journal, query: &Query) -> Result<Option<Entry>, JournalError> {
+    let received = journal.get_realtime_usec()?;
+    let timestamp = entry_timestamp(journal, query.timestamp_source, received)?;
+    let Some(mut entry) = read_entry(journal, query, None, timestamp)? else {
+        return Ok(None);
+    };
+    
+    let (monotonic, boot_id) = journal.get_monotonic_usec()?;
+    entry.monotonic_usec = Some(monotonic);
+    entry.boot_id = Some(boot_id);
+    entry.cursor = Some(journal.get_cursor()?);
+    entry.is_context = true;
+    Ok(Some(entry))
+}
+
+/// The timestamp of the current entry according to `source`
+fn entry_timestamp(journal: &Journal, source: TimestampSource, received: u64) -> Result<u64, JournalError> {
+    Ok(match source {
+        TimestampSource::Receive => received,
+        TimestampSource::Source => field_value(journal, "_SOURCE_REALTIME_TIMESTAMP")?
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(received),
+    })
+}
+
+/// Which way a query scans the journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Direction {
+    /// From the start of the range towards the end
+    Forward,
+    /// From the end of the range towards the start
+    Backward,
+}
+
+/// Where the journal is positioned while a query scans it
+pub(crate) struct Position {
+    /// Timestamp from the query's `timestamp_source`
+    pub(crate) timestamp: u64,
+    pub(crate) monotonic_usec: u64,
+    pub(crate) boot_id: String,
+}
+
+/// Walk the entries selected by the query's matches and time range
+///
+/// `visit` is called with the journal positioned on each entry in range,
+/// in `direction` order, and can stop the scan early. The text filters
+/// (`message_contains` and `grep`) are left to the visitor, which receives
+/// the compiled `grep`. `limit` and `reverse` are also left to the caller.
+/// Entries the source holds more than once are visited once. `control` is
+/// consulted before each entry is read.
+pub(crate) fn scan_journal<S, F>(
+    source: &S,
+    query: &Query,
+    direction: Direction,
+    control: &mut ScanControl,
+    mut visit: F,
+) -> Result<(), JournalError>
+where
+    S: JournalSource + ?Sized,
+    F: FnMut(&Journal, Option<&CompiledGrep>, Position) -> Result<ControlFlow<()>, JournalError>,
+{
+    control.check()?;
+    let grep = query.grep.as_ref()
+        .filter(|grep| !grep.patterns.is_empty())
+        .map(Grep::compile)
+        .transpose()?;
+    let journal = source.open_journal()?;
+    let mut seen = source.has_duplicates().then(SeenEntries::default);
+    
+    // Bound the size of the fields we read
+    if let Some(threshold) = query.data_threshold {
+        journal.set_data_threshold(threshold)?;
+    }
+    
+    // Resolve the boot first, since listing boots resets the matches
+    let boot_id = selected_boot(query)
+        .map(|boot| boot.boot_id(&journal))
+        .transpose()?;
+    
+    // With aliases the hostname filter selects every name the journal
+    // knows the host by
+    let hostnames = match (&query.hostname, &query.host_aliases) {
+        (Some(hostname), Some(aliases)) => hostnames_of(&journal, aliases, hostname)?,
+        (Some(hostname), None) => vec![hostname.clone()],
+        (None, _) => Vec::new(),
+    };
     
     // Clear any existing matches
     journal.flush_matches();
     
-    // Add hostname filter if specified
-    if let Some(hostname) = &query.hostname {
+    // Add the journalctl-style matches as alternatives, AND'ed with the
+    // filters below
+    add_alternatives(&journal, &query.matches)?;
+    
+    // Add unit filters if specified; a unit can expand to several alternatives
+    let mut unit_matches = Vec::new();
+    for unit in query.unit.iter().chain(&query.units) {
+        unit_matches.extend(query.unit_match.unit_matches(unit));
+    }
+    for unit in &query.user_units {
+        unit_matches.extend(query.unit_match.user_unit_matches(unit));
+    }
+    add_alternatives(&journal, &unit_matches)?;
+    
+    // Add boot filter if specified
+    if let Some(boot_id) = &boot_id {
+        journal.add_match("_BOOT_ID", boot_id)?;
+    }
+    
+    // Add hostname filter if specified; matches on the same field are OR'ed
+    for hostname in &hostnames {
         journal.add_match("_HOSTNAME", hostname)?;
     }
     
-    // Add unit filter if specified
-    if let Some(unit) = &query.unit {
-        journal.add_match("_SYSTEMD_UNIT", unit)?;
+    // Add syslog identifier filters if specified; matches on the same field are OR'ed
+    for identifier in &query.identifiers {
+        journal.add_match("SYSLOG_IDENTIFIER", identifier)?;
     }
     
-    // Seek to the start time
-    journal.seek_realtime_usec(query.start_time_utc)?;
+    // Add priority filter if specified; matches on the same field are OR'ed
+    if let Some((most, least)) = query.priority {
+        for level in most..=least.min(7) {
+            journal.add_match("PRIORITY", &level.to_string())?;
+        }
+    }
     
-    let mut entries = Vec::new();
+    // The range the scan covers: monotonic within the boot if a boot
+    // window is given, otherwise realtime
+    let (start, end) = scan_range(query);
+    
+    // Seek to the edge of the range the scan starts from
+    let boot_window_id = boot_id.as_ref().filter(|_| query.boot_window.is_some());
+    
+    // Progress is estimated against the first and last entries the matches select
+    let progress_range = match control.wants_progress() {
+        true => scan_bounds(&journal, query.boot_window.is_some())?
+            .map(|(first, last)| (first.max(start), last.min(end))),
+        false => None,
+    };
+    
+    match (direction, boot_window_id) {
+        (Direction::Forward, Some(boot_id)) => journal.seek_monotonic_usec(boot_id, start)?,
+        (Direction::Forward, None) => journal.seek_realtime_usec(start)?,
+        (Direction::Backward, _) if end == u64::MAX => journal.seek_tail()?,
+        (Direction::Backward, Some(boot_id)) => journal.seek_monotonic_usec(boot_id, end)?,
+        (Direction::Backward, None) => journal.seek_realtime_usec(end)?,
+    }
     
     // Iterate through entries
-    while journal.next()? {
-        // Get timestamp and check if we've exceeded end time
-        let timestamp = journal.get_realtime_usec()?;
-        if timestamp > query.end_time_utc {
+    loop {
+        let more = match direction {
+            Direction::Forward => journal.next()?,
+            Direction::Backward => journal.previous()?,
+        };
+        if !more {
             break;
         }
         
-        // Get entry fields and strip field name prefixes
-        let hostname = journal.get_field("_HOSTNAME")?
-            .and_then(|raw| raw.strip_prefix("_HOSTNAME=").map(|s| s.to_string()));
-        let unit = journal.get_field("_SYSTEMD_UNIT")?
-            .and_then(|raw| raw.strip_prefix("_SYSTEMD_UNIT=").map(|s| s.to_string()));
-        let message = journal.get_field("MESSAGE")?
-            .and_then(|raw| raw.strip_prefix("MESSAGE=").map(|s| s.to_string()))
-            .unwrap_or_else(|| "(no message)".to_string());
+        let received = journal.get_realtime_usec()?;
+        let (monotonic, entry_boot_id) = journal.get_monotonic_usec()?;
         
-        // Apply message filter if specified
-        if let Some(filter_text) = &query.message_contains {
-            if !message.contains(filter_text) {
+        // Check if we've left the window. With a boot window the monotonic
+        // clock decides, and the realtime range only filters.
+        let position = match query.boot_window {
+            Some(_) => monotonic,
+            None => received,
+        };
+        let left = match direction {
+            Direction::Forward => position > end,
+            Direction::Backward => position < start,
+        };
+        if left {
+            break;
+        }
+        
+        let fraction = progress_range
+            .and_then(|(first, last)| fraction_between(first, last, position))
+            .map(|fraction| match direction {
+                Direction::Forward => fraction,
+                Direction::Backward => 1.0 - fraction,
+            });
+        control.advance(Some(received), fraction)?;
+        
+        if query.boot_window.is_some_and(|(start, end)| monotonic < start || monotonic > end) {
+            continue;
+        }
+        
+        let timestamp = entry_timestamp(&journal, query.timestamp_source, received)?;
+        if timestamp < query.start_time_utc || timestamp > query.end_time_utc {
+            continue;
+        }
+        
+        // Skip copies of entries already visited
+        if let Some(seen) = &mut seen {
+            if seen.repeated(&journal, received)? {
                 continue;
             }
         }
         
-        // Create entry
-        let entry = Entry {
-            hostname,
-            unit,
-            timestamp_utc: timestamp,
-            message,
+        let flow = visit(&journal, grep.as_ref(), Position {
+            timestamp,
+            monotonic_usec: monotonic,
+            boot_id: entry_boot_id,
+        })?;
+        if flow.is_break() {
+            break;
+        }
+    }
+    
+    control.finish();
+    Ok(())
+}
+
+/// Scan positions of the first and last entries the matches select: monotonic
+/// with a boot window, otherwise realtime
+fn scan_bounds(journal: &Journal, monotonic: bool) -> Result<Option<(u64, u64)>, JournalError> {
+    let position = |journal: &Journal| -> Result<u64, JournalError> {
+        match monotonic {
+            true => Ok(journal.get_monotonic_usec()?.0),
+            false => journal.get_realtime_usec(),
+        }
+    };
+    
+    journal.seek_head()?;
+    if !journal.next()? {
+        return Ok(None);
+    }
+    let first = position(journal)?;
+    
+    journal.seek_tail()?;
+    if !journal.previous()? {
+        return Ok(None);
+    }
+    let last = position(journal)?;
+    
+    Ok(Some((first.min(last), first.max(last))))
+}
+
+/// The boot the query selects. A boot window without a boot refers to the
+/// current boot.
+fn selected_boot(query: &Query) -> Option<BootSelector> {
+    match (&query.boot, query.boot_window) {
+        (None, Some(_)) => Some(BootSelector::Offset(0)),
+        (boot, _) => boot.clone(),
+    }
+}
+
+/// Resolve a boot offset in the query to the boot's ID
+///
+/// Boot offsets count the boots in the whole source, so this is done before
+/// a query runs on only some of its files.
+pub(crate) fn pin_boot<S: JournalSource + ?Sized>(source: &S, query: &mut Query) -> Result<(), JournalError> {
+    if let Some(boot @ BootSelector::Offset(_)) = selected_boot(query) {
+        let journal = source.open_journal()?;
+        query.boot = Some(BootSelector::Id(boot.boot_id(&journal)?));
+    }
+    Ok(())
+}
+
+/// The range a scan for the query covers, widened by the tolerance to catch
+/// out-of-order entries: monotonic times within the boot with a boot window,
+/// otherwise receive times
+fn scan_range(query: &Query) -> (u64, u64) {
+    let tolerance = match query.window_mode {
+        WindowMode::Strict => 0,
+        WindowMode::Tolerant(tolerance) => u64::try_from(tolerance.as_micros()).unwrap_or(u64::MAX),
+    };
+    let (start, end) = query.boot_window.unwrap_or((query.start_time_utc, query.end_time_utc));
+    (start.saturating_sub(tolerance), end.saturating_add(tolerance))
+}
+
+/// The receive times a scan for the query can visit: its time range widened
+/// by the tolerance. `None` with a boot window, which is scanned by monotonic
+/// time instead.
+pub(crate) fn receive_range(query: &Query) -> Option<(u64, u64)> {
+    match query.boot_window {
+        Some(_) => None,
+        None => Some(scan_range(query)),
+    }
+}
+
+/// Add sets of matches as alternatives, AND'ed with the matches added after them
+fn add_alternatives(journal: &Journal, alternatives: &[Vec<(String, String)>]) -> Result<(), JournalError> {
+    if alternatives.iter().all(|group| group.is_empty()) {
+        return Ok(());
+    }
+    
+    for group in alternatives.iter().filter(|group| !group.is_empty()) {
+        for (field, value) in group {
+            journal.add_match(field, value)?;
+        }
+        journal.add_disjunction()?;
+    }
+    journal.add_conjunction()
+}
+
+/// Check the current entry against `message_contains` and `grep`
+pub(crate) fn matches_text_filters(
+    journal: &Journal,
+    query: &Query,
+    grep: Option<&CompiledGrep>,
+) -> Result<bool, JournalError> {
+    Ok(text_filter_matches(journal, query, grep, |_| None)?.is_some())
+}
+
+/// Apply `message_contains` and `grep` to the current entry
+///
+/// `projected` gives the values of fields that were read already, so they
+/// are not read again; other fields are read from the journal.
+///
+/// # Returns
+/// Where `grep` matched, or `None` if the entry does not pass the filters
+fn text_filter_matches<'a>(
+    journal: &Journal,
+    query: &Query,
+    grep: Option<&CompiledGrep>,
+    projected: impl Fn(&str) -> Option<&'a str>,
+) -> Result<Option<Vec<FieldMatch>>, JournalError> {
+    if let Some(filter_text) = &query.message_contains {
+        let matched = match projected("MESSAGE") {
+            Some(message) => message.contains(filter_text),
+            None => field_value(journal, "MESSAGE")?
+                .is_some_and(|message| message.contains(filter_text)),
         };
-        
-        entries.push(entry);
+        if !matched {
+            return Ok(None);
+        }
     }
     
-    // Entries should already be in chronological order from journal iteration
-    Ok(entries)
-}
\ No newline at end of file
+    // Remember where the regex filter matched
+    let Some(grep) = grep else {
+        return Ok(Some(Vec::new()));
+    };
+    let found = match projected(&grep.field) {
+        Some(value) => grep.find(value),
+        None => field_value(journal, &grep.field)?
+            .map(|value| grep.find(&value))
+            .unwrap_or_default(),
+    };
+    Ok((!found.is_empty()).then_some(found))
+}
+
+/// Read the current entry, applying the text filters and the projection
+/// 
+/// Returns `None` if the entry does not pass `message_contains` or `grep`.
+/// Fields needed by the filters are read even when they are not projected.
+fn read_entry(
+    journal: &Journal,
+    query: &Query,
+    grep: Option<&CompiledGrep>,
+    timestamp: u64,
+) -> Result<Option<Entry>, JournalError> {
+    let mut hostname = None;
+    let mut unit = None;
+    let mut message = None;
+    let mut fields = BTreeMap::new();
+    
+    // Collect the projected fields
+    match &query.projection {
+        Projection::Default => {
+            hostname = field_value(journal, "_HOSTNAME")?;
+            unit = field_value(journal, "_SYSTEMD_UNIT")?;
+            message = Some(field_value(journal, "MESSAGE")?
+                .unwrap_or_else(|| "(no message)".to_string()));
+        }
+        Projection::Fields(names) => {
+            for name in names {
+                let value = field_value(journal, name)?;
+                match name.as_str() {
+                    "_HOSTNAME" => hostname = value,
+                    "_SYSTEMD_UNIT" => unit = value,
+                    "MESSAGE" => message = Some(value.unwrap_or_else(|| "(no message)".to_string())),
+                    _ => {
+                        if let Some(value) = value {
+                            fields.insert(name.clone(), value);
+                        }
+                    }
+                }
+            }
+        }
+        Projection::All => {
+            for data in journal.get_all_fields()? {
+                if let Some((name, value)) = data.split_once('=') {
+                    match name {
+                        "_HOSTNAME" => hostname = Some(value.to_string()),
+                        "_SYSTEMD_UNIT" => unit = Some(value.to_string()),
+                        "MESSAGE" => message = Some(value.to_string()),
+                        _ => {
+                            fields.insert(name.to_string(), value.to_string());
+                        }
+                    }
+                }
+            }
+            message.get_or_insert_with(|| "(no message)".to_string());
+        }
+        Projection::TimestampsOnly => {}
+    }
+    
+    // Apply the text filters, remembering where the regex filter matched
+    let projected = |field: &str| match field {
+        "MESSAGE" => message.as_deref(),
+        "_HOSTNAME" => hostname.as_deref(),
+        "_SYSTEMD_UNIT" => unit.as_deref(),
+        field => fields.get(field).map(String::as_str),
+    };
+    let Some(matches) = text_filter_matches(journal, query, grep, projected)? else {
+        return Ok(None);
+    };
+    
+    Ok(Some(Entry {
+        hostname,
+        unit,
+        timestamp_utc: timestamp,
+        message: message.unwrap_or_default(),
+        matches,
+        monotonic_usec: None,
+        boot_id: None,
+        fields,
+        cursor: None,
+        is_context: false,
+    }))
+}
+
+/// Read a field from the current entry with the `FIELD=` prefix stripped
+pub(crate) fn field_value(journal: &Journal, field: &str) -> Result<Option<String>, JournalError> {
+    let prefix_len = field.len() + 1;
+    Ok(journal.get_field_lossy(field)?
+        .and_then(|raw| raw.get(prefix_len..).map(|s| s.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(cursor: &str, is_context: bool) -> Entry {
+        Entry {
+            hostname: None,
+            unit: None,
+            timestamp_utc: 0,
+            message: cursor.to_string(),
+            matches: Vec::new(),
+            monotonic_usec: None,
+            boot_id: None,
+            fields: BTreeMap::new(),
+            cursor: Some(cursor.to_string()),
+            is_context,
+        }
+    }
+
+    fn layout(entries: &[Entry]) -> Vec<(String, bool)> {
+        entries.iter().map(|e| (e.message.clone(), e.is_context)).collect()
+    }
+
+    #[test]
+    fn test_boot_window_saturates() {
+        let query = Query::all().boot_window(Duration::from_secs(1), Duration::MAX);
+        assert_eq!(query.boot_window, Some((1_000_000, u64::MAX)));
+    }
+
+    #[test]
+    fn test_journalctl_unit_matches() {
+        assert_eq!(UnitMatch::Exact.unit_matches("nginx.service"), vec![vec![field("_SYSTEMD_UNIT", "nginx.service")]]);
+
+        // Other processes only count when they are privileged
+        let alternatives = UnitMatch::Journalctl.unit_matches("nginx.service");
+        assert_eq!(alternatives.len(), 4);
+        assert!(alternatives.contains(&vec![field("_UID", "0"), field("OBJECT_SYSTEMD_UNIT", "nginx.service")]));
+        for alternative in &alternatives[1..] {
+            assert!(alternative.contains(&field("_UID", "0")) || alternative.contains(&field("_PID", "1")));
+        }
+        for alternative in &UnitMatch::Journalctl.user_unit_matches("app.service")[2..] {
+            assert!(alternative.contains(&field("_UID", "0")));
+        }
+    }
+
+    #[test]
+    fn test_merge_group_skips_duplicates() {
+        let mut entries = Vec::new();
+        let mut seen = HashMap::new();
+        merge_group(&mut entries, &mut seen, vec![entry("a", true), entry("b", false), entry("c", true)]);
+        merge_group(&mut entries, &mut seen, vec![entry("b", true), entry("c", true), entry("d", false)]);
+
+        assert_eq!(layout(&entries), vec![
+            ("a".to_string(), true),
+            ("b".to_string(), false),
+            ("c".to_string(), true),
+            ("d".to_string(), false),
+        ]);
+    }
+
+    #[test]
+    fn test_merge_group_promotes_context_to_match() {
+        let mut entries = Vec::new();
+        let mut seen = HashMap::new();
+        merge_group(&mut entries, &mut seen, vec![entry("a", false), entry("b", true)]);
+        merge_group(&mut entries, &mut seen, vec![entry("a", true), entry("b", false)]);
+
+        assert_eq!(layout(&entries), vec![("a".to_string(), false), ("b".to_string(), false)]);
+    }
+
+    #[test]
+    fn test_apply_limit_counts_matches_only() {
+        let all = vec![
+            entry("a", false),
+            entry("b", true),
+            entry("c", false),
+            entry("d", true),
+            entry("e", false),
+        ];
+
+        // Oldest first: keep the newest matches and the context before them
+        let mut entries = all.clone();
+        apply_limit(&mut entries, 2, false);
+        assert_eq!(layout(&entries).len(), 4);
+        assert_eq!(entries[0].message, "b");
+
+        // Newest first: keep the first matches and the context after them
+        let mut entries: Vec<Entry> = all.iter().rev().cloned().collect();
+        apply_limit(&mut entries, 1, true);
+        assert_eq!(entries.iter().map(|e| e.message.as_str()).collect::<Vec<_>>(), vec!["e", "d"]);
+
+        // A limit above the number of matches keeps everything
+        let mut entries = all.clone();
+        apply_limit(&mut entries, 10, false);
+        assert_eq!(entries, all);
+    }
+}
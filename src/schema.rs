@@ -0,0 +1,236 @@
+//! JSON schema of the serializable types
+//!
+//! With the `serde` feature, [`Entry`], [`Query`], [`Host`], [`Hosts`],
+//! [`TailConfig`], [`UnitTimeline`], [`HealthReport`] and [`JournalError`] implement `Serialize`
+//! and `Deserialize`, along with the types they contain. The JSON they produce
+//! is versioned with [`SCHEMA_VERSION`], which changes whenever a field is
+//! renamed or removed or its encoding changes. Adding fields does not change the version, so readers
+//! should ignore fields they do not know.
+//!
+//! Encoding:
+//! - [`Hosts`] and [`HealthReport`], the documents discovery and health reports
+//!   produce, carry the version in a `schema_version` field. It is ignored
+//!   when reading them back.
+//! - Fields keep their Rust names. Missing `Option` values are `null`.
+//! - Timestamps (`*_utc`, `monotonic_usec`) are integers in microseconds, as in
+//!   the Rust types. Durations, such as `TailConfig::poll_interval`, are
+//!   integers in microseconds too.
+//! - `HealthReport::to_json` returns a report in this encoding as a string.
+//! - Enum variants are `snake_case`. Unit variants are plain strings, others
+//!   are objects with the variant as the only key, e.g. `"strict"` and
+//!   `{"tolerant": 5000000}` for `WindowMode`.
+//! - `Entry::cursor` is the journal cursor, which can be passed back to resume
+//!   reading after the entry.
+//! - A `Query` can leave out any field; missing ones take the values of
+//!   `Query::all()`.
+//!
+//! An entry as JSON:
+//! ```json
+//! {
+//!   "hostname": "web-server",
+//!   "unit": "nginx.service",
+//!   "timestamp_utc": 1640995200000000,
+//!   "message": "GET / 200",
+//!   "matches": [],
+//!   "monotonic_usec": 5123456,
+//!   "boot_id": "6c7c6013a8354d1b8d5d0b3a8b1c2c3d",
+//!   "fields": {},
+//!   "cursor": "s=739ad463348b4ceca5a9e69c95a3c93f;i=4ece7;b=6c7c6013a8354d1b8d5d0b3a8b1c2c3d;m=4e2d40;t=5d4b2e1e8c000;x=5f0e4c1b2a3d4e5f",
+//!   "is_context": false
+//! }
+//! ```
+//!
+//! [`Entry`]: crate::Entry
+//! [`Query`]: crate::Query
+//! [`Host`]: crate::Host
+//! [`Hosts`]: crate::Hosts
+//! [`TailConfig`]: crate::TailConfig
+//! [`UnitTimeline`]: crate::UnitTimeline
+//! [`HealthReport`]: crate::HealthReport
+//! [`JournalError`]: crate::JournalError
+
+/// Version of the JSON schema of the serializable types
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for crate::Hosts {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut hosts = serializer.serialize_struct("Hosts", 2)?;
+        hosts.serialize_field("schema_version", &SCHEMA_VERSION)?;
+        hosts.serialize_field("hosts", &self.hosts)?;
+        hosts.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for crate::HealthReport {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut report = serializer.serialize_struct("HealthReport", 4)?;
+        report.serialize_field("schema_version", &SCHEMA_VERSION)?;
+        report.serialize_field("start_time_utc", &self.start_time_utc)?;
+        report.serialize_field("end_time_utc", &self.end_time_utc)?;
+        report.serialize_field("hosts", &self.hosts)?;
+        report.end()
+    }
+}
+
+/// Serializes a `Duration` as whole microseconds
+#[cfg(feature = "serde")]
+pub(crate) mod duration_usec {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        u64::try_from(duration.as_micros()).unwrap_or(u64::MAX).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_micros)
+    }
+}
+
+/// Serializes an `Option<Duration>` as whole microseconds or `null`
+#[cfg(feature = "serde")]
+pub(crate) mod option_duration_usec {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        duration
+            .map(|duration| u64::try_from(duration.as_micros()).unwrap_or(u64::MAX))
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        Option::<u64>::deserialize(deserializer).map(|usec| usec.map(Duration::from_micros))
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::SCHEMA_VERSION;
+    use crate::{
+        Context, Entry, Health, HealthReport, Host, HostHealth, HostnameSeen, Hosts, JournalError, Query, TailConfig,
+        UnitHealth, WindowMode,
+    };
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    fn round_trip<T>(value: &T) -> T
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_entry_schema() {
+        let entry = Entry {
+            hostname: Some("web-server".to_string()),
+            unit: Some("nginx.service".to_string()),
+            timestamp_utc: 1640995200000000,
+            message: "GET / 200".to_string(),
+            matches: Vec::new(),
+            monotonic_usec: Some(5123456),
+            boot_id: None,
+            fields: BTreeMap::from([("PRIORITY".to_string(), "6".to_string())]),
+            cursor: Some("s=739ad463;i=4ece7".to_string()),
+            is_context: false,
+        };
+        assert_eq!(serde_json::to_value(&entry).unwrap(), json!({
+            "hostname": "web-server",
+            "unit": "nginx.service",
+            "timestamp_utc": 1640995200000000u64,
+            "message": "GET / 200",
+            "matches": [],
+            "monotonic_usec": 5123456,
+            "boot_id": null,
+            "fields": {"PRIORITY": "6"},
+            "cursor": "s=739ad463;i=4ece7",
+            "is_context": false,
+        }));
+        assert_eq!(round_trip(&entry), entry);
+    }
+
+    #[test]
+    fn test_query_schema() {
+        let query = Query::all()
+            .hostname("web-server")
+            .window_mode(WindowMode::Tolerant(Duration::from_secs(5)))
+            .surrounding(Duration::from_millis(1500));
+        let value = serde_json::to_value(&query).unwrap();
+        assert_eq!(value["window_mode"], json!({"tolerant": 5000000}));
+        assert_eq!(value["context"], json!({"surrounding": 1500000}));
+        assert_eq!(value["unit_match"], json!("exact"));
+        assert_eq!(round_trip(&query), query);
+        assert_eq!(query.context, Some(Context::Surrounding(Duration::from_millis(1500))));
+
+        // Missing fields take the defaults of `Query::all()`
+        let partial: Query = serde_json::from_value(json!({"unit": "nginx.service"})).unwrap();
+        assert_eq!(partial, Query::all().unit("nginx.service"));
+    }
+
+    #[test]
+    fn test_discovery_and_config_schema() {
+        let hosts = Hosts {
+            hosts: vec![Host {
+                hostname: "web-server".to_string(),
+                machine_id: Some("aaaa".to_string()),
+                hostnames: vec![HostnameSeen {
+                    hostname: "web-server".to_string(),
+                    first_seen_utc: 100,
+                    last_seen_utc: 200,
+                }],
+                units: vec!["nginx.service".to_string()],
+                unit_stats: None,
+            }],
+        };
+        assert_eq!(serde_json::to_value(&hosts).unwrap()["schema_version"], json!(SCHEMA_VERSION));
+        assert_eq!(round_trip(&hosts), hosts);
+
+        let config = TailConfig::new("web-server", "nginx.service", "/var/log/journal").with_poll_interval_ms(50);
+        assert_eq!(serde_json::to_value(&config).unwrap()["poll_interval"], json!(50000));
+        assert_eq!(round_trip(&config), config);
+
+        assert_eq!(serde_json::to_value(JournalError::IoError).unwrap(), json!("io_error"));
+        let error = JournalError::InvalidPattern("(".to_string());
+        assert_eq!(serde_json::to_value(&error).unwrap(), json!({"invalid_pattern": "("}));
+        assert_eq!(round_trip(&error), error);
+    }
+
+    #[test]
+    fn test_health_report_schema() {
+        let report = HealthReport {
+            start_time_utc: 100,
+            end_time_utc: 200,
+            hosts: vec![HostHealth {
+                hostname: "web-server".to_string(),
+                machine_id: Some("aaaa".to_string()),
+                units: vec![UnitHealth {
+                    unit: "nginx.service".to_string(),
+                    entries: 4,
+                    errors: 1,
+                    error_rate: 0.25,
+                    restarts: 0,
+                    crashes: 0,
+                    coredumps: 0,
+                    last_failure: None,
+                    last_log_utc: Some(150),
+                    since_last_log: Some(Duration::from_micros(50)),
+                    health: Health::Degraded,
+                }],
+            }],
+        };
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["schema_version"], json!(SCHEMA_VERSION));
+        assert_eq!(value["hosts"][0]["units"][0]["since_last_log"], json!(50));
+        assert_eq!(value["hosts"][0]["units"][0]["health"], json!("degraded"));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&report.to_json()).unwrap(), value);
+        assert_eq!(round_trip(&report), report);
+    }
+}
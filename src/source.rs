@@ -0,0 +1,396 @@
+use crate::error::{JournalError, Result};
+use crate::index::JournalIndex;
+use crate::journal::Journal;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Somewhere journal entries can be read from
+///
+/// Implemented for directory paths (`&str`, `String`, `Path`, `PathBuf`) and
+/// for [`MultiJournal`], so they can be passed to [`crate::query_journal`],
+/// [`crate::aggregate`] and [`crate::discover_services`] alike.
+pub trait JournalSource {
+    /// Open a new handle on the journal
+    fn open_journal(&self) -> Result<Journal>;
+
+    /// The journal files the source reads
+    fn journal_files(&self) -> Result<Vec<PathBuf>>;
+
+    /// Whether the same entry can be found more than once
+    ///
+    /// Queries drop repeated entries from sources that return `true`.
+    fn has_duplicates(&self) -> bool {
+        false
+    }
+
+    /// The index queries and aggregations consult before reading the source
+    ///
+    /// `None` unless the source is wrapped with [`JournalIndex::source`].
+    fn index(&self) -> Option<&JournalIndex> {
+        None
+    }
+}
+
+impl JournalSource for Path {
+    fn open_journal(&self) -> Result<Journal> {
+        Journal::open_directory(self)
+    }
+
+    fn journal_files(&self) -> Result<Vec<PathBuf>> {
+        directory_journal_files(self)
+    }
+}
+
+impl JournalSource for PathBuf {
+    fn open_journal(&self) -> Result<Journal> {
+        Journal::open_directory(self)
+    }
+
+    fn journal_files(&self) -> Result<Vec<PathBuf>> {
+        directory_journal_files(self.as_ref())
+    }
+}
+
+impl JournalSource for str {
+    fn open_journal(&self) -> Result<Journal> {
+        Journal::open_directory(self)
+    }
+
+    fn journal_files(&self) -> Result<Vec<PathBuf>> {
+        directory_journal_files(self.as_ref())
+    }
+}
+
+impl JournalSource for String {
+    fn open_journal(&self) -> Result<Journal> {
+        Journal::open_directory(self)
+    }
+
+    fn journal_files(&self) -> Result<Vec<PathBuf>> {
+        directory_journal_files(self.as_ref())
+    }
+}
+
+impl<T: JournalSource + ?Sized> JournalSource for &T {
+    fn open_journal(&self) -> Result<Journal> {
+        (**self).open_journal()
+    }
+
+    fn journal_files(&self) -> Result<Vec<PathBuf>> {
+        (**self).journal_files()
+    }
+
+    fn has_duplicates(&self) -> bool {
+        (**self).has_duplicates()
+    }
+
+    fn index(&self) -> Option<&JournalIndex> {
+        (**self).index()
+    }
+}
+
+/// Several journal directories read as one
+///
+/// Each directory is searched recursively for journal files, so trees
+/// written by `systemd-journal-remote` with per-host subdirectories are
+/// picked up as well as `/var/log/journal` and `/run/log/journal`. The files
+/// are opened together, so entries are merged in time order, and entries
+/// found in more than one directory are returned once.
+///
+/// Directories that do not exist are skipped.
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{query_journal, MultiJournal, Query};
+///
+/// let journals = MultiJournal::new(["/var/log/journal", "/run/log/journal"])
+///     .add_directory("/var/log/journal/remote");
+///
+/// let entries = query_journal(&journals, Query::all().since("-1h")?)?;
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct MultiJournal {
+    directories: Vec<PathBuf>,
+}
+
+impl MultiJournal {
+    /// Create a journal over the given directories
+    pub fn new<I, P>(directories: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        MultiJournal {
+            directories: directories.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Add another directory
+    pub fn add_directory<P: Into<PathBuf>>(mut self, directory: P) -> Self {
+        self.directories.push(directory.into());
+        self
+    }
+
+    /// The directories this journal reads
+    pub fn directories(&self) -> &[PathBuf] {
+        &self.directories
+    }
+
+    /// Find the journal files in all directories, sorted and without repeats
+    ///
+    /// Active (`.journal`) and dirty archived (`.journal~`) files are included.
+    pub fn journal_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut visited = HashSet::new();
+        for directory in &self.directories {
+            if directory.is_dir() {
+                collect_journal_files(directory, &mut visited, &mut files)?;
+            }
+        }
+
+        // The same file may be reached through overlapping directories
+        for file in &mut files {
+            if let Ok(canonical) = file.canonicalize() {
+                *file = canonical;
+            }
+        }
+        files.sort();
+        files.dedup();
+
+        Ok(files)
+    }
+}
+
+impl JournalSource for MultiJournal {
+    /// Open all journal files found in the directories as one journal
+    ///
+    /// Files that cannot be read as journals are skipped, as when opening a
+    /// single directory. Fails with `JournalError::NotFound` if none of the
+    /// directories exist.
+    fn open_journal(&self) -> Result<Journal> {
+        if !self.directories.iter().any(|directory| directory.is_dir()) {
+            return Err(JournalError::NotFound);
+        }
+        open_readable_files(MultiJournal::journal_files(self)?)
+    }
+
+    fn journal_files(&self) -> Result<Vec<PathBuf>> {
+        MultiJournal::journal_files(self)
+    }
+
+    fn has_duplicates(&self) -> bool {
+        self.directories.len() > 1
+    }
+}
+
+/// A fixed set of journal files, read by one worker of a parallel query
+#[derive(Debug, Clone)]
+pub(crate) struct JournalFiles {
+    pub(crate) files: Vec<PathBuf>,
+    pub(crate) duplicates: bool,
+}
+
+impl JournalSource for JournalFiles {
+    fn open_journal(&self) -> Result<Journal> {
+        open_readable_files(self.files.clone())
+    }
+
+    fn journal_files(&self) -> Result<Vec<PathBuf>> {
+        Ok(self.files.clone())
+    }
+
+    fn has_duplicates(&self) -> bool {
+        self.duplicates
+    }
+}
+
+/// The journal files `sd_journal_open_directory` reads: those in the
+/// directory itself and in subdirectories named after a machine ID
+fn directory_journal_files(directory: &Path) -> Result<Vec<PathBuf>> {
+    if !directory.is_dir() {
+        return Err(JournalError::NotFound);
+    }
+
+    let mut files = Vec::new();
+    let entries = fs::read_dir(directory).map_err(|_| JournalError::IoError)?;
+    for entry in entries {
+        let path = entry.map_err(|_| JournalError::IoError)?.path();
+        if path.is_dir() && is_machine_id(&path) {
+            for entry in fs::read_dir(&path).map_err(|_| JournalError::IoError)? {
+                let path = entry.map_err(|_| JournalError::IoError)?.path();
+                if is_journal_file(&path) {
+                    files.push(path);
+                }
+            }
+        } else if is_journal_file(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+fn is_machine_id(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.len() == 32 && name.bytes().all(|byte| byte.is_ascii_hexdigit()))
+}
+
+/// Open journal files as one journal, skipping those that cannot be read
+///
+/// The files are opened together first; only if that fails is each one
+/// tried on its own to find the ones to leave out.
+fn open_readable_files(files: Vec<PathBuf>) -> Result<Journal> {
+    if let Ok(journal) = Journal::open_files(files.clone()) {
+        return Ok(journal);
+    }
+    let files = files
+        .into_iter()
+        .filter(|file| Journal::open_files(vec![file]).is_ok())
+        .collect();
+    Journal::open_files(files)
+}
+
+/// Walk a directory tree, collecting journal files
+///
+/// Symlinked directories are followed, each directory at most once, so a
+/// symlink loop ends the walk instead of recursing forever.
+fn collect_journal_files(directory: &Path, visited: &mut HashSet<PathBuf>, files: &mut Vec<PathBuf>) -> Result<()> {
+    let canonical = directory.canonicalize().map_err(|_| JournalError::IoError)?;
+    if !visited.insert(canonical) {
+        return Ok(());
+    }
+
+    let entries = fs::read_dir(directory).map_err(|_| JournalError::IoError)?;
+    for entry in entries {
+        let path = entry.map_err(|_| JournalError::IoError)?.path();
+        if path.is_dir() {
+            collect_journal_files(&path, visited, files)?;
+        } else if is_journal_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_journal_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".journal") || name.ends_with(".journal~"))
+}
+
+/// The part of a cursor that identifies an entry's content and origin
+///
+/// A cursor also names the journal file's sequence number, which differs
+/// between copies of the same entry. The boot ID, monotonic and realtime
+/// timestamps and content hash (`b=`, `m=`, `t=`, `x=`) do not.
+pub(crate) fn entry_identity(cursor: &str) -> String {
+    cursor
+        .split(';')
+        .filter(|part| ["b=", "m=", "t=", "x="].iter().any(|key| part.starts_with(key)))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// The copies of entries already read from a source with duplicates
+///
+/// Copies of an entry share its receive time and are read one after the
+/// other, so only the identities of the entries received at the time of the
+/// last entry are kept, not those of every entry read.
+#[derive(Debug, Default)]
+pub(crate) struct SeenEntries {
+    received: u64,
+    identities: HashSet<String>,
+}
+
+impl SeenEntries {
+    /// Whether the current entry, received at `received`, was read before.
+    /// Records it if not.
+    pub(crate) fn repeated(&mut self, journal: &Journal, received: u64) -> Result<bool> {
+        if received != self.received {
+            self.identities.clear();
+            self.received = received;
+        }
+        Ok(!self.identities.insert(entry_identity(&journal.get_cursor()?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_identity_ignores_sequence_numbers() {
+        let local = "s=739ad463348b4ceca5a9e69c95a3c93f;i=4ece7;b=6c7c6013a8e44f19a2d0c7b0ad0d9d18;m=8e30e7a;t=5d1a5c8b10bc5;x=8d2f3c0e3d5e0a0c";
+        let remote = "s=0cb2e1a43e2a4d2e8e29f2c0a4f53f6a;i=12;b=6c7c6013a8e44f19a2d0c7b0ad0d9d18;m=8e30e7a;t=5d1a5c8b10bc5;x=8d2f3c0e3d5e0a0c";
+        assert_eq!(entry_identity(local), entry_identity(remote));
+        assert_eq!(
+            entry_identity(local),
+            "b=6c7c6013a8e44f19a2d0c7b0ad0d9d18;m=8e30e7a;t=5d1a5c8b10bc5;x=8d2f3c0e3d5e0a0c"
+        );
+    }
+
+    #[test]
+    fn test_journal_file_names() {
+        assert!(is_journal_file(Path::new("/var/log/journal/abc/system.journal")));
+        assert!(is_journal_file(Path::new("system@0005f1-abc.journal~")));
+        assert!(!is_journal_file(Path::new("notes.txt")));
+        assert!(!is_journal_file(Path::new("system.journal.bak")));
+    }
+
+    #[test]
+    fn test_directory_journal_files() {
+        let root = std::env::temp_dir().join(format!("journald-query-source-{}", std::process::id()));
+        let machine = root.join("0123456789abcdef0123456789abcdef");
+        fs::create_dir_all(&machine).unwrap();
+        fs::create_dir_all(root.join("remote")).unwrap();
+        for file in [
+            root.join("system.journal"),
+            machine.join("user-1000.journal~"),
+            root.join("remote/remote-host.journal"),
+            root.join("notes.txt"),
+        ] {
+            fs::write(file, "").unwrap();
+        }
+
+        let files = directory_journal_files(&root);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(files.unwrap(), vec![
+            machine.join("user-1000.journal~"),
+            root.join("system.journal"),
+        ]);
+        assert_eq!(directory_journal_files(Path::new("/nonexistent/journal")), Err(JournalError::NotFound));
+    }
+
+    #[test]
+    fn test_symlink_loops() {
+        let root = std::env::temp_dir().join(format!("journald-query-symlinks-{}", std::process::id()));
+        fs::create_dir_all(root.join("host")).unwrap();
+        fs::write(root.join("host/system.journal"), "").unwrap();
+        std::os::unix::fs::symlink(&root, root.join("host/loop")).unwrap();
+
+        let files = MultiJournal::new([&root]).journal_files();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(files.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_builder() {
+        let journals = MultiJournal::new(["/var/log/journal"]).add_directory("/run/log/journal");
+        assert_eq!(
+            journals.directories(),
+            &[PathBuf::from("/var/log/journal"), PathBuf::from("/run/log/journal")]
+        );
+        assert!(journals.has_duplicates());
+    }
+
+    #[test]
+    fn test_missing_directories() {
+        let journals = MultiJournal::new(["/nonexistent/journal/a", "/nonexistent/journal/b"]);
+        assert_eq!(journals.journal_files().unwrap(), Vec::<PathBuf>::new());
+        assert!(matches!(journals.open_journal(), Err(JournalError::NotFound)));
+    }
+}
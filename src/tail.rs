@@ -1,14 +1,12 @@
-use crate::error::{JournalError, Result};
-use crate::ffi;
+use crate::error::Result;
+use crate::journal::Journal;
 use crate::query::Entry;
-use std::ffi::CString;
-use std::marker::PhantomData;
-use std::os::raw::c_void;
-use std::ptr;
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 /// Configuration for tailing journal entries from a specific service
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TailConfig {
     /// Hostname to filter by (_HOSTNAME field)
     pub hostname: String,
@@ -17,8 +15,10 @@ pub struct TailConfig {
     /// Path to journal directory 
     pub journal_path: String,
     /// Polling interval for checking new entries (default: 100ms)
+    #[cfg_attr(feature = "serde", serde(with = "crate::schema::duration_usec"))]
     pub poll_interval: Duration,
     /// How far back in time to start reading entries (default: 10 seconds ago)
+    #[cfg_attr(feature = "serde", serde(with = "crate::schema::duration_usec"))]
     pub start_time_offset: Duration,
 }
 
@@ -155,10 +155,9 @@ impl TailConfig {
 /// for new entries matching the specified hostname and service.
 #[derive(Debug)]
 pub struct JournalTail {
-    handle: *mut ffi::SdJournal,
+    // Not thread-safe, like the journal itself
+    journal: Journal,
     config: TailConfig,
-    // PhantomData to make this !Send + !Sync (not thread-safe)
-    _not_thread_safe: PhantomData<*const ()>,
 }
 
 impl JournalTail {
@@ -192,13 +191,12 @@ impl JournalTail {
     /// ```
     pub fn new(config: TailConfig) -> Result<Self> {
         // Open the journal
-        let handle = Self::open_journal(&config)?;
+        let journal = Journal::open_directory(&config.journal_path)?;
         
         // Create the tail instance
         let mut tail = Self {
-            handle,
+            journal,
             config,
-            _not_thread_safe: PhantomData,
         };
         
         // Set up filters and position
@@ -218,68 +216,16 @@ impl JournalTail {
     
     // Private helper methods
     
-    fn open_journal(config: &TailConfig) -> Result<*mut ffi::SdJournal> {
-        let mut handle: *mut ffi::SdJournal = ptr::null_mut();
-
-
-        
-        let result = {
-            // Open from specified directory
-            let path_cstr = CString::new(config.journal_path.as_str())
-                .map_err(|_| JournalError::InvalidArgument)?;
-            
-            unsafe {
-                ffi::sd_journal_open_directory(
-                    &mut handle,
-                    path_cstr.as_ptr(),
-                    0,
-                )
-            }
-        };
-        
-        if result < 0 {
-            return Err(JournalError::from_errno(result));
-        }
-        
-        if handle.is_null() {
-            return Err(JournalError::IoError);
-        }
-        
-        Ok(handle)
-    }
-    
     fn setup_filters(&mut self) -> Result<()> {
         // Add hostname filter: _HOSTNAME=hostname
-        let hostname_match = format!("_HOSTNAME={}", self.config.hostname);
-        self.add_match(&hostname_match)?;
+        self.journal.add_match("_HOSTNAME", &self.config.hostname)?;
         
         // Add service filter: _SYSTEMD_UNIT=service
-        let service_match = format!("_SYSTEMD_UNIT={}", self.config.service);
-        self.add_match(&service_match)?;
+        self.journal.add_match("_SYSTEMD_UNIT", &self.config.service)?;
         
         Ok(())
     }
     
-    fn add_match(&mut self, match_str: &str) -> Result<()> {
-        let match_cstr = CString::new(match_str)
-            .map_err(|_| JournalError::InvalidArgument)?;
-        let match_bytes = match_cstr.as_bytes();
-        
-        let result = unsafe {
-            ffi::sd_journal_add_match(
-                self.handle,
-                match_bytes.as_ptr() as *const c_void,
-                match_bytes.len(),
-            )
-        };
-        
-        if result < 0 {
-            Err(JournalError::from_errno(result))
-        } else {
-            Ok(())
-        }
-    }
-    
     fn seek_to_tail(&mut self) -> Result<()> {
         // For live tailing, we want to start from configurable time offset
         // Use the configured start_time_offset to determine how far back to go
@@ -292,23 +238,12 @@ impl JournalTail {
         let offset_micros = self.config.start_time_offset.as_micros() as u64;
         let start_time = now.saturating_sub(offset_micros);
         
-        let result = unsafe { 
-            ffi::sd_journal_seek_realtime_usec(self.handle, start_time)
-        };
-        
-        if result < 0 {
-            return Err(JournalError::from_errno(result));
-        }
-        
-        // Move to the first entry at or after this time
-        let result = unsafe { ffi::sd_journal_next(self.handle) };
+        self.journal.seek_realtime_usec(start_time)?;
         
-        if result < 0 {
-            Err(JournalError::from_errno(result))
-        } else {
-            // Position is now set, ready for iteration
-            Ok(())
-        }
+        // Move to the first entry at or after this time; the position is
+        // then set, ready for iteration
+        self.journal.next()?;
+        Ok(())
     }
     
     /// Wait for new journal entries using polling approach
@@ -316,7 +251,7 @@ impl JournalTail {
     /// You might be tempted to use sd_journal_wait() here. I would recommend against that
     /// for two reasons:
     /// 1. It only captures changes every 250ms - see:
-    /// https://github.com/systemd/systemd/issues/17574
+    ///    https://github.com/systemd/systemd/issues/17574
     /// 2. It can hang indefinitely for reasons I don't completely understand.
     fn wait_for_entries_polling(&mut self) -> Result<()> {
         // Use the configured polling interval
@@ -333,77 +268,41 @@ impl JournalTail {
         // This reuses the existing logic from journal.rs
         // We need to extract the entry data from the current journal position
         
-        let hostname = self.get_field_data("_HOSTNAME").ok();
-        let unit = self.get_field_data("_SYSTEMD_UNIT").ok();
-        let message = self.get_field_data("MESSAGE")
-            .unwrap_or_else(|_| String::new());
+        let hostname = self.get_field_data("_HOSTNAME");
+        let unit = self.get_field_data("_SYSTEMD_UNIT");
+        let message = self.get_field_data("MESSAGE").unwrap_or_default();
         
         // Get timestamp
-        let mut timestamp: u64 = 0;
-        let result = unsafe {
-            ffi::sd_journal_get_realtime_usec(self.handle, &mut timestamp)
-        };
+        let timestamp = self.journal.get_realtime_usec()?;
         
-        if result < 0 {
-            return Err(JournalError::from_errno(result));
-        }
+        // Get monotonic timestamp and boot ID
+        let (monotonic_usec, boot_id) = match self.journal.get_monotonic_usec() {
+            Ok((monotonic, boot_id)) => (Some(monotonic), Some(boot_id)),
+            Err(_) => (None, None),
+        };
         
         Ok(Entry {
             hostname,
             unit,
             timestamp_utc: timestamp,
             message,
+            matches: Vec::new(),
+            monotonic_usec,
+            boot_id,
+            fields: BTreeMap::new(),
+            cursor: self.journal.get_cursor().ok(),
+            is_context: false,
         })
     }
     
-    /// Get data for a specific field from the current journal entry
-    fn get_field_data(&self, field: &str) -> Result<String> {
-        let field_cstr = CString::new(field)
-            .map_err(|_| JournalError::InvalidArgument)?;
-        
-        let mut data: *const c_void = ptr::null();
-        let mut length: usize = 0;
-        
-        let result = unsafe {
-            ffi::sd_journal_get_data(
-                self.handle,
-                field_cstr.as_ptr(),
-                &mut data,
-                &mut length,
-            )
-        };
-        
-        if result < 0 {
-            return Err(JournalError::from_errno(result));
-        }
-        
-        if data.is_null() || length == 0 {
-            return Err(JournalError::NotFound);
-        }
-        
-        // Convert the data to a string
-        let data_slice = unsafe {
-            std::slice::from_raw_parts(data as *const u8, length)
-        };
-        
-        let data_str = std::str::from_utf8(data_slice)
-            .map_err(|_| JournalError::InvalidData)?;
+    /// Get the value of a field of the current journal entry, if it has one
+    fn get_field_data(&self, field: &str) -> Option<String> {
+        let data = self.journal.get_field(field).ok()??;
         
         // The data is in format "FIELD=value", we want just the value
-        if let Some(equals_pos) = data_str.find('=') {
-            Ok(data_str[equals_pos + 1..].to_string())
-        } else {
-            Ok(data_str.to_string())
-        }
-    }
-}
-
-impl Drop for JournalTail {
-    fn drop(&mut self) {
-        if !self.handle.is_null() {
-            unsafe {
-                ffi::sd_journal_close(self.handle);
-            }
+        match data.split_once('=') {
+            Some((_, value)) => Some(value.to_string()),
+            None => Some(data),
         }
     }
 }
@@ -422,16 +321,12 @@ impl<'a> Iterator for JournalIterator<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             // Try to get the next entry
-            let next_result = unsafe {
-                ffi::sd_journal_next(self.tail.handle)
-            };
-            
-            match next_result {
-                1 => {
+            match self.tail.journal.next() {
+                Ok(true) => {
                     // Found an entry
                     return Some(self.tail.get_current_entry());
                 }
-                0 => {
+                Ok(false) => {
                     // No more entries, wait for new ones using polling approach
                     match self.tail.wait_for_entries_polling() {
                         Ok(()) => {
@@ -444,13 +339,9 @@ impl<'a> Iterator for JournalIterator<'a> {
                         }
                     }
                 }
-                r if r < 0 => {
+                Err(e) => {
                     // Error getting next entry
-                    return Some(Err(JournalError::from_errno(r)));
-                }
-                _ => {
-                    // Unexpected result
-                    return Some(Err(JournalError::Unknown(next_result)));
+                    return Some(Err(e));
                 }
             }
         }
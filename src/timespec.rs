@@ -0,0 +1,504 @@
+use crate::error::{JournalError, Result};
+
+const USEC_PER_SEC: i64 = 1_000_000;
+const SECS_PER_DAY: i64 = 86_400;
+/// Latest year a calendar time may name, as in systemd
+const MAX_YEAR: i64 = 9999;
+
+/// Time zone used for calendar times that carry no explicit offset
+///
+/// - `Local`: the system's local time zone (honoring `TZ`), including DST rules.
+/// - `Utc`: Coordinated Universal Time.
+/// - `Fixed`: a fixed offset in seconds east of UTC (e.g. `3600` for UTC+1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TimeZone {
+    #[default]
+    Local,
+    Utc,
+    Fixed(i32),
+}
+
+/// Parse a journalctl-style time specification relative to the current time
+///
+/// Calendar times without an explicit offset are interpreted in the local
+/// time zone. See [`parse_timestamp_at`] for the accepted formats.
+///
+/// # Examples
+/// ```
+/// use journald_query::timespec::parse_timestamp;
+///
+/// let hour_ago = parse_timestamp("-1h")?;
+/// let epoch = parse_timestamp("@1640995200")?;
+/// assert_eq!(epoch, 1640995200000000);
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+pub fn parse_timestamp(spec: &str) -> Result<u64> {
+    parse_timestamp_in(spec, TimeZone::Local)
+}
+
+/// Parse a time specification relative to the current time in the given time zone
+pub fn parse_timestamp_in(spec: &str, tz: TimeZone) -> Result<u64> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|_| JournalError::InvalidTimeSpec(spec.to_string()))?
+        .as_micros() as u64;
+    parse_timestamp_at(spec, now, tz)
+}
+
+/// Parse a time specification relative to `now_usec`
+///
+/// Accepted formats (timestamps are returned in microseconds since the Unix epoch):
+/// - `now`, `today`, `yesterday`, `tomorrow` (midnight in `tz`)
+/// - Relative offsets: `-1h`, `+30min`, `-1h 30min`, `2d ago`
+/// - Epoch seconds: `@1640995200`, `@1640995200.5`
+/// - Calendar times: `2024-03-01`, `2024-03-01 10:00`, `2024-03-01 10:00:00.250`, `10:00` (today)
+/// - Explicit zones: `2024-03-01 10:00:00 UTC`, `2024-03-01T10:00:00+02:00`, `2024-03-01T10:00:00Z`
+///
+/// # Examples
+/// ```
+/// use journald_query::timespec::{parse_timestamp_at, TimeZone};
+///
+/// let now = 1640998800000000; // 2022-01-01 01:00:00 UTC
+/// assert_eq!(parse_timestamp_at("-1h", now, TimeZone::Utc)?, 1640995200000000);
+/// assert_eq!(parse_timestamp_at("today", now, TimeZone::Utc)?, 1640995200000000);
+/// assert_eq!(parse_timestamp_at("2022-01-01T02:00:00+01:00", now, TimeZone::Utc)?, 1640998800000000);
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+pub fn parse_timestamp_at(spec: &str, now_usec: u64, tz: TimeZone) -> Result<u64> {
+    let invalid = || JournalError::InvalidTimeSpec(spec.to_string());
+    let trimmed = spec.trim();
+    let now = i64::try_from(now_usec).map_err(|_| invalid())?;
+
+    let usec = match trimmed {
+        "" => return Err(invalid()),
+        "now" => now,
+        "today" => midnight(now, 0, tz).ok_or_else(invalid)?,
+        "yesterday" => midnight(now, -1, tz).ok_or_else(invalid)?,
+        "tomorrow" => midnight(now, 1, tz).ok_or_else(invalid)?,
+        _ => {
+            if let Some(epoch) = trimmed.strip_prefix('@') {
+                parse_epoch(epoch).ok_or_else(invalid)?
+            } else if let Some(span) = trimmed.strip_prefix('-') {
+                now.checked_sub(span_to_i64(span).ok_or_else(invalid)?).ok_or_else(invalid)?
+            } else if let Some(span) = trimmed.strip_prefix('+') {
+                now.checked_add(span_to_i64(span).ok_or_else(invalid)?).ok_or_else(invalid)?
+            } else if let Some(span) = trimmed.strip_suffix(" ago") {
+                now.checked_sub(span_to_i64(span).ok_or_else(invalid)?).ok_or_else(invalid)?
+            } else {
+                parse_calendar(trimmed, now, tz).ok_or_else(invalid)?
+            }
+        }
+    };
+
+    u64::try_from(usec).map_err(|_| invalid())
+}
+
+/// Parse a time span such as `90s`, `1h 30min` or `2.5d` into microseconds
+///
+/// A number without a unit is taken as seconds.
+///
+/// # Examples
+/// ```
+/// use journald_query::timespec::parse_timespan;
+///
+/// assert_eq!(parse_timespan("1h 30min")?, 5_400_000_000);
+/// assert_eq!(parse_timespan("250ms")?, 250_000);
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+pub fn parse_timespan(span: &str) -> Result<u64> {
+    span_to_i64(span)
+        .and_then(|usec| u64::try_from(usec).ok())
+        .ok_or_else(|| JournalError::InvalidTimeSpec(span.to_string()))
+}
+
+fn span_to_i64(span: &str) -> Option<i64> {
+    let mut rest = span.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut total: f64 = 0.0;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = rest[number_len..].trim_start();
+
+        let unit_len = rest
+            .find(|c: char| !c.is_alphabetic() && c != 'µ')
+            .unwrap_or(rest.len());
+        let unit = &rest[..unit_len];
+        rest = rest[unit_len..].trim_start();
+
+        total += number * unit_usec(unit)? as f64;
+    }
+
+    // Casting would saturate spans too long to represent
+    let total = total.round();
+    if total >= i64::MAX as f64 {
+        return None;
+    }
+    Some(total as i64)
+}
+
+/// Microseconds per unit, using systemd's unit names and lengths
+fn unit_usec(unit: &str) -> Option<i64> {
+    let usec = match unit {
+        "usec" | "us" | "µs" => 1,
+        "msec" | "ms" => 1_000,
+        "" | "seconds" | "second" | "sec" | "s" => USEC_PER_SEC,
+        "minutes" | "minute" | "min" | "m" => 60 * USEC_PER_SEC,
+        "hours" | "hour" | "hr" | "h" => 3_600 * USEC_PER_SEC,
+        "days" | "day" | "d" => SECS_PER_DAY * USEC_PER_SEC,
+        "weeks" | "week" | "w" => 7 * SECS_PER_DAY * USEC_PER_SEC,
+        "months" | "month" | "M" => 2_629_800 * USEC_PER_SEC,
+        "years" | "year" | "y" => 31_557_600 * USEC_PER_SEC,
+        _ => return None,
+    };
+    Some(usec)
+}
+
+/// Parse `@SECONDS[.FRACTION]` without going through floating point
+fn parse_epoch(epoch: &str) -> Option<i64> {
+    let (secs, fraction) = epoch.split_once('.').unwrap_or((epoch, ""));
+    if secs.is_empty() || !secs.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    secs.parse::<i64>().ok()?.checked_mul(USEC_PER_SEC)?.checked_add(parse_fraction(fraction)?)
+}
+
+/// Convert fractional-second digits into microseconds (extra digits are truncated)
+fn parse_fraction(fraction: &str) -> Option<i64> {
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let digits: String = fraction.chars().chain("000000".chars()).take(6).collect();
+    digits.parse().ok()
+}
+
+/// Midnight at the start of the day `day_offset` days from the day containing `now`
+fn midnight(now: i64, day_offset: i64, tz: TimeZone) -> Option<i64> {
+    let (year, month, day) = current_date(now, tz)?;
+    civil_to_usec(year, month, day + day_offset, 0, 0, 0, 0, Zone::Implicit(tz))
+}
+
+/// Calendar date containing `now` in the given time zone
+fn current_date(now: i64, tz: TimeZone) -> Option<(i64, i64, i64)> {
+    let secs = now.div_euclid(USEC_PER_SEC);
+    match tz {
+        TimeZone::Utc => Some(civil_from_days(secs.div_euclid(SECS_PER_DAY))),
+        TimeZone::Fixed(offset) => {
+            Some(civil_from_days((secs + offset as i64).div_euclid(SECS_PER_DAY)))
+        }
+        TimeZone::Local => {
+            let time = secs as libc::time_t;
+            let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+            let result = unsafe { libc::localtime_r(&time, &mut tm) };
+            if result.is_null() {
+                return None;
+            }
+            Some((tm.tm_year as i64 + 1900, tm.tm_mon as i64 + 1, tm.tm_mday as i64))
+        }
+    }
+}
+
+/// Offset information attached to a calendar time
+enum Zone {
+    /// Explicit offset in seconds east of UTC, from the spec itself
+    Explicit(i64),
+    /// No offset in the spec, so the caller's time zone applies
+    Implicit(TimeZone),
+}
+
+/// Parse `DATE`, `DATE TIME`, `DATE'T'TIME` or `TIME`, with an optional zone suffix
+fn parse_calendar(spec: &str, now: i64, tz: TimeZone) -> Option<i64> {
+    let (body, zone) = split_zone(spec, tz)?;
+
+    let (date, time) = match body.split_once(['T', ' ']) {
+        Some((date, time)) => (Some(date), Some(time.trim())),
+        None if body.contains(':') => (None, Some(body)),
+        None => (Some(body), None),
+    };
+
+    let (year, month, day) = match date {
+        Some(date) => parse_date(date)?,
+        None => current_date(now, tz)?,
+    };
+    let (hour, minute, second, usec) = match time {
+        Some(time) => parse_time(time)?,
+        None => (0, 0, 0, 0),
+    };
+
+    civil_to_usec(year, month, day, hour, minute, second, usec, zone)
+}
+
+/// Split a trailing ` UTC`, `Z` or `±HH:MM` suffix from a calendar spec
+fn split_zone(spec: &str, tz: TimeZone) -> Option<(&str, Zone)> {
+    if let Some(body) = spec.strip_suffix(" UTC") {
+        return Some((body.trim_end(), Zone::Explicit(0)));
+    }
+    if let Some(body) = spec.strip_suffix('Z') {
+        return Some((body, Zone::Explicit(0)));
+    }
+
+    // An offset only follows a time, so look after the last ':'-bearing part
+    if let Some(time_start) = spec.find(':') {
+        if let Some(pos) = spec[time_start..].rfind(['+', '-']) {
+            let split = time_start + pos;
+            let offset = parse_offset(&spec[split..])?;
+            return Some((spec[..split].trim_end(), Zone::Explicit(offset)));
+        }
+    }
+
+    Some((spec, Zone::Implicit(tz)))
+}
+
+/// Parse `+HH:MM`, `+HHMM` or `+HH` into seconds east of UTC
+fn parse_offset(offset: &str) -> Option<i64> {
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let digits: String = offset[1..].chars().filter(|c| *c != ':').collect();
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i64>().ok()?, 0),
+        4 => (digits[..2].parse::<i64>().ok()?, digits[2..].parse::<i64>().ok()?),
+        _ => return None,
+    };
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3_600 + minutes * 60))
+}
+
+fn parse_date(date: &str) -> Option<(i64, i64, i64)> {
+    let mut parts = date.split('-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+    Some((year, month, day))
+}
+
+/// Number of days in `month` of the proleptic Gregorian `year`
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn parse_time(time: &str) -> Option<(i64, i64, i64, i64)> {
+    let (clock, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut parts = clock.split(':');
+    let hour = parts.next()?.parse().ok()?;
+    let minute = parts.next()?.parse().ok()?;
+    let second = match parts.next() {
+        Some(second) => second.parse().ok()?,
+        None => 0,
+    };
+    if parts.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    Some((hour, minute, second, parse_fraction(fraction)?))
+}
+
+/// Convert a broken-down calendar time into microseconds since the epoch
+///
+/// `day` may fall outside the month; it is normalized the same way `mktime` does.
+/// Years outside `1..=9999` are rejected.
+#[allow(clippy::too_many_arguments)]
+fn civil_to_usec(
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+    usec: i64,
+    zone: Zone,
+) -> Option<i64> {
+    if !(1..=MAX_YEAR).contains(&year) {
+        return None;
+    }
+    let offset = match zone {
+        Zone::Explicit(offset) => offset,
+        Zone::Implicit(TimeZone::Utc) => 0,
+        Zone::Implicit(TimeZone::Fixed(offset)) => offset as i64,
+        Zone::Implicit(TimeZone::Local) => {
+            let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+            tm.tm_year = (year - 1900) as libc::c_int;
+            tm.tm_mon = (month - 1) as libc::c_int;
+            tm.tm_mday = day as libc::c_int;
+            tm.tm_hour = hour as libc::c_int;
+            tm.tm_min = minute as libc::c_int;
+            tm.tm_sec = second as libc::c_int;
+            tm.tm_isdst = -1; // Let the C library work out DST
+            let secs = unsafe { libc::mktime(&mut tm) };
+            if secs == -1 {
+                return None;
+            }
+            return (secs as i64).checked_mul(USEC_PER_SEC)?.checked_add(usec);
+        }
+    };
+
+    let secs = days_from_civil(year, month, 1) * SECS_PER_DAY
+        + (day - 1) * SECS_PER_DAY
+        + hour * 3_600
+        + minute * 60
+        + second
+        - offset;
+    Some(secs * USEC_PER_SEC + usec)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of [`days_from_civil`]
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2022-01-01 01:00:00 UTC
+    const NOW: u64 = 1640998800000000;
+    const MIDNIGHT: u64 = 1640995200000000;
+
+    fn parse(spec: &str) -> Result<u64> {
+        parse_timestamp_at(spec, NOW, TimeZone::Utc)
+    }
+
+    #[test]
+    fn test_keywords() {
+        assert_eq!(parse("now").unwrap(), NOW);
+        assert_eq!(parse("today").unwrap(), MIDNIGHT);
+        assert_eq!(parse("yesterday").unwrap(), MIDNIGHT - 86_400_000_000);
+        assert_eq!(parse("tomorrow").unwrap(), MIDNIGHT + 86_400_000_000);
+    }
+
+    #[test]
+    fn test_relative_offsets() {
+        assert_eq!(parse("-1h").unwrap(), MIDNIGHT);
+        assert_eq!(parse("-30min").unwrap(), NOW - 1_800_000_000);
+        assert_eq!(parse("-30m 15s").unwrap(), NOW - 1_815_000_000);
+        assert_eq!(parse("+2d").unwrap(), NOW + 172_800_000_000);
+        assert_eq!(parse("1h ago").unwrap(), MIDNIGHT);
+        assert!(parse("-1 fortnight").is_err());
+    }
+
+    #[test]
+    fn test_epoch() {
+        assert_eq!(parse("@1640995200").unwrap(), MIDNIGHT);
+        assert_eq!(parse("@1640995200.25").unwrap(), MIDNIGHT + 250_000);
+        assert!(parse("@").is_err());
+        assert!(parse("@-5").is_err());
+    }
+
+    #[test]
+    fn test_calendar_times() {
+        assert_eq!(parse("2022-01-01").unwrap(), MIDNIGHT);
+        assert_eq!(parse("2022-01-01 01:00").unwrap(), NOW);
+        assert_eq!(parse("2022-01-01 01:00:00").unwrap(), NOW);
+        assert_eq!(parse("2022-01-01 00:00:00.5").unwrap(), MIDNIGHT + 500_000);
+        assert_eq!(parse("00:30").unwrap(), MIDNIGHT + 1_800_000_000);
+        assert!(parse("2022-13-01").is_err());
+        for spec in ["2023-02-29", "2024-04-31", "1900-02-29"] {
+            assert_eq!(parse(spec), Err(JournalError::InvalidTimeSpec(spec.to_string())));
+        }
+        assert!(parse("2024-02-29").is_ok());
+        assert!(parse("2000-02-29").is_ok());
+        assert!(parse("2022-01-01 25:00").is_err());
+    }
+
+    #[test]
+    fn test_explicit_zones() {
+        assert_eq!(parse("2022-01-01 01:00:00 UTC").unwrap(), NOW);
+        assert_eq!(parse("2022-01-01T01:00:00Z").unwrap(), NOW);
+        assert_eq!(parse("2022-01-01T02:00:00+01:00").unwrap(), NOW);
+        assert_eq!(parse("2021-12-31T20:00:00-0500").unwrap(), NOW);
+        assert!(parse("2022-01-01T02:00:00+25:00").is_err());
+    }
+
+    #[test]
+    fn test_fixed_time_zone() {
+        let tz = TimeZone::Fixed(2 * 3_600);
+        // 01:00 UTC is 03:00 at UTC+2, so "today" starts at 22:00 UTC the day before
+        assert_eq!(parse_timestamp_at("today", NOW, tz).unwrap(), MIDNIGHT - 7_200_000_000);
+        assert_eq!(parse_timestamp_at("2022-01-01 03:00", NOW, tz).unwrap(), NOW);
+    }
+
+    #[test]
+    fn test_local_time_zone() {
+        let now = parse_timestamp_in("now", TimeZone::Local).unwrap();
+        let today = parse_timestamp_in("today", TimeZone::Local).unwrap();
+        assert!(today <= now);
+        assert!(now - today < 86_400_000_000 + 3_600_000_000);
+    }
+
+    #[test]
+    fn test_timespan() {
+        assert_eq!(parse_timespan("90").unwrap(), 90_000_000);
+        assert_eq!(parse_timespan("1h30min").unwrap(), 5_400_000_000);
+        assert_eq!(parse_timespan("1.5s").unwrap(), 1_500_000);
+        assert_eq!(parse_timespan("2w").unwrap(), 14 * 86_400_000_000);
+        assert!(parse_timespan("").is_err());
+        assert!(parse_timespan("soon").is_err());
+    }
+
+    #[test]
+    fn test_civil_round_trip() {
+        for days in [-719_468, -1, 0, 1, 18_993, 19_052, 2_932_896] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+        assert_eq!(civil_from_days(18_993), (2022, 1, 1));
+    }
+
+    #[test]
+    fn test_invalid_specs() {
+        for spec in ["", "   ", "sometime", "2022-01", "-", "+"] {
+            assert!(
+                matches!(parse(spec), Err(JournalError::InvalidTimeSpec(_))),
+                "{:?} should be rejected",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn test_out_of_range_specs() {
+        for spec in ["+1000000000y", "1000000000y ago", "@99999999999999", "99999999999-01-01", "10000-01-01 00:00"] {
+            let rejected = Err(JournalError::InvalidTimeSpec(spec.to_string()));
+            assert_eq!(parse(spec), rejected, "{:?} should be rejected", spec);
+            assert_eq!(parse_timestamp_at(spec, NOW, TimeZone::Local), rejected, "{:?} should be rejected", spec);
+        }
+        assert!(parse_timespan("1000000000000y").is_err());
+        assert_eq!(parse("9999-12-31 23:59:59").unwrap(), 253_402_300_799_000_000);
+    }
+}
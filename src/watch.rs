@@ -0,0 +1,384 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::alias::HostAliases;
+use crate::control::ScanControl;
+use crate::discover::{discover_services_with_options, DiscoveryOptions, Host, HostKey, HostnameSeen, Hosts};
+use crate::error::Result;
+use crate::journal::Journal;
+use crate::query::field_value;
+use crate::source::JournalSource;
+
+/// A change in the hosts and units that log to the journal
+///
+/// Hosts are identified as in [`Host`]: by `machine_id` if their entries carry
+/// one, otherwise by `hostname`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DiscoveryEvent {
+    /// A host logged for the first time. Its units follow as `UnitAdded` events.
+    HostAdded {
+        hostname: String,
+        machine_id: Option<String>,
+    },
+    /// A unit logged from a host for the first time
+    UnitAdded {
+        hostname: String,
+        machine_id: Option<String>,
+        unit: String,
+    },
+    /// A unit has logged nothing from a host for `silent_for`, which is at
+    /// least the watcher's silence threshold. Reported once per silence.
+    UnitSilent {
+        hostname: String,
+        machine_id: Option<String>,
+        unit: String,
+        silent_for: Duration,
+    },
+}
+
+/// Follows the journal for hosts and units that appear or fall silent
+///
+/// Starts from a [`crate::discover_services_with_options`] snapshot and then
+/// reads each new entry, like [`crate::JournalTail`] but without host or unit
+/// filters. Journal files created later, such as those of a new remote host,
+/// are picked up too. [`DiscoveryWatcher::hosts`] is kept up to date with
+/// what the events report.
+///
+/// The snapshot honors all discovery options, so `filter` can leave out
+/// hosts that have not logged for months. New entries are not filtered.
+///
+/// # Examples
+/// ```no_run
+/// use journald_query::{DiscoveryEvent, DiscoveryWatcher};
+/// use std::time::Duration;
+///
+/// let mut watcher = DiscoveryWatcher::new("/var/log/journal")?
+///     .silence_threshold(Duration::from_secs(600));
+/// println!("{} hosts so far", watcher.hosts().len());
+///
+/// for event in watcher.iter() {
+///     match event? {
+///         DiscoveryEvent::HostAdded { hostname, .. } => println!("new host {}", hostname),
+///         DiscoveryEvent::UnitAdded { hostname, unit, .. } => println!("new unit {} on {}", unit, hostname),
+///         DiscoveryEvent::UnitSilent { hostname, unit, silent_for, .. } => {
+///             println!("{} on {} silent for {:?}", unit, hostname, silent_for)
+///         }
+///     }
+/// }
+/// # Ok::<(), journald_query::JournalError>(())
+/// ```
+#[derive(Debug)]
+pub struct DiscoveryWatcher {
+    journal: Journal,
+    hosts: Hosts,
+    /// Position of each host in `hosts`
+    positions: HashMap<HostKey, usize>,
+    aliases: HostAliases,
+    /// Receive time of the latest entry of each host and unit
+    last_seen: HashMap<(HostKey, String), u64>,
+    silent: HashSet<(HostKey, String)>,
+    pending: VecDeque<DiscoveryEvent>,
+    poll_interval: Duration,
+    silence_threshold: Option<Duration>,
+}
+
+impl DiscoveryWatcher {
+    /// Discover the services in the journal and start following it
+    ///
+    /// Defaults:
+    /// - Polling interval: 1 second
+    /// - Silence threshold: none, so `UnitSilent` is not reported
+    pub fn new<S: JournalSource + ?Sized>(source: &S) -> Result<Self> {
+        DiscoveryWatcher::with_options(source, &DiscoveryOptions::new())
+    }
+
+    /// Discover the services in the journal with the given options and start
+    /// following it
+    ///
+    /// Units in the snapshot count as last seen when the watcher started,
+    /// unless `options.unit_stats` gives their last entry.
+    pub fn with_options<S: JournalSource + ?Sized>(source: &S, options: &DiscoveryOptions) -> Result<Self> {
+        let journal = source.open_journal()?;
+        journal.watch()?;
+
+        // Remember where the journal ends before the snapshot, so entries
+        // written while it is taken are read afterwards
+        journal.seek_tail()?;
+        let end = match journal.previous()? {
+            true => Some(journal.get_cursor()?),
+            false => None,
+        };
+
+        let hosts = discover_services_with_options(source, options, &mut ScanControl::new())?;
+
+        match end {
+            Some(cursor) => {
+                journal.seek_cursor(&cursor)?;
+                journal.next()?;
+            }
+            None => journal.seek_head()?,
+        }
+
+        let started = now_usec();
+        let mut last_seen = HashMap::new();
+        for host in &hosts.hosts {
+            for unit in &host.units {
+                let seen = host.stats(unit).map_or(started, |stats| stats.last_seen_utc);
+                last_seen.insert((host.key(), unit.clone()), seen);
+            }
+        }
+
+        let mut watcher = DiscoveryWatcher {
+            journal,
+            hosts,
+            positions: HashMap::new(),
+            aliases: options.host_aliases.clone().unwrap_or_default(),
+            last_seen,
+            silent: HashSet::new(),
+            pending: VecDeque::new(),
+            poll_interval: Duration::from_secs(1),
+            silence_threshold: None,
+        };
+        watcher.index_hosts();
+        Ok(watcher)
+    }
+
+    /// Set how long to wait between checks for new entries
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Report units that log nothing for `threshold` as `UnitSilent`
+    pub fn silence_threshold(mut self, threshold: Duration) -> Self {
+        self.silence_threshold = Some(threshold);
+        self
+    }
+
+    /// The hosts and units known so far, sorted by hostname
+    pub fn hosts(&self) -> &Hosts {
+        &self.hosts
+    }
+
+    /// Read the entries written since the last poll and report what changed
+    ///
+    /// Does not block. Events already returned by [`DiscoveryWatcher::iter`]
+    /// are not repeated.
+    pub fn poll(&mut self) -> Result<Vec<DiscoveryEvent>> {
+        let mut events: Vec<DiscoveryEvent> = self.pending.drain(..).collect();
+
+        self.journal.process()?;
+        while self.journal.next()? {
+            self.record_entry(&mut events)?;
+        }
+        self.check_silence(now_usec(), &mut events);
+
+        Ok(events)
+    }
+
+    /// Get a blocking iterator over events
+    ///
+    /// Each call to `next()` waits, polling every `poll_interval`, until
+    /// there is an event to return.
+    pub fn iter(&mut self) -> DiscoveryEvents<'_> {
+        DiscoveryEvents { watcher: self }
+    }
+
+    /// Update the hosts and units with the current entry
+    fn record_entry(&mut self, events: &mut Vec<DiscoveryEvent>) -> Result<()> {
+        let journal = &self.journal;
+        let Some(hostname) = field_value(journal, "_HOSTNAME")? else {
+            return Ok(());
+        };
+        let hostname = self.aliases.canonical(&hostname).to_string();
+        let machine_id = field_value(journal, "_MACHINE_ID")?;
+        let unit = field_value(journal, "_SYSTEMD_UNIT")?;
+        let received = journal.get_realtime_usec()?;
+
+        let key = HostKey::new(machine_id.clone(), &hostname);
+        let position = match self.positions.get(&key) {
+            Some(&position) => position,
+            None => {
+                self.hosts.hosts.push(Host {
+                    hostname: hostname.clone(),
+                    machine_id: machine_id.clone(),
+                    hostnames: Vec::new(),
+                    units: Vec::new(),
+                    unit_stats: None,
+                });
+                events.push(DiscoveryEvent::HostAdded {
+                    hostname: hostname.clone(),
+                    machine_id: machine_id.clone(),
+                });
+                self.index_hosts();
+                self.positions[&key]
+            }
+        };
+
+        // A renamed host moves in the sorted hosts
+        let position = match saw_hostname(&mut self.hosts.hosts[position], &hostname, received) {
+            true => {
+                self.index_hosts();
+                self.positions[&key]
+            }
+            false => position,
+        };
+
+        let Some(unit) = unit else {
+            return Ok(());
+        };
+        let host = &mut self.hosts.hosts[position];
+        if let Err(insert_at) = host.units.binary_search(&unit) {
+            host.units.insert(insert_at, unit.clone());
+            // Statistics of the snapshot are not kept up to date
+            host.unit_stats = None;
+            events.push(DiscoveryEvent::UnitAdded {
+                hostname: host.hostname.clone(),
+                machine_id: machine_id.clone(),
+                unit: unit.clone(),
+            });
+        }
+
+        let unit_key = (key, unit);
+        self.silent.remove(&unit_key);
+        let last = self.last_seen.entry(unit_key).or_insert(received);
+        *last = (*last).max(received);
+        Ok(())
+    }
+
+    /// Report units that have been silent for the threshold, once each
+    fn check_silence(&mut self, now: u64, events: &mut Vec<DiscoveryEvent>) {
+        let Some(threshold) = self.silence_threshold else {
+            return;
+        };
+
+        let mut silent = Vec::new();
+        for ((key, unit), &last) in &self.last_seen {
+            let silent_for = Duration::from_micros(now.saturating_sub(last));
+            if silent_for < threshold || self.silent.contains(&(key.clone(), unit.clone())) {
+                continue;
+            }
+            let host = &self.hosts.hosts[self.positions[key]];
+            silent.push(DiscoveryEvent::UnitSilent {
+                hostname: host.hostname.clone(),
+                machine_id: host.machine_id.clone(),
+                unit: unit.clone(),
+                silent_for,
+            });
+            self.silent.insert((key.clone(), unit.clone()));
+        }
+
+        // Report in a stable order
+        silent.sort_by(|a, b| event_order(a).cmp(&event_order(b)));
+        events.extend(silent);
+    }
+
+    /// Sort the hosts and remember where each one is
+    fn index_hosts(&mut self) {
+        self.hosts.hosts.sort_by(|a, b| (&a.hostname, &a.machine_id).cmp(&(&b.hostname, &b.machine_id)));
+        self.positions = self.hosts.hosts
+            .iter()
+            .enumerate()
+            .map(|(position, host)| (host.key(), position))
+            .collect();
+    }
+}
+
+/// Record that a host logged under `hostname`, which becomes its current name
+///
+/// Returns whether the host was renamed.
+fn saw_hostname(host: &mut Host, hostname: &str, received: u64) -> bool {
+    match host.hostnames.iter_mut().find(|seen| seen.hostname == hostname) {
+        Some(seen) => {
+            seen.first_seen_utc = seen.first_seen_utc.min(received);
+            seen.last_seen_utc = seen.last_seen_utc.max(received);
+        }
+        None => host.hostnames.push(HostnameSeen {
+            hostname: hostname.to_string(),
+            first_seen_utc: received,
+            last_seen_utc: received,
+        }),
+    }
+    if host.hostname == hostname {
+        return false;
+    }
+    host.hostname = hostname.to_string();
+    true
+}
+
+fn event_order(event: &DiscoveryEvent) -> (&str, Option<&str>, &str) {
+    match event {
+        DiscoveryEvent::HostAdded { hostname, machine_id } => (hostname, machine_id.as_deref(), ""),
+        DiscoveryEvent::UnitAdded { hostname, machine_id, unit }
+        | DiscoveryEvent::UnitSilent { hostname, machine_id, unit, .. } => (hostname, machine_id.as_deref(), unit),
+    }
+}
+
+fn now_usec() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_micros() as u64)
+}
+
+/// Blocking iterator over discovery events
+///
+/// Each call to `next()` waits until there is an event to return.
+pub struct DiscoveryEvents<'a> {
+    watcher: &'a mut DiscoveryWatcher,
+}
+
+impl Iterator for DiscoveryEvents<'_> {
+    type Item = Result<DiscoveryEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.watcher.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            match self.watcher.poll() {
+                Ok(events) if events.is_empty() => thread::sleep(self.watcher.poll_interval),
+                Ok(events) => self.watcher.pending.extend(events),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saw_hostname() {
+        let mut host = Host {
+            hostname: "old-name".to_string(),
+            machine_id: Some("0123456789abcdef0123456789abcdef".to_string()),
+            hostnames: vec![HostnameSeen {
+                hostname: "old-name".to_string(),
+                first_seen_utc: 100,
+                last_seen_utc: 200,
+            }],
+            units: Vec::new(),
+            unit_stats: None,
+        };
+
+        assert!(!saw_hostname(&mut host, "old-name", 300));
+        assert_eq!(host.hostnames[0].last_seen_utc, 300);
+        assert!(saw_hostname(&mut host, "new-name", 400));
+        assert_eq!(host.hostname, "new-name");
+        assert_eq!(host.hostnames.len(), 2);
+    }
+
+    #[test]
+    fn test_event_order() {
+        let silent = |hostname: &str, unit: &str| DiscoveryEvent::UnitSilent {
+            hostname: hostname.to_string(),
+            machine_id: None,
+            unit: unit.to_string(),
+            silent_for: Duration::from_secs(60),
+        };
+        let mut events = vec![silent("web", "nginx.service"), silent("db", "mysql.service"), silent("web", "cron.service")];
+        events.sort_by(|a, b| event_order(a).cmp(&event_order(b)));
+        assert_eq!(events, vec![silent("db", "mysql.service"), silent("web", "cron.service"), silent("web", "nginx.service")]);
+    }
+}
@@ -209,6 +209,601 @@ MESSAGE=Memory usage warning: 85% used
 PRIORITY=4
 _PID=888
 
+__CURSOR=s=7234567890abcdef;i=24;b=7234567890abcdef;m=3004000;t=1640995404000000;x=24
+__REALTIME_TIMESTAMP=1640995404000000
+__MONOTONIC_TIMESTAMP=3004000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=failing.service
+MESSAGE_ID=98e322203f7a4ed290d09fe03c09fe15
+MESSAGE=failing.service: Main process exited, code=exited, status=1/FAILURE
+EXIT_CODE=exited
+EXIT_STATUS=1
+PRIORITY=5
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=25;b=7234567890abcdef;m=3005000;t=1640995405000000;x=25
+__REALTIME_TIMESTAMP=1640995405000000
+__MONOTONIC_TIMESTAMP=3005000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=failing.service
+MESSAGE_ID=d9b373ed55a64feb8242e02dbe79a49c
+MESSAGE=failing.service: Failed with result 'exit-code'.
+UNIT_RESULT=exit-code
+PRIORITY=4
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=26;b=7234567890abcdef;m=3006000;t=1640995406000000;x=26
+__REALTIME_TIMESTAMP=1640995406000000
+__MONOTONIC_TIMESTAMP=3006000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=failing.service
+MESSAGE_ID=be02cf6855d2428ba40df7e9d022f03d
+MESSAGE=Failed to start failing.service.
+JOB_TYPE=start
+JOB_RESULT=failed
+PRIORITY=3
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=27;b=7234567890abcdef;m=3007000;t=1640995407000000;x=27
+__REALTIME_TIMESTAMP=1640995407000000
+__MONOTONIC_TIMESTAMP=3007000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=memory-constrained-server
+_SYSTEMD_UNIT=user@1000.service
+SYSLOG_IDENTIFIER=systemd
+USER_UNIT=backup.service
+MESSAGE_ID=39f53479d3a045ac8e11786248231fbf
+MESSAGE=Started backup.service.
+PRIORITY=6
+_UID=1000
+_PID=1500
+
+__CURSOR=s=7234567890abcdef;i=28;b=7234567890abcdef;m=3008000;t=1640995408000000;x=28
+__REALTIME_TIMESTAMP=1640995408000000
+__MONOTONIC_TIMESTAMP=3008000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=memory-constrained-server
+_SYSTEMD_UNIT=user@1000.service
+_SYSTEMD_USER_UNIT=backup.service
+SYSLOG_IDENTIFIER=backup
+MESSAGE=Backup completed
+PRIORITY=6
+_UID=1000
+_PID=1501
+
+__CURSOR=s=7234567890abcdef;i=29;b=7234567890abcdef;m=3100000;t=1640995500000000;x=29
+__REALTIME_TIMESTAMP=1640995500000000
+__MONOTONIC_TIMESTAMP=3100000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=7d4958e842da4a758f6c1cdc7b36dcc5
+MESSAGE=Starting auto-restart.service...
+JOB_TYPE=start
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=30;b=7234567890abcdef;m=3101000;t=1640995500001000;x=30
+__REALTIME_TIMESTAMP=1640995500001000
+__MONOTONIC_TIMESTAMP=3101000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=39f53479d3a045ac8e11786248231fbf
+MESSAGE=Started auto-restart.service.
+JOB_TYPE=start
+JOB_RESULT=done
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=31;b=7234567890abcdef;m=123100000;t=1640995620000000;x=31
+__REALTIME_TIMESTAMP=1640995620000000
+__MONOTONIC_TIMESTAMP=123100000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=auto-restart.service
+SYSLOG_IDENTIFIER=auto-restart
+MESSAGE=Connection to backend refused, exiting
+PRIORITY=3
+_PID=1001
+
+__CURSOR=s=7234567890abcdef;i=32;b=7234567890abcdef;m=123101000;t=1640995620001000;x=32
+__REALTIME_TIMESTAMP=1640995620001000
+__MONOTONIC_TIMESTAMP=123101000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=98e322203f7a4ed290d09fe03c09fe15
+MESSAGE=auto-restart.service: Main process exited, code=exited, status=1/FAILURE
+COMMAND=ExecStart
+EXIT_CODE=exited
+EXIT_STATUS=1
+PRIORITY=5
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=33;b=7234567890abcdef;m=123102000;t=1640995620002000;x=33
+__REALTIME_TIMESTAMP=1640995620002000
+__MONOTONIC_TIMESTAMP=123102000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=d9b373ed55a64feb8242e02dbe79a49c
+MESSAGE=auto-restart.service: Failed with result 'exit-code'.
+UNIT_RESULT=exit-code
+PRIORITY=4
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=34;b=7234567890abcdef;m=128100000;t=1640995625000000;x=34
+__REALTIME_TIMESTAMP=1640995625000000
+__MONOTONIC_TIMESTAMP=128100000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=5eb03494b6584870a536b337290809b3
+MESSAGE=auto-restart.service: Scheduled restart job, restart counter is at 1.
+N_RESTARTS=1
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=35;b=7234567890abcdef;m=128101000;t=1640995625001000;x=35
+__REALTIME_TIMESTAMP=1640995625001000
+__MONOTONIC_TIMESTAMP=128101000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=9d1aaa27d60140bd96365438aad20286
+MESSAGE=Stopped auto-restart.service.
+JOB_TYPE=restart
+JOB_RESULT=done
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=36;b=7234567890abcdef;m=128102000;t=1640995625002000;x=36
+__REALTIME_TIMESTAMP=1640995625002000
+__MONOTONIC_TIMESTAMP=128102000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=7d4958e842da4a758f6c1cdc7b36dcc5
+MESSAGE=Starting auto-restart.service...
+JOB_TYPE=start
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=37;b=7234567890abcdef;m=128103000;t=1640995625003000;x=37
+__REALTIME_TIMESTAMP=1640995625003000
+__MONOTONIC_TIMESTAMP=128103000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=39f53479d3a045ac8e11786248231fbf
+MESSAGE=Started auto-restart.service.
+JOB_TYPE=start
+JOB_RESULT=done
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=38;b=7234567890abcdef;m=243100000;t=1640995740000000;x=38
+__REALTIME_TIMESTAMP=1640995740000000
+__MONOTONIC_TIMESTAMP=243100000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=auto-restart.service
+SYSLOG_IDENTIFIER=auto-restart
+MESSAGE=Connection to backend refused, exiting
+PRIORITY=3
+_PID=1002
+
+__CURSOR=s=7234567890abcdef;i=39;b=7234567890abcdef;m=243101000;t=1640995740001000;x=39
+__REALTIME_TIMESTAMP=1640995740001000
+__MONOTONIC_TIMESTAMP=243101000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=98e322203f7a4ed290d09fe03c09fe15
+MESSAGE=auto-restart.service: Main process exited, code=exited, status=1/FAILURE
+COMMAND=ExecStart
+EXIT_CODE=exited
+EXIT_STATUS=1
+PRIORITY=5
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=40;b=7234567890abcdef;m=243102000;t=1640995740002000;x=40
+__REALTIME_TIMESTAMP=1640995740002000
+__MONOTONIC_TIMESTAMP=243102000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=d9b373ed55a64feb8242e02dbe79a49c
+MESSAGE=auto-restart.service: Failed with result 'exit-code'.
+UNIT_RESULT=exit-code
+PRIORITY=4
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=41;b=7234567890abcdef;m=248100000;t=1640995745000000;x=41
+__REALTIME_TIMESTAMP=1640995745000000
+__MONOTONIC_TIMESTAMP=248100000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=5eb03494b6584870a536b337290809b3
+MESSAGE=auto-restart.service: Scheduled restart job, restart counter is at 2.
+N_RESTARTS=2
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=42;b=7234567890abcdef;m=248101000;t=1640995745001000;x=42
+__REALTIME_TIMESTAMP=1640995745001000
+__MONOTONIC_TIMESTAMP=248101000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=9d1aaa27d60140bd96365438aad20286
+MESSAGE=Stopped auto-restart.service.
+JOB_TYPE=restart
+JOB_RESULT=done
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=43;b=7234567890abcdef;m=248102000;t=1640995745002000;x=43
+__REALTIME_TIMESTAMP=1640995745002000
+__MONOTONIC_TIMESTAMP=248102000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=7d4958e842da4a758f6c1cdc7b36dcc5
+MESSAGE=Starting auto-restart.service...
+JOB_TYPE=start
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=44;b=7234567890abcdef;m=248103000;t=1640995745003000;x=44
+__REALTIME_TIMESTAMP=1640995745003000
+__MONOTONIC_TIMESTAMP=248103000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=39f53479d3a045ac8e11786248231fbf
+MESSAGE=Started auto-restart.service.
+JOB_TYPE=start
+JOB_RESULT=done
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=45;b=7234567890abcdef;m=363100000;t=1640995860000000;x=45
+__REALTIME_TIMESTAMP=1640995860000000
+__MONOTONIC_TIMESTAMP=363100000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=auto-restart.service
+SYSLOG_IDENTIFIER=auto-restart
+MESSAGE=Connection to backend refused, exiting
+PRIORITY=3
+_PID=1003
+
+__CURSOR=s=7234567890abcdef;i=46;b=7234567890abcdef;m=363101000;t=1640995860001000;x=46
+__REALTIME_TIMESTAMP=1640995860001000
+__MONOTONIC_TIMESTAMP=363101000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=98e322203f7a4ed290d09fe03c09fe15
+MESSAGE=auto-restart.service: Main process exited, code=exited, status=1/FAILURE
+COMMAND=ExecStart
+EXIT_CODE=exited
+EXIT_STATUS=1
+PRIORITY=5
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=47;b=7234567890abcdef;m=363102000;t=1640995860002000;x=47
+__REALTIME_TIMESTAMP=1640995860002000
+__MONOTONIC_TIMESTAMP=363102000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=d9b373ed55a64feb8242e02dbe79a49c
+MESSAGE=auto-restart.service: Failed with result 'exit-code'.
+UNIT_RESULT=exit-code
+PRIORITY=4
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=48;b=7234567890abcdef;m=368100000;t=1640995865000000;x=48
+__REALTIME_TIMESTAMP=1640995865000000
+__MONOTONIC_TIMESTAMP=368100000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=5eb03494b6584870a536b337290809b3
+MESSAGE=auto-restart.service: Scheduled restart job, restart counter is at 3.
+N_RESTARTS=3
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=49;b=7234567890abcdef;m=368101000;t=1640995865001000;x=49
+__REALTIME_TIMESTAMP=1640995865001000
+__MONOTONIC_TIMESTAMP=368101000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=9d1aaa27d60140bd96365438aad20286
+MESSAGE=Stopped auto-restart.service.
+JOB_TYPE=restart
+JOB_RESULT=done
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=50;b=7234567890abcdef;m=368102000;t=1640995865002000;x=50
+__REALTIME_TIMESTAMP=1640995865002000
+__MONOTONIC_TIMESTAMP=368102000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=7d4958e842da4a758f6c1cdc7b36dcc5
+MESSAGE=Starting auto-restart.service...
+JOB_TYPE=start
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=51;b=7234567890abcdef;m=368103000;t=1640995865003000;x=51
+__REALTIME_TIMESTAMP=1640995865003000
+__MONOTONIC_TIMESTAMP=368103000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=39f53479d3a045ac8e11786248231fbf
+MESSAGE=Started auto-restart.service.
+JOB_TYPE=start
+JOB_RESULT=done
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=52;b=7234567890abcdef;m=483100000;t=1640995980000000;x=52
+__REALTIME_TIMESTAMP=1640995980000000
+__MONOTONIC_TIMESTAMP=483100000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=auto-restart.service
+SYSLOG_IDENTIFIER=auto-restart
+MESSAGE=Connection to backend refused, exiting
+PRIORITY=3
+_PID=1004
+
+__CURSOR=s=7234567890abcdef;i=53;b=7234567890abcdef;m=483101000;t=1640995980001000;x=53
+__REALTIME_TIMESTAMP=1640995980001000
+__MONOTONIC_TIMESTAMP=483101000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=98e322203f7a4ed290d09fe03c09fe15
+MESSAGE=auto-restart.service: Main process exited, code=exited, status=1/FAILURE
+COMMAND=ExecStart
+EXIT_CODE=exited
+EXIT_STATUS=1
+PRIORITY=5
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=54;b=7234567890abcdef;m=483102000;t=1640995980002000;x=54
+__REALTIME_TIMESTAMP=1640995980002000
+__MONOTONIC_TIMESTAMP=483102000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=d9b373ed55a64feb8242e02dbe79a49c
+MESSAGE=auto-restart.service: Failed with result 'exit-code'.
+UNIT_RESULT=exit-code
+PRIORITY=4
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=55;b=7234567890abcdef;m=488100000;t=1640995985000000;x=55
+__REALTIME_TIMESTAMP=1640995985000000
+__MONOTONIC_TIMESTAMP=488100000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=5eb03494b6584870a536b337290809b3
+MESSAGE=auto-restart.service: Scheduled restart job, restart counter is at 4.
+N_RESTARTS=4
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=56;b=7234567890abcdef;m=488101000;t=1640995985001000;x=56
+__REALTIME_TIMESTAMP=1640995985001000
+__MONOTONIC_TIMESTAMP=488101000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=9d1aaa27d60140bd96365438aad20286
+MESSAGE=Stopped auto-restart.service.
+JOB_TYPE=restart
+JOB_RESULT=done
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=57;b=7234567890abcdef;m=488102000;t=1640995985002000;x=57
+__REALTIME_TIMESTAMP=1640995985002000
+__MONOTONIC_TIMESTAMP=488102000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=7d4958e842da4a758f6c1cdc7b36dcc5
+MESSAGE=Starting auto-restart.service...
+JOB_TYPE=start
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=58;b=7234567890abcdef;m=488103000;t=1640995985003000;x=58
+__REALTIME_TIMESTAMP=1640995985003000
+__MONOTONIC_TIMESTAMP=488103000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=39f53479d3a045ac8e11786248231fbf
+MESSAGE=Started auto-restart.service.
+JOB_TYPE=start
+JOB_RESULT=done
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=59;b=7234567890abcdef;m=603100000;t=1640996100000000;x=59
+__REALTIME_TIMESTAMP=1640996100000000
+__MONOTONIC_TIMESTAMP=603100000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=auto-restart.service
+SYSLOG_IDENTIFIER=auto-restart
+MESSAGE=Connection to backend refused, exiting
+PRIORITY=3
+_PID=1005
+
+__CURSOR=s=7234567890abcdef;i=60;b=7234567890abcdef;m=603101000;t=1640996100001000;x=60
+__REALTIME_TIMESTAMP=1640996100001000
+__MONOTONIC_TIMESTAMP=603101000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=98e322203f7a4ed290d09fe03c09fe15
+MESSAGE=auto-restart.service: Main process exited, code=exited, status=1/FAILURE
+COMMAND=ExecStart
+EXIT_CODE=exited
+EXIT_STATUS=1
+PRIORITY=5
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=61;b=7234567890abcdef;m=603102000;t=1640996100002000;x=61
+__REALTIME_TIMESTAMP=1640996100002000
+__MONOTONIC_TIMESTAMP=603102000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=d9b373ed55a64feb8242e02dbe79a49c
+MESSAGE=auto-restart.service: Failed with result 'exit-code'.
+UNIT_RESULT=exit-code
+PRIORITY=4
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=62;b=7234567890abcdef;m=608100000;t=1640996105000000;x=62
+__REALTIME_TIMESTAMP=1640996105000000
+__MONOTONIC_TIMESTAMP=608100000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=5eb03494b6584870a536b337290809b3
+MESSAGE=auto-restart.service: Scheduled restart job, restart counter is at 5.
+N_RESTARTS=5
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=63;b=7234567890abcdef;m=608101000;t=1640996105001000;x=63
+__REALTIME_TIMESTAMP=1640996105001000
+__MONOTONIC_TIMESTAMP=608101000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=9d1aaa27d60140bd96365438aad20286
+MESSAGE=Stopped auto-restart.service.
+JOB_TYPE=restart
+JOB_RESULT=done
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=64;b=7234567890abcdef;m=608102000;t=1640996105002000;x=64
+__REALTIME_TIMESTAMP=1640996105002000
+__MONOTONIC_TIMESTAMP=608102000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=7d4958e842da4a758f6c1cdc7b36dcc5
+MESSAGE=Starting auto-restart.service...
+JOB_TYPE=start
+PRIORITY=6
+_PID=1
+
+__CURSOR=s=7234567890abcdef;i=65;b=7234567890abcdef;m=608103000;t=1640996105003000;x=65
+__REALTIME_TIMESTAMP=1640996105003000
+__MONOTONIC_TIMESTAMP=608103000
+_BOOT_ID=7234567890abcdef1234567890abcdef
+_HOSTNAME=error-prone-server
+_SYSTEMD_UNIT=init.scope
+SYSLOG_IDENTIFIER=systemd
+UNIT=auto-restart.service
+MESSAGE_ID=39f53479d3a045ac8e11786248231fbf
+MESSAGE=Started auto-restart.service.
+JOB_TYPE=start
+JOB_RESULT=done
+PRIORITY=6
+_PID=1
+
 EOF
 
 # Now convert these export format files to proper journal files
@@ -266,10 +861,25 @@ Single-Host Many-Units Scenario:
   Units: systemd.service, NetworkManager.service, sshd.service, docker.service, cron.service, systemd-logind.service
   Messages: System boot and service startup messages
 
-Error Scenarios:
+Error Scenarios (built into a binary journal by the tests that read it):
   Hosts: error-prone-server, disk-full-server, memory-constrained-server
-  Units: failing.service, broken.service, disk-monitor.service, memory-monitor.service
-  Messages: Various error and warning messages (priorities 1-4)
+  Units: failing.service, broken.service, disk-monitor.service, memory-monitor.service, auto-restart.service, init.scope, user@1000.service
+  Messages: Various error and warning messages (priorities 1-4), plus systemd's own
+  messages about failing.service (UNIT=) and the user unit backup.service (USER_UNIT=),
+  and auto-restart.service exiting with status 1 and being restarted 5 times in 10 minutes
+
+Shared Hostname Scenario (shared_hostname.export, built into a binary journal by the tests):
+  Machines: aaaa... and bbbb..., both named localhost
+  Units: nginx.service on aaaa, postgres.service on bbbb, sshd.service on both
+  Messages: sshd.service restarted once on aaaa, and dumping core once on bbbb
+
+Renamed Host Scenario (renamed_host.export, built into a binary journal by the tests):
+  Machines: cccc... (old-name, then mid-name, then new-name), dddd... (db-server)
+  Units: app.service and cron.service on cccc, postgres.service on dddd
+
+Mixed Machine ID Scenario (mixed_machine_ids.export, built into a binary journal by the tests):
+  Machines: eeee... (workstation), plus web-server and db-server without machine IDs
+  Units: sshd.service and nginx.service on eeee, nginx.service on web-server, postgres.service on db-server
 
 === FILES CREATED ===
 $(ls -la "${TEST_DIR}"/*.journal* "${TEST_DIR}"/*.export 2>/dev/null || echo "No journal files found")
@@ -573,6 +1183,238 @@ PRIORITY=6
 
 EOF
 
+echo "Creating shared hostname scenario..."
+
+# Machines aaaa and bbbb both call themselves localhost. Both run sshd.service,
+# which systemd restarts on aaaa and which dumps core on bbbb.
+cat > "${TEST_DIR}/shared_hostname.export" << 'EOF'
+__REALTIME_TIMESTAMP=1641081600000000
+__MONOTONIC_TIMESTAMP=600000000
+_BOOT_ID=a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1
+_MACHINE_ID=aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+_HOSTNAME=localhost
+_SYSTEMD_UNIT=nginx.service
+MESSAGE=nginx started
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641081601000000
+__MONOTONIC_TIMESTAMP=601000000
+_BOOT_ID=b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1
+_MACHINE_ID=bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb
+_HOSTNAME=localhost
+_SYSTEMD_UNIT=postgres.service
+MESSAGE=postgres started
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641081602000000
+__MONOTONIC_TIMESTAMP=602000000
+_BOOT_ID=a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1
+_MACHINE_ID=aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+_HOSTNAME=localhost
+_SYSTEMD_UNIT=nginx.service
+MESSAGE=GET / 200
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641081603000000
+__MONOTONIC_TIMESTAMP=603000000
+_BOOT_ID=b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1
+_MACHINE_ID=bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb
+_HOSTNAME=localhost
+_SYSTEMD_UNIT=postgres.service
+MESSAGE=checkpoint complete
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641081604000000
+__MONOTONIC_TIMESTAMP=604000000
+_BOOT_ID=a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1
+_MACHINE_ID=aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+_HOSTNAME=localhost
+_SYSTEMD_UNIT=sshd.service
+MESSAGE=Server listening on 0.0.0.0 port 22
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641081605000000
+__MONOTONIC_TIMESTAMP=605000000
+_BOOT_ID=b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1
+_MACHINE_ID=bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb
+_HOSTNAME=localhost
+_SYSTEMD_UNIT=sshd.service
+MESSAGE=Server listening on 0.0.0.0 port 22
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641081606000000
+__MONOTONIC_TIMESTAMP=606000000
+_BOOT_ID=a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1
+_MACHINE_ID=aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+_HOSTNAME=localhost
+_PID=1
+_UID=0
+UNIT=sshd.service
+MESSAGE_ID=98e322203f7a4ed290d09fe03c09fe15
+MESSAGE=sshd.service: Main process exited, code=exited, status=255/EXCEPTION
+EXIT_CODE=exited
+EXIT_STATUS=255
+PRIORITY=5
+
+__REALTIME_TIMESTAMP=1641081607000000
+__MONOTONIC_TIMESTAMP=607000000
+_BOOT_ID=a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1
+_MACHINE_ID=aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+_HOSTNAME=localhost
+_PID=1
+_UID=0
+UNIT=sshd.service
+MESSAGE_ID=5eb03494b6584870a536b337290809b3
+MESSAGE=sshd.service: Scheduled restart job, restart counter is at 1.
+N_RESTARTS=1
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641081608000000
+__MONOTONIC_TIMESTAMP=608000000
+_BOOT_ID=b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1
+_MACHINE_ID=bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb
+_HOSTNAME=localhost
+_PID=4242
+_UID=0
+COREDUMP_UNIT=sshd.service
+MESSAGE_ID=fc2e22bc6ee647b6b90729ab34a250b1
+MESSAGE=Process 812 (sshd) of user 0 dumped core.
+PRIORITY=2
+
+__REALTIME_TIMESTAMP=1641081609000000
+__MONOTONIC_TIMESTAMP=609000000
+_BOOT_ID=a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1
+_MACHINE_ID=aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+_HOSTNAME=localhost
+_SYSTEMD_UNIT=sshd.service
+MESSAGE=Server listening on 0.0.0.0 port 22
+PRIORITY=6
+EOF
+
+echo "Creating renamed host scenario..."
+
+# Machine cccc is renamed from old-name to mid-name to new-name, next to db-server
+cat > "${TEST_DIR}/renamed_host.export" << 'EOF'
+__REALTIME_TIMESTAMP=1641168000000000
+__MONOTONIC_TIMESTAMP=1000000000
+_BOOT_ID=c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1
+_MACHINE_ID=cccccccccccccccccccccccccccccccc
+_HOSTNAME=old-name
+_SYSTEMD_UNIT=app.service
+MESSAGE=app started
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641168001000000
+__MONOTONIC_TIMESTAMP=1001000000
+_BOOT_ID=d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1
+_MACHINE_ID=dddddddddddddddddddddddddddddddd
+_HOSTNAME=db-server
+_SYSTEMD_UNIT=postgres.service
+MESSAGE=postgres started
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641168002000000
+__MONOTONIC_TIMESTAMP=1002000000
+_BOOT_ID=c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1
+_MACHINE_ID=cccccccccccccccccccccccccccccccc
+_HOSTNAME=old-name
+_SYSTEMD_UNIT=app.service
+MESSAGE=renaming host
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641168003000000
+__MONOTONIC_TIMESTAMP=1003000000
+_BOOT_ID=c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1
+_MACHINE_ID=cccccccccccccccccccccccccccccccc
+_HOSTNAME=mid-name
+_SYSTEMD_UNIT=app.service
+MESSAGE=app running
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641168004000000
+__MONOTONIC_TIMESTAMP=1004000000
+_BOOT_ID=d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1d1
+_MACHINE_ID=dddddddddddddddddddddddddddddddd
+_HOSTNAME=db-server
+_SYSTEMD_UNIT=postgres.service
+MESSAGE=checkpoint complete
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641168005000000
+__MONOTONIC_TIMESTAMP=1005000000
+_BOOT_ID=c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1
+_MACHINE_ID=cccccccccccccccccccccccccccccccc
+_HOSTNAME=new-name
+_SYSTEMD_UNIT=app.service
+MESSAGE=app running
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641168006000000
+__MONOTONIC_TIMESTAMP=1006000000
+_BOOT_ID=c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1
+_MACHINE_ID=cccccccccccccccccccccccccccccccc
+_HOSTNAME=new-name
+_SYSTEMD_UNIT=cron.service
+MESSAGE=job done
+PRIORITY=6
+EOF
+
+echo "Creating mixed machine ID scenario..."
+
+# Machine eeee logs next to web-server and db-server, imported without machine IDs
+cat > "${TEST_DIR}/mixed_machine_ids.export" << 'EOF'
+__REALTIME_TIMESTAMP=1641254400000000
+__MONOTONIC_TIMESTAMP=1100000000
+_BOOT_ID=e1e1e1e1e1e1e1e1e1e1e1e1e1e1e1e1
+_MACHINE_ID=eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee
+_HOSTNAME=workstation
+_SYSTEMD_UNIT=sshd.service
+MESSAGE=Server listening on 0.0.0.0 port 22
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641254401000000
+__MONOTONIC_TIMESTAMP=1101000000
+_BOOT_ID=f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1
+_HOSTNAME=web-server
+_SYSTEMD_UNIT=nginx.service
+MESSAGE=nginx started
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641254402000000
+__MONOTONIC_TIMESTAMP=1102000000
+_BOOT_ID=e1e1e1e1e1e1e1e1e1e1e1e1e1e1e1e1
+_MACHINE_ID=eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee
+_HOSTNAME=workstation
+_SYSTEMD_UNIT=nginx.service
+MESSAGE=nginx started
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641254403000000
+__MONOTONIC_TIMESTAMP=1103000000
+_BOOT_ID=f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2f2
+_HOSTNAME=db-server
+_SYSTEMD_UNIT=postgres.service
+MESSAGE=postgres started
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641254404000000
+__MONOTONIC_TIMESTAMP=1104000000
+_BOOT_ID=f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1f1
+_HOSTNAME=web-server
+_SYSTEMD_UNIT=nginx.service
+MESSAGE=GET / 200
+PRIORITY=6
+
+__REALTIME_TIMESTAMP=1641254405000000
+__MONOTONIC_TIMESTAMP=1105000000
+_BOOT_ID=e1e1e1e1e1e1e1e1e1e1e1e1e1e1e1e1
+_MACHINE_ID=eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee
+_HOSTNAME=workstation
+_SYSTEMD_UNIT=sshd.service
+MESSAGE=Accepted publickey for admin
+PRIORITY=6
+EOF
+
 echo ""
 echo "=== Test Journal Creation Complete ==="
 echo "Files created in: ${TEST_DIR}"
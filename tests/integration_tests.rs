@@ -10,6 +10,57 @@ fn get_test_file_path(filename: &str) -> PathBuf {
     Path::new(TEST_JOURNAL_DIR).join(filename)
 }
 
+/// A binary journal written by systemd-journal-remote from one of the export
+/// files, alone in a temporary directory that is removed on drop
+struct BinaryJournal {
+    directory: PathBuf,
+}
+
+impl BinaryJournal {
+    /// Returns None when systemd-journal-remote is not installed
+    fn build(export_file: &str) -> Option<Self> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        
+        let directory = std::env::temp_dir().join(format!(
+            "journald-query-binary-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&directory).unwrap();
+        let journal = BinaryJournal { directory };
+        
+        let output = journal.directory.join("test.journal");
+        for remote in ["systemd-journal-remote", "/usr/lib/systemd/systemd-journal-remote"] {
+            match std::process::Command::new(remote)
+                .arg("-o")
+                .arg(&output)
+                .arg(get_test_file_path(export_file))
+                .output()
+            {
+                Ok(result) => {
+                    assert!(result.status.success(), "systemd-journal-remote failed on {}: {}",
+                        export_file, String::from_utf8_lossy(&result.stderr));
+                    return Some(journal);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => panic!("Could not run {}: {}", remote, e),
+            }
+        }
+        None
+    }
+    
+    fn path(&self) -> &Path {
+        &self.directory
+    }
+}
+
+impl Drop for BinaryJournal {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
+
 #[test]
 fn test_discover_services_api_with_test_files() {
     let test_journal_dir = "test_journal_dir";
@@ -493,7 +544,7 @@ fn test_query_journal_with_stress_test_data() {
     let first_hour_end = 1640998800000000;   // 2022-01-01 01:00:00
     
     let query = Query::new(first_hour_start, first_hour_end);
-    let entries = query_journal(&stress_test_file.parent().unwrap(), query)
+    let entries = query_journal(stress_test_file.parent().unwrap(), query)
         .expect("Should be able to query stress test data");
     
     println!("First hour query returned {} entries", entries.len());
@@ -512,7 +563,7 @@ fn test_query_journal_with_stress_test_data() {
     let web_server_query = Query::new(first_hour_start, first_hour_end + 3600000000) // 2 hours
         .hostname("web-server");
     
-    let web_server_entries = query_journal(&stress_test_file.parent().unwrap(), web_server_query)
+    let web_server_entries = query_journal(stress_test_file.parent().unwrap(), web_server_query)
         .expect("Should be able to query web-server entries");
     
     println!("Web-server query returned {} entries", web_server_entries.len());
@@ -527,7 +578,7 @@ fn test_query_journal_with_stress_test_data() {
     let nginx_query = Query::new(first_hour_start, first_hour_end + 3600000000) // 2 hours
         .unit("nginx.service");
     
-    let nginx_entries = query_journal(&stress_test_file.parent().unwrap(), nginx_query)
+    let nginx_entries = query_journal(stress_test_file.parent().unwrap(), nginx_query)
         .expect("Should be able to query nginx entries");
     
     println!("Nginx query returned {} entries", nginx_entries.len());
@@ -542,7 +593,7 @@ fn test_query_journal_with_stress_test_data() {
     let http_query = Query::new(first_hour_start, first_hour_end + 3600000000) // 2 hours
         .message_contains("HTTP");
     
-    let http_entries = query_journal(&stress_test_file.parent().unwrap(), http_query)
+    let http_entries = query_journal(stress_test_file.parent().unwrap(), http_query)
         .expect("Should be able to query HTTP entries");
     
     println!("HTTP query returned {} entries", http_entries.len());
@@ -559,7 +610,7 @@ fn test_query_journal_with_stress_test_data() {
         .unit("nginx.service")
         .message_contains("200");
     
-    let combined_entries = query_journal(&stress_test_file.parent().unwrap(), combined_query)
+    let combined_entries = query_journal(stress_test_file.parent().unwrap(), combined_query)
         .expect("Should be able to query with combined filters");
     
     println!("Combined query returned {} entries", combined_entries.len());
@@ -643,7 +694,7 @@ fn test_query_journal_edge_cases() {
         Ok(long_entries) => {
             println!("Very long time range returned {} entries", long_entries.len());
             // Should return all entries in the test file
-            assert!(long_entries.len() > 0, "Should find some entries in long time range");
+            assert!(!long_entries.is_empty(), "Should find some entries in long time range");
         },
         Err(e) => {
             println!("Long time range query failed: {}", e);
@@ -699,6 +750,1542 @@ fn test_query_journal_timestamp_precision() {
     }
 }
 
+#[test]
+fn test_query_journal_grep() {
+    use journald_query::{CaseSensitivity, Query, query_journal};
+    use std::path::PathBuf;
+    
+    // Test builder behavior for grep filters
+    let query = Query::new(1640995200000000, 1640998800000000)
+        .grep("timeout")
+        .grep("HTTP (GET|POST)");
+    let grep = query.grep.as_ref().expect("grep should be set");
+    assert_eq!(grep.patterns, vec!["timeout".to_string(), "HTTP (GET|POST)".to_string()]);
+    assert_eq!(grep.field, "MESSAGE");
+    assert_eq!(grep.case, CaseSensitivity::Smart);
+    
+    // Field and case can be set before or after the patterns
+    let query_field_first = Query::new(0, u64::MAX)
+        .grep_field("_SYSTEMD_UNIT")
+        .case_sensitivity(CaseSensitivity::Insensitive)
+        .grep("NGINX");
+    let grep = query_field_first.grep.as_ref().unwrap();
+    assert_eq!(grep.patterns, vec!["NGINX".to_string()]);
+    assert_eq!(grep.field, "_SYSTEMD_UNIT");
+    assert_eq!(grep.case, CaseSensitivity::Insensitive);
+    
+    let stress_test_file = PathBuf::from("test_journald_files/stress_test.journal");
+    let test_dir = stress_test_file.parent().unwrap();
+    
+    // Invalid patterns are reported before touching the journal
+    let invalid = query_journal(test_dir, Query::new(0, u64::MAX).grep("(unclosed"));
+    assert!(matches!(invalid, Err(journald_query::JournalError::InvalidPattern(_))));
+    
+    if !stress_test_file.exists() {
+        eprintln!("Stress test file not found, skipping grep query tests: {:?}", stress_test_file);
+        return;
+    }
+    
+    // Every returned entry must carry the positions of its matches
+    let entries = query_journal(test_dir, Query::new(0, u64::MAX).grep(r"\b5\d\d\b"))
+        .expect("Should be able to grep stress test data");
+    for entry in &entries {
+        assert!(!entry.matches.is_empty(), "Matched entry should record match positions");
+        for m in &entry.matches {
+            assert_eq!(m.field, "MESSAGE");
+            assert!(entry.message[m.start..m.end].starts_with('5'));
+        }
+    }
+    
+    // Matching on a different field uses that field's value
+    let unit_entries = query_journal(test_dir, Query::new(0, u64::MAX)
+        .grep("^nginx")
+        .grep_field("_SYSTEMD_UNIT"))
+        .expect("Should be able to grep by unit");
+    for entry in &unit_entries {
+        assert_eq!(entry.unit, Some("nginx.service".to_string()));
+        assert_eq!(entry.matches[0].field, "_SYSTEMD_UNIT");
+    }
+}
+
+#[test]
+fn test_query_time_specs() {
+    use journald_query::{Query, query_journal};
+    use std::path::PathBuf;
+    
+    // Open-ended queries cover all time until narrowed
+    let all = Query::all();
+    assert_eq!(all.start_time_utc, 0);
+    assert_eq!(all.end_time_utc, u64::MAX);
+    
+    // Epoch and RFC 3339 specs are independent of the local time zone
+    let query = Query::all()
+        .since("@1640995200").expect("epoch spec should parse")
+        .until("2022-01-01T02:00:00+01:00").expect("RFC 3339 spec should parse");
+    assert_eq!(query.start_time_utc, 1640995200000000);
+    assert_eq!(query.end_time_utc, 1640998800000000);
+    
+    // Relative specs resolve against the current time
+    let recent = Query::all().since("-1h").expect("relative spec should parse");
+    assert!(recent.start_time_utc > 1640995200000000);
+    assert_eq!(recent.end_time_utc, u64::MAX);
+    
+    let invalid = Query::all().since("the day before yesterday");
+    assert!(matches!(invalid, Err(journald_query::JournalError::InvalidTimeSpec(_))));
+    
+    let stress_test_file = PathBuf::from("test_journald_files/stress_test.journal");
+    if !stress_test_file.exists() {
+        eprintln!("Stress test file not found, skipping time spec query tests: {:?}", stress_test_file);
+        return;
+    }
+    
+    let entries = query_journal(stress_test_file.parent().unwrap(), query)
+        .expect("Should be able to query with parsed time bounds");
+    for entry in &entries {
+        assert!(entry.timestamp_utc >= 1640995200000000);
+        assert!(entry.timestamp_utc <= 1640998800000000);
+    }
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_entry_chrono_timestamp() {
+    let entry = journald_query::Entry {
+        hostname: None,
+        unit: None,
+        timestamp_utc: 1640995200000001,
+        message: String::new(),
+        matches: Vec::new(),
+        monotonic_usec: None,
+        boot_id: None,
+        fields: std::collections::BTreeMap::new(),
+        cursor: None,
+        is_context: false,
+    };
+    let datetime = entry.datetime().expect("The timestamp is a valid date");
+    assert_eq!(datetime.timestamp(), 1640995200);
+    assert_eq!(datetime.timestamp_subsec_micros(), 1);
+    
+    // Timestamps past what chrono represents do not wrap to dates before 1970
+    for timestamp_utc in [i64::MAX as u64 + 1, u64::MAX] {
+        let entry = journald_query::Entry { timestamp_utc, ..entry.clone() };
+        assert_eq!(entry.datetime(), None);
+    }
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_entry_time_timestamp() {
+    let entry = journald_query::Entry {
+        hostname: None,
+        unit: None,
+        timestamp_utc: 1640995200000001,
+        message: String::new(),
+        matches: Vec::new(),
+        monotonic_usec: None,
+        boot_id: None,
+        fields: std::collections::BTreeMap::new(),
+        cursor: None,
+        is_context: false,
+    };
+    let datetime = entry.offset_datetime().expect("The timestamp is a valid date");
+    assert_eq!(datetime.unix_timestamp(), 1640995200);
+    assert_eq!(datetime.microsecond(), 1);
+    
+    for timestamp_utc in [i64::MAX as u64 + 1, u64::MAX] {
+        let entry = journald_query::Entry { timestamp_utc, ..entry.clone() };
+        assert_eq!(entry.offset_datetime(), None);
+    }
+}
+
+#[test]
+fn test_list_boots_multi_host_unit_file() {
+    use journald_query::{list_boots, BootSelector};
+    
+    let file = get_test_file_path("multi_host_multi_unit.journal");
+    let journal = match Journal::open_files(vec![&file]) {
+        Ok(journal) => journal,
+        Err(e) => {
+            eprintln!("Could not open {:?}, skipping boot listing test: {}", file, e);
+            return;
+        }
+    };
+    
+    let boots = list_boots(&journal).expect("Should be able to list boots");
+    println!("Found boots: {:?}", boots);
+    
+    // Boots are ordered oldest first and numbered relative to the latest
+    for (index, boot) in boots.iter().enumerate() {
+        assert_eq!(boot.offset, index as i64 - (boots.len() as i64 - 1));
+        assert!(boot.first_realtime_usec <= boot.last_realtime_usec);
+        assert_eq!(boot.boot_id.len(), 32, "Boot IDs should be 128-bit hex strings");
+    }
+    for window in boots.windows(2) {
+        assert!(window[0].first_realtime_usec <= window[1].first_realtime_usec);
+    }
+    
+    if let Some(latest) = boots.last() {
+        assert_eq!(BootSelector::Offset(0).resolve(&boots), Some(latest));
+        assert_eq!(BootSelector::Id(latest.boot_id.clone()).resolve(&boots), Some(latest));
+    }
+}
+
+#[test]
+fn test_query_journal_by_boot() {
+    use journald_query::{BootSelector, JournalError, Query, query_journal};
+    use std::path::PathBuf;
+    
+    let query = Query::all().boot(BootSelector::Offset(-1));
+    assert_eq!(query.boot, Some(BootSelector::Offset(-1)));
+    
+    let stress_test_file = PathBuf::from("test_journald_files/stress_test.journal");
+    if !stress_test_file.exists() {
+        eprintln!("Stress test file not found, skipping boot query tests: {:?}", stress_test_file);
+        return;
+    }
+    let test_dir = stress_test_file.parent().unwrap();
+    
+    // A boot far beyond what the journal holds is reported as not found
+    let missing = query_journal(test_dir, Query::all().boot(BootSelector::Offset(-1000)));
+    assert!(matches!(missing, Err(JournalError::NotFound)));
+    
+    // An explicit boot ID is pushed down as a match, so unknown IDs just return nothing
+    let unknown = query_journal(test_dir, Query::all()
+        .boot(BootSelector::Id("00000000000000000000000000000000".to_string())))
+        .expect("Should be able to query an unknown boot ID");
+    assert!(unknown.is_empty());
+}
+
+#[test]
+fn test_monotonic_timestamps() {
+    let file = get_test_file_path("multi_host_multi_unit.journal");
+    let journal = match Journal::open_files(vec![&file]) {
+        Ok(journal) => journal,
+        Err(e) => {
+            eprintln!("Could not open {:?}, skipping monotonic test: {}", file, e);
+            return;
+        }
+    };
+    
+    journal.seek_head().expect("Should be able to seek to head");
+    if journal.next().expect("Should be able to get first entry") {
+        let (monotonic, boot_id) = journal.get_monotonic_usec()
+            .expect("Should be able to get monotonic timestamp");
+        assert_eq!(boot_id.len(), 32);
+        
+        // Seeking back to the same monotonic position lands on an entry of that boot
+        journal.seek_monotonic_usec(&boot_id, monotonic)
+            .expect("Should be able to seek by monotonic timestamp");
+        if journal.next().expect("Should be able to get entry after seeking") {
+            let (after, after_boot) = journal.get_monotonic_usec().unwrap();
+            assert_eq!(after_boot, boot_id);
+            assert!(after >= monotonic);
+        }
+    }
+    
+    // Boot IDs must be 128-bit hex
+    assert!(journal.seek_monotonic_usec("not-a-boot-id", 0).is_err());
+}
+
+#[test]
+fn test_query_journal_boot_window() {
+    use journald_query::{BootSelector, Query, query_journal};
+    use std::path::PathBuf;
+    use std::time::Duration;
+    
+    let query = Query::all().boot_window(Duration::from_secs(1), Duration::from_secs(30));
+    assert_eq!(query.boot_window, Some((1_000_000, 30_000_000)));
+    assert!(query.boot.is_none(), "The boot is only resolved at query time");
+    
+    let stress_test_file = PathBuf::from("test_journald_files/stress_test.journal");
+    if !stress_test_file.exists() {
+        eprintln!("Stress test file not found, skipping boot window tests: {:?}", stress_test_file);
+        return;
+    }
+    let test_dir = stress_test_file.parent().unwrap();
+    
+    match query_journal(test_dir, query.boot(BootSelector::Offset(0))) {
+        Ok(entries) => {
+            for entry in &entries {
+                let monotonic = entry.monotonic_usec.expect("Entries should carry monotonic time");
+                assert!((1_000_000..=30_000_000).contains(&monotonic));
+            }
+            for window in entries.windows(2) {
+                assert_eq!(window[0].boot_id, window[1].boot_id, "All entries should come from one boot");
+            }
+        }
+        Err(e) => println!("Boot window query failed (expected if the journal has no boots): {}", e),
+    }
+}
+
+#[test]
+fn test_query_journal_projection() {
+    use journald_query::{Projection, Query, query_journal};
+    use std::path::PathBuf;
+    
+    let query = Query::all();
+    assert_eq!(query.projection, Projection::Default);
+    assert!(query.data_threshold.is_none());
+    
+    let query = Query::all().projection(Projection::TimestampsOnly).data_threshold(1024);
+    assert_eq!(query.projection, Projection::TimestampsOnly);
+    assert_eq!(query.data_threshold, Some(1024));
+    
+    let stress_test_file = PathBuf::from("test_journald_files/stress_test.journal");
+    if !stress_test_file.exists() {
+        eprintln!("Stress test file not found, skipping projection tests: {:?}", stress_test_file);
+        return;
+    }
+    let test_dir = stress_test_file.parent().unwrap();
+    
+    // Timestamps only: no fields are materialized, but filters still apply
+    let entries = query_journal(test_dir, Query::all()
+        .projection(Projection::TimestampsOnly)
+        .message_contains("HTTP"))
+        .expect("Should be able to run a timestamps-only query");
+    for entry in &entries {
+        assert!(entry.hostname.is_none());
+        assert!(entry.unit.is_none());
+        assert!(entry.message.is_empty());
+        assert!(entry.fields.is_empty());
+        assert!(entry.timestamp_utc > 0);
+    }
+    
+    // Selected fields: dedicated slots are filled, others go into `fields`
+    let entries = query_journal(test_dir, Query::all()
+        .projection(Projection::Fields(vec!["MESSAGE".to_string(), "PRIORITY".to_string()])))
+        .expect("Should be able to run a field projection query");
+    for entry in &entries {
+        assert!(entry.hostname.is_none(), "_HOSTNAME was not projected");
+        assert!(entry.fields.keys().all(|name| name == "PRIORITY"));
+    }
+    
+    // All fields: everything the entry carries
+    let entries = query_journal(test_dir, Query::all()
+        .projection(Projection::All)
+        .data_threshold(16))
+        .expect("Should be able to run an all-fields query");
+    for entry in &entries {
+        assert!(entry.hostname.is_some());
+        assert!(entry.fields.contains_key("PRIORITY"));
+        assert!(!entry.fields.contains_key("MESSAGE"), "MESSAGE has its own slot");
+    }
+}
+
+
+#[test]
+fn test_query_journal_window_modes() {
+    use journald_query::{Query, TimestampSource, WindowMode, query_journal};
+    use std::path::PathBuf;
+    use std::time::Duration;
+    
+    let query = Query::all();
+    assert_eq!(query.window_mode, WindowMode::Strict);
+    assert_eq!(query.timestamp_source, TimestampSource::Receive);
+    
+    let query = Query::all()
+        .window_mode(WindowMode::Tolerant(Duration::from_secs(60)))
+        .timestamp_source(TimestampSource::Source);
+    assert_eq!(query.window_mode, WindowMode::Tolerant(Duration::from_secs(60)));
+    assert_eq!(query.timestamp_source, TimestampSource::Source);
+    
+    let multi_host_file = PathBuf::from("test_journald_files/multi_host_multi_unit.journal");
+    if !multi_host_file.exists() {
+        eprintln!("Multi-host file not found, skipping window mode tests: {:?}", multi_host_file);
+        return;
+    }
+    let test_dir = multi_host_file.parent().unwrap();
+    
+    let start = 1640995200000000; // 2022-01-01 00:00:00 UTC
+    let end = start + 5_000_000;
+    
+    let strict = query_journal(test_dir, Query::new(start, end))
+        .expect("Should be able to run a strict query");
+    let tolerant = query_journal(test_dir, Query::new(start, end)
+        .window_mode(WindowMode::Tolerant(Duration::from_secs(3600))))
+        .expect("Should be able to run a tolerant query");
+    
+    // Tolerant scanning never loses entries, and never leaves the range
+    assert!(tolerant.len() >= strict.len());
+    for entry in &strict {
+        assert!(tolerant.contains(entry), "Tolerant results should include {:?}", entry);
+    }
+    for entry in &tolerant {
+        assert!(entry.timestamp_utc >= start && entry.timestamp_utc <= end);
+    }
+    assert!(tolerant.windows(2).all(|pair| pair[0].timestamp_utc <= pair[1].timestamp_utc));
+    
+    // Source timestamps fall back to the receive time when missing
+    let by_source = query_journal(test_dir, Query::new(start, end)
+        .window_mode(WindowMode::Tolerant(Duration::from_secs(3600)))
+        .timestamp_source(TimestampSource::Source))
+        .expect("Should be able to run a source timestamp query");
+    for entry in &by_source {
+        assert!(entry.timestamp_utc >= start && entry.timestamp_utc <= end);
+    }
+    assert!(by_source.windows(2).all(|pair| pair[0].timestamp_utc <= pair[1].timestamp_utc));
+}
+
+#[test]
+fn test_aggregate_counts_and_histograms() {
+    use journald_query::{aggregate, Aggregation, Query, query_journal};
+    use std::path::PathBuf;
+    use std::time::Duration;
+    
+    let multi_host_file = PathBuf::from("test_journald_files/multi_host_multi_unit.journal");
+    if !multi_host_file.exists() {
+        eprintln!("Multi-host file not found, skipping aggregation tests: {:?}", multi_host_file);
+        return;
+    }
+    let test_dir = multi_host_file.parent().unwrap();
+    
+    let entries = query_journal(test_dir, Query::all())
+        .expect("Should be able to query all entries");
+    
+    // A plain count matches the number of entries
+    let total = aggregate(test_dir, Query::all(), &Aggregation::new())
+        .expect("Should be able to count entries");
+    let counted: u64 = total.iter().map(|row| row.count).sum();
+    assert_eq!(counted, entries.len() as u64);
+    for row in &total {
+        assert!(row.group.is_empty());
+        assert!(row.bucket_start_utc.is_none());
+    }
+    
+    // Grouped counts agree with the entries' units
+    let by_unit = aggregate(test_dir, Query::all(), &Aggregation::new().group_by("_SYSTEMD_UNIT"))
+        .expect("Should be able to count entries by unit");
+    for row in &by_unit {
+        assert_eq!(row.group.len(), 1);
+        let expected = entries.iter().filter(|entry| entry.unit == row.group[0]).count();
+        assert_eq!(row.count, expected as u64, "Count mismatch for {:?}", row.group);
+    }
+    
+    // Histogram buckets are aligned to the bucket width
+    let minute = 60_000_000;
+    let histogram = aggregate(test_dir, Query::all(), &Aggregation::new()
+        .group_by("_HOSTNAME")
+        .bucket(Duration::from_secs(60)))
+        .expect("Should be able to build a histogram");
+    let counted: u64 = histogram.iter().map(|row| row.count).sum();
+    assert_eq!(counted, entries.len() as u64);
+    for row in &histogram {
+        let start = row.bucket_start_utc.expect("Histogram rows have a bucket");
+        assert_eq!(start % minute, 0);
+    }
+    
+    // Text filters apply without building entries
+    let filtered = aggregate(test_dir, Query::all().message_contains("nginx"), &Aggregation::new())
+        .expect("Should be able to count filtered entries");
+    let expected = entries.iter().filter(|entry| entry.message.contains("nginx")).count();
+    assert_eq!(filtered.iter().map(|row| row.count).sum::<u64>(), expected as u64);
+}
+
+#[test]
+fn test_query_journal_journalctl_expressions() {
+    use journald_query::{parse_journalctl, query_journal, JournalError, Query};
+    use std::path::PathBuf;
+    
+    assert!(matches!(parse_journalctl("--follow"), Err(JournalError::InvalidExpression(_))));
+    
+    let multi_host_file = PathBuf::from("test_journald_files/multi_host_multi_unit.journal");
+    if !multi_host_file.exists() {
+        eprintln!("Multi-host file not found, skipping journalctl expression tests: {:?}", multi_host_file);
+        return;
+    }
+    let test_dir = multi_host_file.parent().unwrap();
+    
+    let all = query_journal(test_dir, Query::all()).expect("Should be able to query all entries");
+    
+    // Alternatives separated by `+`
+    let query = parse_journalctl("_SYSTEMD_UNIT=nginx.service + _HOSTNAME=database-server")
+        .expect("Should parse field matches");
+    let entries = query_journal(test_dir, query).expect("Should run a parsed query");
+    let expected: Vec<_> = all.iter()
+        .filter(|entry| entry.unit.as_deref() == Some("nginx.service")
+            || entry.hostname.as_deref() == Some("database-server"))
+        .collect();
+    assert_eq!(entries.len(), expected.len());
+    
+    // Matches on the same field are OR'ed, different fields AND'ed
+    let query = parse_journalctl("_HOSTNAME=web-server _HOSTNAME=database-server _SYSTEMD_UNIT=nginx.service")
+        .expect("Should parse field matches");
+    let entries = query_journal(test_dir, query).expect("Should run a parsed query");
+    for entry in &entries {
+        assert_eq!(entry.unit.as_deref(), Some("nginx.service"));
+    }
+    
+    // Options combine with the matches
+    let query = parse_journalctl("-u nginx.service -p info -n 1")
+        .expect("Should parse options");
+    let entries = query_journal(test_dir, query).expect("Should run a parsed query");
+    let nginx: Vec<_> = all.iter()
+        .filter(|entry| entry.unit.as_deref() == Some("nginx.service"))
+        .collect();
+    assert_eq!(entries.len(), nginx.len().min(1));
+    if let (Some(entry), Some(last)) = (entries.first(), nginx.last()) {
+        assert_eq!(entry, *last, "-n keeps the most recent entries");
+    }
+    
+    // Reverse returns newest first
+    let entries = query_journal(test_dir, parse_journalctl("-r").unwrap())
+        .expect("Should run a reversed query");
+    assert_eq!(entries.len(), all.len());
+    for entry in &entries {
+        assert!(all.contains(entry));
+    }
+    let forward = query_journal(test_dir, Query::all().hostname("web-server"))
+        .expect("Should run a host query");
+    let reversed = query_journal(test_dir, parse_journalctl("-r _HOSTNAME=web-server").unwrap())
+        .expect("Should run a reversed host query");
+    assert!(reversed.iter().eq(forward.iter().rev()));
+    
+    // Priorities outside the range are excluded
+    let entries = query_journal(test_dir, parse_journalctl("-p err").unwrap())
+        .expect("Should run a priority query");
+    assert!(entries.len() <= all.len());
+}
+
+#[test]
+fn test_query_journal_unit_match_modes() {
+    use journald_query::{parse_journalctl, query_journal, Query, UnitMatch};
+    use std::path::PathBuf;
+    
+    assert_eq!(Query::all().unit_match, UnitMatch::Exact);
+    
+    let errors_file = PathBuf::from("test_journald_files/error_scenarios.journal");
+    if !errors_file.exists() {
+        eprintln!("Error scenarios file not found, skipping unit match tests: {:?}", errors_file);
+        return;
+    }
+    let test_dir = errors_file.parent().unwrap();
+    
+    // Exact: only what the unit logged itself
+    let exact = query_journal(test_dir, Query::all().unit("failing.service"))
+        .expect("Should be able to query a unit");
+    for entry in &exact {
+        assert_eq!(entry.unit.as_deref(), Some("failing.service"));
+    }
+    
+    // Journalctl: also systemd's own messages about the unit
+    let expanded = query_journal(test_dir, Query::all()
+        .unit("failing.service")
+        .unit_match(UnitMatch::Journalctl))
+        .expect("Should be able to query a unit with journalctl semantics");
+    assert!(expanded.len() >= exact.len());
+    for entry in &exact {
+        assert!(expanded.contains(entry));
+    }
+    for entry in expanded.iter().filter(|entry| entry.unit.as_deref() != Some("failing.service")) {
+        assert_eq!(entry.unit.as_deref(), Some("init.scope"), "Only PID 1 adds messages here");
+        assert!(entry.message.contains("failing.service"));
+    }
+    if !exact.is_empty() {
+        assert!(
+            expanded.iter().any(|entry| entry.message == "Failed to start failing.service."),
+            "The failure is reported by systemd, not by the unit"
+        );
+    }
+    
+    // The parser uses journalctl semantics for -u
+    let parsed = query_journal(test_dir, parse_journalctl("-u failing.service").unwrap())
+        .expect("Should be able to run a parsed unit query");
+    assert_eq!(parsed, expanded);
+    
+    // User units: the user manager's messages carry USER_UNIT
+    let user_exact = query_journal(test_dir, Query::all().user_unit("backup.service"))
+        .expect("Should be able to query a user unit");
+    let user_expanded = query_journal(test_dir, parse_journalctl("--user-unit backup.service").unwrap())
+        .expect("Should be able to query a user unit with journalctl semantics");
+    assert!(user_expanded.len() >= user_exact.len());
+    for entry in &user_expanded {
+        assert_eq!(entry.hostname.as_deref(), Some("memory-constrained-server"));
+    }
+}
+
+#[test]
+fn test_query_journal_context() {
+    use journald_query::{query_journal, Context, Query};
+    use std::time::Duration;
+    
+    assert_eq!(Query::all().context(2, 1).context, Some(Context::Entries { before: 2, after: 1 }));
+    assert_eq!(
+        Query::all().surrounding(Duration::from_secs(5)).context,
+        Some(Context::Surrounding(Duration::from_secs(5)))
+    );
+    
+    let Some(journal) = BinaryJournal::build("error_scenarios.journal") else {
+        eprintln!("systemd-journal-remote not found, skipping context tests");
+        return;
+    };
+    let test_dir = journal.path();
+    
+    // Entries before each match, among the entries the other filters select
+    let entries = query_journal(test_dir, Query::all()
+        .hostname("error-prone-server")
+        .message_contains("Failed to start")
+        .context(2, 0))
+        .expect("Should be able to query with context");
+    let matches: Vec<_> = entries.iter().filter(|entry| !entry.is_context).collect();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].message, "Failed to start failing.service.");
+    assert!(matches[0].cursor.is_some());
+    let messages: Vec<_> = entries.iter().map(|entry| (entry.is_context, entry.message.as_str())).collect();
+    assert_eq!(messages, vec![
+        (true, "failing.service: Main process exited, code=exited, status=1/FAILURE"),
+        (true, "failing.service: Failed with result 'exit-code'."),
+        (false, "Failed to start failing.service."),
+    ]);
+    for context in &entries[..2] {
+        assert_eq!(context.hostname.as_deref(), Some("error-prone-server"));
+        assert!(context.timestamp_utc <= matches[0].timestamp_utc);
+    }
+    
+    // Without context, only the matches come back
+    let plain = query_journal(test_dir, Query::all()
+        .hostname("error-prone-server")
+        .message_contains("Failed to start"))
+        .expect("Should be able to query without context");
+    assert_eq!(plain.len(), 1);
+    assert!(plain.iter().all(|entry| !entry.is_context));
+    
+    // Surrounding window: the same host, any unit
+    let window = Duration::from_secs(10);
+    let entries = query_journal(test_dir, Query::all()
+        .message_contains("Critical system failure")
+        .surrounding(window))
+        .expect("Should be able to query a surrounding window");
+    let hits: Vec<_> = entries.iter().filter(|entry| !entry.is_context).collect();
+    assert_eq!(hits.len(), 1);
+    let hit = hits[0];
+    assert_eq!(hit.unit.as_deref(), Some("failing.service"));
+    assert_eq!(entries.len(), 5);
+    for entry in &entries {
+        assert_eq!(entry.hostname, hit.hostname, "Context stays on the same host");
+        assert!(entry.timestamp_utc.abs_diff(hit.timestamp_utc) <= window.as_micros() as u64);
+    }
+    let units: Vec<_> = entries.iter().filter(|entry| entry.is_context).filter_map(|entry| entry.unit.as_deref()).collect();
+    assert_eq!(units, vec!["broken.service", "init.scope", "init.scope", "init.scope"], "Surrounding context spans units");
+}
+
+#[test]
+fn test_multi_journal_merges_directories() {
+    use journald_query::{aggregate, discover_services, query_journal, Aggregation, MultiJournal, Query};
+    use std::fs;
+    
+    let errors_file = PathBuf::from("test_journald_files/error_scenarios.journal");
+    if !errors_file.exists() {
+        eprintln!("Error scenarios file not found, skipping multi-journal tests: {:?}", errors_file);
+        return;
+    }
+    
+    // The same journal stored locally and in a journal-remote style tree
+    let root = std::env::temp_dir().join(format!("journald-query-multi-{}", std::process::id()));
+    let local = root.join("local");
+    let remote = root.join("remote");
+    fs::create_dir_all(&local).unwrap();
+    fs::create_dir_all(remote.join("error-prone-server")).unwrap();
+    fs::copy(&errors_file, local.join("system.journal")).unwrap();
+    fs::copy(&errors_file, remote.join("error-prone-server/remote-error-prone-server.journal")).unwrap();
+    fs::write(remote.join("README"), "not a journal").unwrap();
+    
+    let journals = MultiJournal::new([&local, &remote])
+        .add_directory(root.join("missing"));
+    assert_eq!(journals.directories().len(), 3);
+    assert_eq!(journals.journal_files().unwrap().len(), 2, "Journal files are found recursively");
+    
+    let single = query_journal(&local, Query::all()).expect("Should be able to query one directory");
+    let merged = query_journal(&journals, Query::all()).expect("Should be able to query merged directories");
+    
+    // Copies of an entry are returned once, in time order
+    assert_eq!(merged.len(), single.len());
+    assert!(merged.windows(2).all(|pair| pair[0].timestamp_utc <= pair[1].timestamp_utc));
+    
+    let total: u64 = aggregate(&journals, Query::all(), &Aggregation::new())
+        .expect("Should be able to aggregate merged directories")
+        .iter()
+        .map(|row| row.count)
+        .sum();
+    assert_eq!(total, single.len() as u64);
+    
+    assert_eq!(
+        discover_services(&journals).expect("Should be able to discover merged directories"),
+        discover_services(&local).expect("Should be able to discover one directory")
+    );
+    
+    // A journal with no existing directories is not found
+    let missing = MultiJournal::new([root.join("missing")]);
+    assert!(matches!(query_journal(&missing, Query::all()), Err(journald_query::JournalError::NotFound)));
+    
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_multi_journal_discovery_stats() {
+    use journald_query::{
+        discover_services_with_options, DiscoveryCheckpoint, DiscoveryOptions, DiscoveryStrategy, MultiJournal,
+        ScanControl,
+    };
+    use std::fs;
+    
+    let Some(journal) = BinaryJournal::build("error_scenarios.journal") else {
+        eprintln!("systemd-journal-remote not found, skipping multi-journal discovery tests");
+        return;
+    };
+    
+    // The same journal in two directories
+    let root = std::env::temp_dir().join(format!("journald-query-multi-stats-{}", std::process::id()));
+    let local = root.join("local");
+    let remote = root.join("remote");
+    fs::create_dir_all(&local).unwrap();
+    fs::create_dir_all(&remote).unwrap();
+    fs::copy(journal.path().join("test.journal"), local.join("system.journal")).unwrap();
+    fs::copy(journal.path().join("test.journal"), remote.join("remote-error-prone-server.journal")).unwrap();
+    let journals = MultiJournal::new([&local, &remote]);
+    
+    // Copies are counted once in the statistics
+    let options = DiscoveryOptions::new().unit_stats(true);
+    let single = discover_services_with_options(&local, &options, &mut ScanControl::new()).unwrap();
+    let merged = discover_services_with_options(&journals, &options, &mut ScanControl::new()).unwrap();
+    let failing = merged.find_host("error-prone-server").and_then(|host| host.stats("failing.service"));
+    assert!(failing.is_some_and(|stats| stats.entries > 0));
+    assert_eq!(merged, single);
+    
+    let scan = DiscoveryOptions::new().strategy(DiscoveryStrategy::Scan);
+    assert_eq!(
+        discover_services_with_options(&journals, &scan, &mut ScanControl::new()).unwrap(),
+        discover_services_with_options(&local, &scan, &mut ScanControl::new()).unwrap()
+    );
+    
+    let mut local_checkpoint = DiscoveryCheckpoint::open(root.join("local.checkpoint")).unwrap();
+    let local_read = local_checkpoint.update(&local, &options);
+    let mut checkpoint = DiscoveryCheckpoint::open(root.join("merged.checkpoint")).unwrap();
+    let read = checkpoint.update(&journals, &options);
+    let hosts = checkpoint.hosts().clone();
+    fs::remove_dir_all(&root).unwrap();
+    assert_eq!(read.unwrap(), local_read.unwrap());
+    assert_eq!(hosts, single);
+}
+
+#[test]
+fn test_query_journal_parallel() {
+    use journald_query::{query_journal, query_journal_parallel, JournalError, Query, WindowMode};
+    use std::time::Duration;
+    
+    let test_dir = Path::new(TEST_JOURNAL_DIR);
+    if !test_dir.exists() {
+        eprintln!("Test journal directory not found, skipping parallel query tests: {:?}", test_dir);
+        return;
+    }
+    
+    let key = |entries: &[journald_query::Entry]| {
+        let mut keys: Vec<_> = entries.iter().map(|entry| (entry.timestamp_utc, entry.message.clone())).collect();
+        keys.sort();
+        keys
+    };
+    let timestamps = |entries: &[journald_query::Entry]| {
+        entries.iter().map(|entry| entry.timestamp_utc).collect::<Vec<_>>()
+    };
+    
+    let serial = query_journal(test_dir, Query::all()).expect("Should be able to query serially");
+    let tolerant = Query::all().window_mode(WindowMode::Tolerant(Duration::from_secs(3600)));
+    let serial_sorted = query_journal(test_dir, tolerant.clone()).expect("Should be able to query serially");
+    let serial_latest = query_journal(test_dir, tolerant.clone().limit(5)).expect("Should be able to query serially");
+    
+    for workers in [1, 2, 3, 8] {
+        // The same entries as a serial query
+        let parallel = query_journal_parallel(test_dir, Query::all(), workers)
+            .expect("Should be able to query in parallel");
+        assert_eq!(key(&parallel), key(&serial), "Same entries with {} workers", workers);
+        
+        // In the same order when the query sorts by timestamp
+        let sorted = query_journal_parallel(test_dir, tolerant.clone(), workers)
+            .expect("Should be able to query in parallel");
+        assert_eq!(timestamps(&sorted), timestamps(&serial_sorted));
+        
+        let latest = query_journal_parallel(test_dir, tolerant.clone().limit(5), workers)
+            .expect("Should be able to query in parallel");
+        assert_eq!(timestamps(&latest), timestamps(&serial_latest));
+        
+        // Files outside the time range are skipped
+        let none = query_journal_parallel(test_dir, Query::new(0, 1_000_000), workers)
+            .expect("Should be able to query in parallel");
+        assert!(none.is_empty());
+    }
+    
+    assert_eq!(query_journal_parallel(test_dir, Query::all(), 0), Err(JournalError::InvalidArgument));
+    assert!(query_journal_parallel(Path::new("/nonexistent/journal"), Query::all(), 2).is_err());
+}
+
+#[test]
+fn test_query_cancellation_deadline_and_progress() {
+    use journald_query::{
+        discover_services_with_control, query_journal, query_journal_with_control,
+        CancellationToken, JournalError, Query, ScanControl,
+    };
+    use std::time::{Duration, Instant};
+    
+    let test_dir = Path::new(TEST_JOURNAL_DIR);
+    if !test_dir.exists() {
+        eprintln!("Test journal directory not found, skipping cancellation tests: {:?}", test_dir);
+        return;
+    }
+    
+    // A cancelled token stops queries and discovery
+    let token = CancellationToken::new();
+    token.cancel();
+    let mut control = ScanControl::new().cancellation(token.clone());
+    assert_eq!(query_journal_with_control(test_dir, Query::all(), &mut control), Err(JournalError::Cancelled));
+    assert_eq!(discover_services_with_control(test_dir, &mut control), Err(JournalError::Cancelled));
+    
+    // So does a deadline that has passed
+    let mut control = ScanControl::new().deadline(Instant::now());
+    assert_eq!(query_journal_with_control(test_dir, Query::all(), &mut control), Err(JournalError::DeadlineExceeded));
+    assert_eq!(discover_services_with_control(test_dir, &mut control), Err(JournalError::DeadlineExceeded));
+    
+    // Progress is reported while scanning and once at the end
+    let expected = query_journal(test_dir, Query::all()).expect("Should be able to query");
+    let mut reports = Vec::new();
+    let mut control = ScanControl::new()
+        .cancellation(CancellationToken::new())
+        .timeout(Duration::from_secs(3600))
+        .progress_interval(2)
+        .on_progress(|progress| reports.push(*progress));
+    let entries = query_journal_with_control(test_dir, Query::all(), &mut control)
+        .expect("Should be able to query with a live token and deadline");
+    drop(control);
+    assert_eq!(entries, expected);
+    
+    let last = reports.last().expect("Completion is always reported");
+    assert_eq!(last.fraction_done, Some(1.0));
+    assert!(last.entries_scanned >= entries.len() as u64);
+    assert_eq!(reports.len() as u64, last.entries_scanned / 2 + 1);
+    for pair in reports.windows(2) {
+        assert!(pair[0].entries_scanned <= pair[1].entries_scanned);
+        assert!(pair[0].fraction_done.is_none_or(|fraction| (0.0..=1.0).contains(&fraction)));
+    }
+}
+
+#[test]
+fn test_query_cache() {
+    use journald_query::{query_journal, Aggregation, Query, QueryCache};
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+    
+    let errors_file = PathBuf::from("test_journald_files/error_scenarios.journal");
+    if !errors_file.exists() {
+        eprintln!("Error scenarios file not found, skipping cache tests: {:?}", errors_file);
+        return;
+    }
+    let dir = std::env::temp_dir().join(format!("journald-query-cache-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let journal_file = dir.join("system.journal");
+    fs::copy(&errors_file, &journal_file).unwrap();
+    let touch = |seconds: u64| {
+        fs::File::options().append(true).open(&journal_file).unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)).unwrap();
+    };
+    
+    let cache = QueryCache::new(10);
+    
+    // Identical queries are answered from the cache
+    let expected = query_journal(&dir, Query::all()).expect("Should be able to query");
+    assert_eq!(cache.query_journal(&dir, Query::all()).unwrap(), expected);
+    assert_eq!(cache.query_journal(&dir, Query::all()).unwrap(), expected);
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.len), (1, 1, 1));
+    
+    // Aggregations are cached separately from entries
+    let aggregation = Aggregation::new().group_by("_HOSTNAME");
+    let counts = cache.aggregate(&dir, Query::all(), &aggregation).unwrap();
+    assert_eq!(cache.aggregate(&dir, Query::all(), &aggregation).unwrap(), counts);
+    assert_eq!(cache.stats().hits, 2);
+    assert_eq!(cache.stats().len, 2);
+    
+    // A changed file invalidates results whose range is still open
+    touch(1_000_000);
+    assert_eq!(cache.query_journal(&dir, Query::all()).unwrap(), expected);
+    assert_eq!(cache.stats().misses, 3);
+    
+    // A range that ended before the newest entry survives the file changing
+    let closed = Query::new(1640995400000000, 1640995402000000);
+    cache.query_journal(&dir, closed.clone()).unwrap();
+    touch(2_000_000);
+    cache.query_journal(&dir, closed).unwrap();
+    let closed_hit = cache.stats().hits == 3;
+    assert_eq!(closed_hit, !expected.is_empty(), "Closed ranges are reused when the journal has entries");
+    
+    cache.clear();
+    assert_eq!(cache.stats().len, 0);
+    
+    // A cache with no capacity never holds results
+    let disabled = QueryCache::new(0);
+    disabled.query_journal(&dir, Query::all()).unwrap();
+    disabled.query_journal(&dir, Query::all()).unwrap();
+    assert_eq!(disabled.stats(), Default::default());
+    
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_journal_index() {
+    use journald_query::{aggregate, query_journal, query_journal_parallel, Aggregation, JournalIndex, Query, QueryCache};
+    use std::time::Duration;
+    
+    let test_dir = Path::new(TEST_JOURNAL_DIR);
+    if !test_dir.exists() {
+        eprintln!("Test journal directory not found, skipping index tests: {:?}", test_dir);
+        return;
+    }
+    let index_path = std::env::temp_dir().join(format!("journald-query-index-{}", std::process::id()));
+    
+    let mut index = JournalIndex::open(&index_path).expect("Should be able to start an index");
+    let indexed = index.update(test_dir).expect("Should be able to index the test journals");
+    assert_eq!(index.indexed_files().count(), indexed);
+    index.save().expect("Should be able to save the index");
+    
+    // Unchanged files are not read again, and the saved index loads back
+    assert_eq!(index.update(test_dir).unwrap(), 0);
+    let reopened = JournalIndex::open(&index_path).expect("Should be able to load the index");
+    assert_eq!(reopened, index);
+    
+    // Queries return the same entries with and without the index, whether
+    // it is used directly or through an indexed source
+    let indexed_source = index.source(test_dir);
+    let cache = QueryCache::new(8);
+    for query in [
+        Query::all().message_contains("Failed to start"),
+        Query::all().message_contains("no such message anywhere"),
+        Query::all().hostname("web-server"),
+        Query::new(1640995400000000, 1640995402000000),
+    ] {
+        let expected = query_journal(test_dir, query.clone()).expect("Should be able to query");
+        let indexed = index.query_journal(test_dir, query.clone()).expect("Should be able to query with the index");
+        assert_eq!(indexed, expected);
+        assert_eq!(query_journal(&indexed_source, query.clone()).unwrap(), expected);
+        assert_eq!(query_journal_parallel(&indexed_source, query.clone(), 2).unwrap(), expected);
+        assert_eq!(cache.query_journal(&indexed_source, query).unwrap(), expected);
+    }
+    
+    // Aggregations the index can answer give the same counts
+    let aggregations = [
+        Aggregation::new(),
+        Aggregation::new().group_by("_HOSTNAME").group_by("PRIORITY"),
+        Aggregation::new().group_by("_SYSTEMD_UNIT").bucket(Duration::from_secs(300)),
+    ];
+    for aggregation in &aggregations {
+        for query in [Query::all(), Query::all().hostname("web-server"), Query::all().priority(3)] {
+            let expected = aggregate(test_dir, query.clone(), aggregation).expect("Should be able to aggregate");
+            let indexed = index.aggregate(test_dir, query.clone(), aggregation).expect("Should be able to aggregate with the index");
+            assert_eq!(indexed, expected);
+            assert_eq!(aggregate(&indexed_source, query, aggregation).unwrap(), expected);
+        }
+    }
+    
+    // Others fall back to reading the journal
+    let by_identifier = Aggregation::new().group_by("SYSLOG_IDENTIFIER");
+    assert_eq!(
+        index.aggregate(test_dir, Query::all(), &by_identifier).unwrap(),
+        aggregate(test_dir, Query::all(), &by_identifier).unwrap()
+    );
+    
+    // A different bucket width starts over
+    let mut index = reopened.bucket_width(Duration::from_secs(300));
+    assert_eq!(index.indexed_files().count(), 0);
+    assert_eq!(index.update(test_dir).unwrap(), indexed);
+    
+    std::fs::remove_file(&index_path).unwrap();
+}
+
+#[test]
+fn test_discovery_strategies_agree() {
+    use journald_query::{discover_services, discover_services_with_options, DiscoveryOptions, DiscoveryStrategy, ScanControl};
+    
+    // Hostname times come from seeks when probing, which can differ in
+    // journals written out of time order
+    let identities = |hosts: &journald_query::Hosts| -> Vec<_> {
+        hosts.hosts.iter().map(|host| {
+            let names: Vec<_> = host.hostnames.iter().map(|seen| seen.hostname.clone()).collect();
+            (host.hostname.clone(), host.machine_id.clone(), names, host.units.clone())
+        }).collect()
+    };
+    let check_strategies = |test_dir: &Path, expected: &journald_query::Hosts| {
+        for strategy in [
+            DiscoveryStrategy::Auto,
+            DiscoveryStrategy::Probe,
+            DiscoveryStrategy::UniqueValues,
+            DiscoveryStrategy::Scan,
+        ] {
+            let options = DiscoveryOptions::new().strategy(strategy);
+            let mut progress = Vec::new();
+            let mut control = ScanControl::new().progress_interval(1).on_progress(|p| progress.push(*p));
+            let hosts = discover_services_with_options(test_dir, &options, &mut control)
+                .expect("Should be able to discover services");
+            drop(control);
+            
+            assert_eq!(identities(&hosts), identities(expected), "{:?} finds the same hosts and units", strategy);
+            assert_eq!(progress.last().and_then(|p| p.fraction_done), Some(1.0));
+        }
+    };
+    
+    let test_dir = Path::new(TEST_JOURNAL_DIR);
+    if test_dir.exists() {
+        let expected = discover_services(test_dir).expect("Should be able to discover services");
+        check_strategies(test_dir, &expected);
+    } else {
+        eprintln!("Test journal directory not found, skipping discovery strategy tests: {:?}", test_dir);
+    }
+    
+    // A local machine next to hosts imported without machine IDs
+    let Some(journal) = BinaryJournal::build("mixed_machine_ids.export") else {
+        eprintln!("systemd-journal-remote not found, skipping mixed machine ID tests");
+        return;
+    };
+    let options = DiscoveryOptions::new().strategy(DiscoveryStrategy::Scan);
+    let expected = discover_services_with_options(journal.path(), &options, &mut ScanControl::new())
+        .expect("Should be able to discover services");
+    assert_eq!(expected.hostnames(), vec!["db-server", "web-server", "workstation"]);
+    assert_eq!(expected.find_host("web-server").map(|host| host.machine_id.clone()), Some(None));
+    assert_eq!(expected.find_host("web-server").unwrap().units, vec!["nginx.service"]);
+    assert_eq!(expected.find_machine(&"e".repeat(32)).unwrap().units, vec!["nginx.service", "sshd.service"]);
+    check_strategies(journal.path(), &expected);
+}
+
+#[test]
+fn test_discovery_shared_hostname() {
+    use journald_query::{discover_services_with_options, DiscoveryOptions, DiscoveryStrategy, ScanControl};
+    
+    let Some(journal) = BinaryJournal::build("shared_hostname.export") else {
+        eprintln!("systemd-journal-remote not found, skipping shared hostname tests");
+        return;
+    };
+    let test_dir = journal.path();
+    
+    for strategy in [
+        DiscoveryStrategy::Auto,
+        DiscoveryStrategy::Probe,
+        DiscoveryStrategy::UniqueValues,
+        DiscoveryStrategy::Scan,
+    ] {
+        let options = DiscoveryOptions::new().strategy(strategy);
+        let hosts = discover_services_with_options(test_dir, &options, &mut ScanControl::new())
+            .expect("Should be able to discover services");
+        
+        // Two machines calling themselves localhost keep their own units
+        let machine = |id: char| {
+            let machine_id = id.to_string().repeat(32);
+            hosts.find_machine(&machine_id).unwrap_or_else(|| panic!("{:?} finds machine {}", strategy, id))
+        };
+        assert_eq!(hosts.len(), 2, "{:?}", strategy);
+        assert_eq!(machine('a').hostname, "localhost");
+        assert_eq!(machine('a').units, vec!["nginx.service", "sshd.service"], "{:?}", strategy);
+        assert_eq!(machine('b').hostname, "localhost");
+        assert_eq!(machine('b').units, vec!["postgres.service", "sshd.service"], "{:?}", strategy);
+    }
+}
+
+#[test]
+fn test_discovery_renamed_host() {
+    use journald_query::{discover_services_with_options, DiscoveryOptions, DiscoveryStrategy, ScanControl};
+    
+    let Some(journal) = BinaryJournal::build("renamed_host.export") else {
+        eprintln!("systemd-journal-remote not found, skipping renamed host tests");
+        return;
+    };
+    let test_dir = journal.path();
+    
+    for strategy in [
+        DiscoveryStrategy::Auto,
+        DiscoveryStrategy::Probe,
+        DiscoveryStrategy::UniqueValues,
+        DiscoveryStrategy::Scan,
+    ] {
+        let options = DiscoveryOptions::new().strategy(strategy);
+        let hosts = discover_services_with_options(test_dir, &options, &mut ScanControl::new())
+            .expect("Should be able to discover services");
+        assert_eq!(hosts.len(), 2, "{:?}", strategy);
+        
+        // Every name the machine went by, with the times it used them
+        let renamed = hosts.find_machine(&"c".repeat(32)).expect("The renamed machine is found");
+        let names: Vec<_> = renamed
+            .hostnames
+            .iter()
+            .map(|seen| (seen.hostname.as_str(), seen.first_seen_utc, seen.last_seen_utc))
+            .collect();
+        assert_eq!(names, vec![
+            ("old-name", 1641168000000000, 1641168002000000),
+            ("mid-name", 1641168003000000, 1641168003000000),
+            ("new-name", 1641168005000000, 1641168006000000),
+        ], "{:?}", strategy);
+        assert_eq!(renamed.hostname, "new-name");
+        assert_eq!(renamed.units, vec!["app.service", "cron.service"], "{:?}", strategy);
+        
+        // The other machine only gets its own name
+        let db = hosts.find_machine(&"d".repeat(32)).expect("The database machine is found");
+        let names: Vec<_> = db.hostnames.iter().map(|seen| seen.hostname.as_str()).collect();
+        assert_eq!(names, vec!["db-server"], "{:?}", strategy);
+        assert_eq!(db.units, vec!["postgres.service"], "{:?}", strategy);
+    }
+}
+
+#[test]
+fn test_discovery_unit_stats() {
+    use journald_query::{discover_services, discover_services_with_options, query_journal, DiscoveryOptions, Query, ScanControl};
+    
+    let test_dir = Path::new(TEST_JOURNAL_DIR);
+    if !test_dir.exists() {
+        eprintln!("Test journal directory not found, skipping unit stats tests: {:?}", test_dir);
+        return;
+    }
+    
+    let options = DiscoveryOptions::new().unit_stats(true);
+    let hosts = discover_services_with_options(test_dir, &options, &mut ScanControl::new())
+        .expect("Should be able to discover services with unit stats");
+    let expected = discover_services(test_dir).expect("Should be able to discover services");
+    assert_eq!(hosts.hostnames(), expected.hostnames(), "Stats do not change the hosts found");
+    
+    for host in &hosts.hosts {
+        let stats = host.unit_stats.as_ref().expect("Stats were requested");
+        let units: Vec<_> = stats.iter().map(|stats| stats.unit.clone()).collect();
+        assert_eq!(units, host.units, "One stats entry per unit, in the same order");
+        
+        for stats in stats {
+            let entries = query_journal(test_dir, Query::all().hostname(&host.hostname).unit(&stats.unit))
+                .expect("Should be able to query the unit");
+            assert_eq!(stats.entries, entries.len() as u64, "Entry count of {}", stats.unit);
+            assert!(stats.first_seen_utc <= stats.last_seen_utc);
+            assert!(stats.at_or_above(7) <= stats.entries);
+            
+            let newest = entries.iter().map(|entry| entry.timestamp_utc).max();
+            assert_eq!(Some(stats.last_seen_utc), newest);
+        }
+    }
+}
+
+#[test]
+fn test_filtered_discovery() {
+    use journald_query::{
+        discover_services_matching, discover_services_with_options, query_journal, DiscoveryOptions,
+        DiscoveryStrategy, Query, ScanControl,
+    };
+    
+    let test_dir = Path::new(TEST_JOURNAL_DIR);
+    if !test_dir.exists() {
+        eprintln!("Test journal directory not found, skipping filtered discovery tests: {:?}", test_dir);
+        return;
+    }
+    
+    // Without restrictions the filter selects every entry
+    let everything = discover_services_matching(test_dir, Query::all())
+        .expect("Should be able to discover services with a filter");
+    let options = DiscoveryOptions::new().strategy(DiscoveryStrategy::Scan);
+    let expected = discover_services_with_options(test_dir, &options, &mut ScanControl::new())
+        .expect("Should be able to discover services");
+    assert_eq!(everything, expected);
+    
+    // Nothing was logged before the epoch's first second
+    let nothing = discover_services_matching(test_dir, Query::new(0, 1))
+        .expect("Should be able to discover services in an empty range");
+    assert!(nothing.is_empty());
+    
+    // Every unit found for a priority filter has entries at that priority
+    let errors = discover_services_matching(test_dir, Query::all().priority(3))
+        .expect("Should be able to discover services logging errors");
+    for host in &errors.hosts {
+        for unit in &host.units {
+            let entries = query_journal(test_dir, Query::all().hostname(&host.hostname).unit(unit).priority(3))
+                .expect("Should be able to query the unit");
+            assert!(!entries.is_empty(), "{} on {} logged errors", unit, host.hostname);
+        }
+    }
+}
+
+#[test]
+fn test_discover_groups() {
+    use journald_query::{discover_groups, discover_services, query_journal, JournalError, Query};
+    
+    assert_eq!(discover_groups(TEST_JOURNAL_DIR, &[]), Err(JournalError::InvalidArgument));
+    
+    let test_dir = Path::new(TEST_JOURNAL_DIR);
+    if !test_dir.exists() {
+        eprintln!("Test journal directory not found, skipping grouping tests: {:?}", test_dir);
+        return;
+    }
+    
+    // Grouping by host and unit finds what service discovery finds
+    let groups = discover_groups(test_dir, &["_HOSTNAME", "_SYSTEMD_UNIT"])
+        .expect("Should be able to group by host and unit");
+    let services = discover_services(test_dir).expect("Should be able to discover services");
+    assert_eq!(groups.len(), services.len());
+    for (group, host) in groups.iter().zip(&services.hosts) {
+        assert_eq!(group.value, host.hostname);
+        assert_eq!(group.child_values(), host.units.iter().collect::<Vec<_>>());
+        
+        let entries = query_journal(test_dir, Query::all().hostname(&host.hostname))
+            .expect("Should be able to query the host");
+        assert_eq!(group.entries, entries.len() as u64, "Entry count of {}", host.hostname);
+        assert!(group.children.iter().map(|child| child.entries).sum::<u64>() <= group.entries);
+    }
+    
+    // Processes that are not units show up by identifier
+    let identifiers = discover_groups(test_dir, &["_HOSTNAME", "SYSLOG_IDENTIFIER"])
+        .expect("Should be able to group by host and identifier");
+    for group in &identifiers {
+        assert_eq!(group.field, "_HOSTNAME");
+        assert!(group.children.iter().all(|child| child.field == "SYSLOG_IDENTIFIER"));
+    }
+}
+
+#[test]
+fn test_host_identity_and_aliases() {
+    use journald_query::{discover_services, discover_services_with_options, query_journal, DiscoveryOptions, HostAliases, Query, ScanControl};
+    
+    let test_dir = Path::new(TEST_JOURNAL_DIR);
+    if !test_dir.exists() {
+        eprintln!("Test journal directory not found, skipping host alias tests: {:?}", test_dir);
+        return;
+    }
+    
+    let hosts = discover_services(test_dir).expect("Should be able to discover services");
+    for host in &hosts.hosts {
+        // The fixtures carry no machine IDs, so hosts are told apart by hostname
+        assert_eq!(host.machine_id, None);
+        let names: Vec<_> = host.hostnames.iter().map(|seen| seen.hostname.as_str()).collect();
+        assert_eq!(names, vec![host.hostname.as_str()]);
+        assert!(host.hostnames[0].first_seen_utc <= host.hostnames[0].last_seen_utc);
+    }
+    
+    let Some(host) = hosts.hosts.first() else {
+        return;
+    };
+    
+    // A fully qualified name selects the host's entries once domains are stripped
+    let fqdn = format!("{}.example.com", host.hostname);
+    let direct = query_journal(test_dir, Query::all().hostname(&host.hostname)).expect("Should query the host");
+    let aliased = query_journal(test_dir, Query::all().hostname(&fqdn).host_aliases(HostAliases::new().strip_domains(true)))
+        .expect("Should query the host by an alias");
+    assert_eq!(aliased, direct);
+    let unaliased = query_journal(test_dir, Query::all().hostname(&fqdn)).expect("Should query the unknown name");
+    assert!(unaliased.is_empty());
+    
+    // Discovery names hosts by their canonical name
+    let options = DiscoveryOptions::new().host_aliases(HostAliases::new().alias(host.hostname.clone(), "renamed"));
+    let renamed = discover_services_with_options(test_dir, &options, &mut ScanControl::new())
+        .expect("Should be able to discover services with aliases");
+    let found = renamed.find_host("renamed").expect("The host goes by its canonical name");
+    assert_eq!(found.units, host.units);
+    assert!(renamed.find_host(&host.hostname).is_none());
+}
+
+#[test]
+fn test_discovery_watcher() {
+    use journald_query::{discover_services, DiscoveryEvent, DiscoveryWatcher};
+    use std::fs;
+    use std::time::Duration;
+    
+    let multi_host_file = get_test_file_path("multi_host_multi_unit.journal");
+    if !multi_host_file.exists() {
+        eprintln!("Multi-host test file not found, skipping watcher tests: {:?}", multi_host_file);
+        return;
+    }
+    let dir = std::env::temp_dir().join(format!("journald-query-watch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    
+    // Start from an empty journal, then add a file as a new remote host would
+    let mut watcher = DiscoveryWatcher::new(&dir).expect("Should be able to watch an empty journal");
+    assert!(watcher.hosts().is_empty());
+    assert_eq!(watcher.poll().unwrap(), vec![]);
+    
+    fs::copy(&multi_host_file, dir.join("remote.journal.tmp")).unwrap();
+    fs::rename(dir.join("remote.journal.tmp"), dir.join("remote.journal")).unwrap();
+    let events = watcher.poll().expect("Should be able to poll for changes");
+    let expected = discover_services(&dir).expect("Should be able to discover services");
+    
+    let added_hosts: Vec<_> = events.iter().filter_map(|event| match event {
+        DiscoveryEvent::HostAdded { hostname, .. } => Some(hostname),
+        _ => None,
+    }).collect();
+    let added_units = events.iter().filter(|event| matches!(event, DiscoveryEvent::UnitAdded { .. })).count();
+    assert_eq!(added_hosts.len(), expected.len());
+    assert_eq!(added_units, expected.all_units().len());
+    assert_eq!(watcher.hosts().hostnames(), expected.hostnames());
+    assert_eq!(watcher.hosts().all_units(), expected.all_units());
+    assert_eq!(watcher.poll().unwrap(), vec![], "Events are reported once");
+    
+    // Units in the snapshot fall silent after the threshold
+    let mut watcher = DiscoveryWatcher::new(&dir)
+        .expect("Should be able to watch the journal")
+        .silence_threshold(Duration::from_micros(1));
+    std::thread::sleep(Duration::from_millis(1));
+    let silent = watcher.poll().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(silent.len(), expected.all_units().len());
+    assert!(silent.iter().all(|event| matches!(event, DiscoveryEvent::UnitSilent { .. })));
+    assert_eq!(watcher.poll().unwrap(), vec![], "Silence is reported once");
+}
+
+#[test]
+fn test_discovery_checkpoint() {
+    use journald_query::{
+        discover_services_with_options, DiscoveryCheckpoint, DiscoveryOptions, DiscoveryStrategy, JournalError,
+        Query, ScanControl,
+    };
+    use std::fs;
+    
+    let multi_host_file = get_test_file_path("multi_host_multi_unit.journal");
+    let single_host_file = get_test_file_path("single_host_many_units.journal");
+    if !multi_host_file.exists() || !single_host_file.exists() {
+        eprintln!("Test files not found, skipping checkpoint tests");
+        return;
+    }
+    let dir = std::env::temp_dir().join(format!("journald-query-checkpoint-{}", std::process::id()));
+    let journal_dir = dir.join("journal");
+    fs::create_dir_all(&journal_dir).unwrap();
+    fs::copy(&multi_host_file, journal_dir.join("multi.journal")).unwrap();
+    let path = dir.join("discovery.checkpoint");
+    
+    let options = DiscoveryOptions::new().unit_stats(true);
+    let expected = |options: &DiscoveryOptions| {
+        let options = options.clone().strategy(DiscoveryStrategy::Scan);
+        discover_services_with_options(&journal_dir, &options, &mut ScanControl::new()).unwrap()
+    };
+    
+    // The first update reads everything, like a scan
+    let mut checkpoint = DiscoveryCheckpoint::open(&path).expect("Missing checkpoints start empty");
+    assert!(checkpoint.hosts().is_empty());
+    checkpoint.update(&journal_dir, &options).expect("Should be able to update the checkpoint");
+    assert_eq!(checkpoint.hosts(), &expected(&options));
+    assert_eq!(checkpoint.files().count(), 1);
+    checkpoint.save().expect("Should be able to save the checkpoint");
+    
+    // A reopened checkpoint resumes after its cursor
+    let mut resumed = DiscoveryCheckpoint::open(&path).expect("Should be able to reopen the checkpoint");
+    assert_eq!(resumed, checkpoint);
+    assert_eq!(resumed.update(&journal_dir, &options).unwrap(), 0, "Nothing was written since");
+    assert_eq!(resumed.hosts(), checkpoint.hosts());
+    
+    // New files are read, and hosts only found in removed files are dropped
+    fs::copy(&single_host_file, journal_dir.join("single.journal")).unwrap();
+    resumed.update(&journal_dir, &options).unwrap();
+    assert_eq!(resumed.hosts(), &expected(&options));
+    fs::remove_file(journal_dir.join("multi.journal")).unwrap();
+    resumed.update(&journal_dir, &options).unwrap();
+    assert_eq!(resumed.hosts().hostnames(), expected(&options).hostnames());
+    assert_eq!(resumed.files().count(), 1);
+    
+    let filtered = options.clone().filter(Query::all().priority(3));
+    assert_eq!(resumed.update(&journal_dir, &filtered), Err(JournalError::InvalidArgument));
+    
+    fs::write(&path, b"garbage").unwrap();
+    let corrupt = DiscoveryCheckpoint::open(&path);
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(matches!(corrupt, Err(JournalError::InvalidCheckpoint(_))));
+}
+
+#[test]
+fn test_discovery_checkpoint_resumes_at_cursor() {
+    use journald_query::{discover_services_with_options, DiscoveryCheckpoint, DiscoveryOptions, Journal, ScanControl};
+    use std::fs;
+    
+    let Some(journal) = BinaryJournal::build("renamed_host.export") else {
+        eprintln!("systemd-journal-remote not found, skipping checkpoint cursor tests");
+        return;
+    };
+    let test_dir = journal.path();
+    
+    // A cursor identifies its own entry only
+    let journal = Journal::open_directory(test_dir).expect("Should be able to open the journal");
+    journal.seek_head().unwrap();
+    assert!(journal.next().unwrap());
+    let cursor = journal.get_cursor().unwrap();
+    assert!(journal.test_cursor(&cursor).unwrap());
+    assert!(journal.next().unwrap());
+    assert!(!journal.test_cursor(&cursor).unwrap());
+    
+    let dir = std::env::temp_dir().join(format!("journald-query-checkpoint-cursor-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("discovery.checkpoint");
+    let options = DiscoveryOptions::new().unit_stats(true);
+    
+    let mut checkpoint = DiscoveryCheckpoint::open(&path).unwrap();
+    assert_eq!(checkpoint.update(test_dir, &options).unwrap(), 7);
+    checkpoint.save().unwrap();
+    
+    // The entry at the cursor was counted before, so it is not counted again
+    let mut resumed = DiscoveryCheckpoint::open(&path).unwrap();
+    let read = resumed.update(test_dir, &options);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(read.unwrap(), 0);
+    
+    let expected = discover_services_with_options(test_dir, &options, &mut ScanControl::new()).unwrap();
+    assert_eq!(resumed.hosts(), &expected);
+    let app = resumed.hosts().find_machine(&"c".repeat(32)).and_then(|host| host.stats("app.service"));
+    assert_eq!(app.map(|stats| stats.entries), Some(4));
+}
+
+#[test]
+fn test_unit_lifecycle() {
+    use journald_query::{unit_lifecycle, JournalSource, LifecycleKind};
+    use std::time::Duration;
+    
+    let Some(journal) = BinaryJournal::build("error_scenarios.journal") else {
+        eprintln!("systemd-journal-remote not found, skipping lifecycle tests");
+        return;
+    };
+    let test_dir = journal.path();
+    let journal = test_dir.open_journal().expect("Should be able to open the journal");
+    
+    // A restart loop: the main process keeps exiting with status 1
+    let timeline = unit_lifecycle(&journal, "auto-restart.service")
+        .expect("Should be able to read the unit lifecycle")
+        .for_host("error-prone-server");
+    assert_eq!(timeline.unit, "auto-restart.service");
+    assert_eq!(timeline.events.len(), 32);
+    assert!(timeline.events.windows(2).all(|pair| pair[0].timestamp_utc <= pair[1].timestamp_utc));
+    assert_eq!(timeline.events[0].kind, LifecycleKind::Starting);
+    assert_eq!(timeline.restarts().count(), 5);
+    assert_eq!(timeline.restart_span(), Some(Duration::from_secs(4 * 120)));
+    assert_eq!(timeline.last_exit_status(), Some(1));
+    assert_eq!(timeline.last_failure(), Some("exit-code"));
+    assert_eq!(
+        timeline.restarts().last().map(|event| &event.kind),
+        Some(&LifecycleKind::RestartScheduled { restart_count: Some(5) })
+    );
+    assert_eq!(timeline.events.last().map(|event| &event.kind), Some(&LifecycleKind::Started));
+    assert!(timeline.clone().for_host("disk-full-server").events.is_empty());
+    
+    // A unit that failed to start and was not restarted
+    let failing = unit_lifecycle(&journal, "failing.service").expect("Should be able to read the unit lifecycle");
+    assert_eq!(failing.restarts().count(), 0);
+    assert_eq!(failing.restart_span(), None);
+    let kinds: Vec<_> = failing.events.iter().map(|event| &event.kind).collect();
+    assert_eq!(kinds, vec![
+        &LifecycleKind::ProcessExited {
+            command: None,
+            exit_code: Some("exited".to_string()),
+            exit_status: Some(1),
+        },
+        &LifecycleKind::Failed { result: Some("exit-code".to_string()) },
+        &LifecycleKind::StartFailed { job_result: Some("failed".to_string()) },
+    ]);
+    assert_eq!(failing.last_exit_status(), Some(1));
+    assert_eq!(failing.events[2].message, "Failed to start failing.service.");
+    
+    // The unit's own messages and other units are not part of the timeline
+    assert!(unit_lifecycle(&journal, "broken.service").unwrap().events.is_empty());
+}
+
+#[test]
+fn test_health_report() {
+    use journald_query::{health_report, Health, HealthOptions};
+    use std::time::Duration;
+    
+    let Some(journal) = BinaryJournal::build("error_scenarios.journal") else {
+        eprintln!("systemd-journal-remote not found, skipping health report tests");
+        return;
+    };
+    let directory = journal.path();
+    
+    // The day of the error scenarios
+    let start = 1640995200000000;
+    let end = start + 24 * 60 * 60 * 1_000_000;
+    let report = health_report(directory, &HealthOptions::new(start, end)).expect("Should be able to build the report");
+    assert_eq!((report.start_time_utc, report.end_time_utc), (start, end));
+    
+    // A restart loop fails on its restarts, even though it ended up started
+    let unit = report.find("error-prone-server", "auto-restart.service").unwrap();
+    assert_eq!(unit.restarts, 5);
+    assert_eq!(unit.crashes, 5);
+    assert_eq!(unit.coredumps, 0);
+    assert_eq!(unit.last_failure.as_deref(), Some("exit-code"));
+    assert_eq!(unit.health, Health::Failing);
+    assert_eq!(unit.since_last_log, Some(Duration::from_secs(85500)));
+    
+    let unit = report.find("error-prone-server", "failing.service").unwrap();
+    assert_eq!(unit.restarts, 0);
+    assert_eq!(unit.crashes, 1);
+    assert_eq!(unit.last_failure.as_deref(), Some("exit-code"));
+    assert_eq!(unit.health, Health::Failing);
+    
+    let unit = report.find("memory-constrained-server", "memory-monitor.service").unwrap();
+    assert_eq!(unit.errors, 0);
+    assert_eq!(unit.error_rate, 0.0);
+    assert_eq!(unit.health, Health::Healthy);
+    
+    assert_eq!(report.health(), Health::Failing);
+    assert!(!report.unhealthy().is_empty());
+    for (_, unit) in report.unhealthy() {
+        assert_ne!(unit.health, Health::Healthy);
+        assert!(unit.error_rate <= 1.0);
+    }
+    
+    // The JSON holds every unit
+    #[cfg(feature = "serde")]
+    {
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).expect("Report should be valid JSON");
+        let units: usize = report.hosts.iter().map(|host| host.units.len()).sum();
+        let json_units: usize = json["hosts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|host| host["units"].as_array().unwrap().len())
+            .sum();
+        assert_eq!(json_units, units);
+    }
+    
+    // A window after everything was logged: no errors or restarts, only silence
+    let later = HealthOptions::new(end, end + 60 * 60 * 1_000_000).silent_after(Duration::from_secs(60 * 60));
+    let quiet = health_report(directory, &later).expect("Should be able to build the report");
+    let hosts: Vec<_> = quiet.hosts.iter().filter(|host| host.hostname == "error-prone-server").collect();
+    assert!(!hosts.is_empty());
+    for host in hosts {
+        for unit in &host.units {
+            assert_eq!((unit.entries, unit.restarts), (0, 0));
+            if unit.last_log_utc.is_some() {
+                assert_eq!(unit.health, Health::Silent, "{} on {}", unit.unit, host.hostname);
+            }
+        }
+    }
+    
+    assert!(health_report(directory, &HealthOptions::new(end, start)).is_err());
+}
+
+#[test]
+fn test_health_report_shared_hostname() {
+    use journald_query::{health_report, Health, HealthOptions};
+    
+    // Two machines call themselves localhost; each keeps its own units,
+    // restarts and core dumps
+    let Some(journal) = BinaryJournal::build("shared_hostname.export") else {
+        eprintln!("systemd-journal-remote not found, skipping shared hostname health report tests");
+        return;
+    };
+    let directory = journal.path();
+    let start = 1641081600000000;
+    let report = health_report(directory, &HealthOptions::new(start, start + 60 * 60 * 1_000_000))
+        .expect("Should be able to build the report");
+    assert_eq!(report.hosts.len(), 2);
+    
+    let host = |machine_id: &str| {
+        report
+            .hosts
+            .iter()
+            .find(|host| host.machine_id.as_deref() == Some(machine_id))
+            .unwrap()
+    };
+    let units = |machine_id: &str| host(machine_id).units.iter().map(|unit| unit.unit.as_str()).collect::<Vec<_>>();
+    let aaaa = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    let bbbb = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+    assert_eq!(units(aaaa), vec!["nginx.service", "sshd.service"]);
+    assert_eq!(units(bbbb), vec!["postgres.service", "sshd.service"]);
+    
+    let sshd = |machine_id: &str| host(machine_id).units.iter().find(|unit| unit.unit == "sshd.service").unwrap();
+    assert_eq!((sshd(aaaa).restarts, sshd(aaaa).crashes, sshd(aaaa).coredumps), (1, 1, 0));
+    assert_eq!(sshd(aaaa).health, Health::Degraded);
+    assert_eq!((sshd(bbbb).restarts, sshd(bbbb).crashes, sshd(bbbb).coredumps), (0, 0, 1));
+    assert_eq!(sshd(bbbb).health, Health::Failing);
+    assert_eq!(sshd(bbbb).entries, 1);
+}
 
 #[test]
 fn test_tail_config_creation() {